@@ -0,0 +1,368 @@
+// Beam analysis by the direct stiffness method with Euler-Bernoulli elements
+use crate::build::Section;
+use crate::load::{Couple, Load, PointLoad};
+use na::{DMatrix, DVector};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Support {
+    /// Restrains deflection only
+    Pinned,
+    /// Restrains deflection and rotation
+    Fixed,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Beam {
+    pub length: f64,
+    /// Flexural rigidity, EI. Left at zero in a model file to take it from the section.
+    #[serde(default)]
+    pub stiffness: f64,
+    #[serde(default, with = "crate::model::supports")]
+    pub supports: Vec<(f64, Support)>,
+    #[serde(default)]
+    pub loads: Vec<Load>,
+}
+
+/// Internal actions and displacements at a position along the beam
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Station {
+    pub x: f64,
+    pub shear: f64,
+    pub moment: f64,
+    pub slope: f64,
+    pub deflection: f64,
+}
+
+/// Support reaction, force positive upward and moment positive counterclockwise
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Reaction {
+    pub position: f64,
+    pub force: f64,
+    pub moment: f64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Analysis {
+    pub length: f64,
+    pub stations: Vec<Station>,
+    pub reactions: Vec<Reaction>,
+    /// Applied loads together with the reactions, used to evaluate internal actions
+    #[serde(skip)]
+    actions: Vec<Load>,
+}
+
+impl Beam {
+    /// This function instantiates an unsupported, unloaded beam
+    pub fn new(length: f64, stiffness: f64) -> Beam {
+        Beam {
+            length,
+            stiffness,
+            supports: vec![],
+            loads: vec![],
+        }
+    }
+
+    /// This function instantiates a beam bending about the major axis of a section
+    pub fn from_section(section: &Section, length: f64) -> Beam {
+        let props = section.properties();
+        Beam::new(length, props.elastic * props.i11)
+    }
+
+    pub fn add_support(&mut self, position: f64, support: Support) {
+        self.supports.push((position, support));
+    }
+
+    pub fn add_load(&mut self, load: Load) {
+        self.loads.push(load);
+    }
+
+    /// This function analyses the beam, subdividing it into at least `divisions` elements.
+    /// Returns None if the supports do not prevent rigid body motion.
+    pub fn analyze(&self, divisions: usize) -> Option<Analysis> {
+        let fixed = self.supports.iter().any(|s| s.1 == Support::Fixed);
+        let mut restrained: Vec<f64> = self.supports.iter().map(|s| s.0).collect();
+        restrained.sort_unstable_by(|a, b| a.total_cmp(b));
+        restrained.dedup();
+        if !fixed && restrained.len() < 2 {
+            return None;
+        }
+
+        let positions = self.positions(divisions);
+        let count = positions.len();
+        let node = |x: f64| {
+            positions
+                .iter()
+                .position(|p| (p - x).abs() <= 1e-9 * self.length)
+                .unwrap()
+        };
+        // concentrated actions are placed on the station they were merged into, so that the
+        // jumps in shear and moment fall at a station
+        let at = |x: f64| positions[node(x)];
+
+        let mut stiff = DMatrix::<f64>::zeros(2 * count, 2 * count);
+        let mut force = DVector::<f64>::zeros(2 * count);
+        for i in 0..count - 1 {
+            let l = positions[i + 1] - positions[i];
+            let ei = self.stiffness;
+            let k = [
+                [12., 6. * l, -12., 6. * l],
+                [6. * l, 4. * l * l, -6. * l, 2. * l * l],
+                [-12., -6. * l, 12., -6. * l],
+                [6. * l, 2. * l * l, -6. * l, 4. * l * l],
+            ];
+            for r in 0..4 {
+                for c in 0..4 {
+                    stiff[(2 * i + r, 2 * i + c)] += ei / l.powi(3) * k[r][c];
+                }
+            }
+
+            let mid = (positions[i] + positions[i + 1]) / 2.;
+            let q: f64 = self
+                .loads
+                .iter()
+                .map(|load| match load {
+                    Load::Distributed(d) if d.start <= mid && mid <= d.end => d.mag,
+                    _ => 0.,
+                })
+                .sum();
+            force[2 * i] -= q * l / 2.;
+            force[2 * i + 1] -= q * l * l / 12.;
+            force[2 * i + 2] -= q * l / 2.;
+            force[2 * i + 3] += q * l * l / 12.;
+        }
+        for load in &self.loads {
+            match load {
+                Load::Point(p) => force[2 * node(p.position)] -= p.mag,
+                Load::Moment(m) => force[2 * node(m.position) + 1] += m.mag,
+                Load::Distributed(_) => {}
+            }
+        }
+
+        let mut constrained = vec![false; 2 * count];
+        for (position, support) in &self.supports {
+            let n = node(*position);
+            constrained[2 * n] = true;
+            if *support == Support::Fixed {
+                constrained[2 * n + 1] = true;
+            }
+        }
+        let free: Vec<usize> = (0..2 * count).filter(|&d| !constrained[d]).collect();
+        let reduced = DMatrix::from_fn(free.len(), free.len(), |r, c| stiff[(free[r], free[c])]);
+        let rhs = DVector::from_fn(free.len(), |r, _| force[free[r]]);
+        let solved = reduced.lu().solve(&rhs)?;
+        let mut disp = DVector::<f64>::zeros(2 * count);
+        for (r, &d) in free.iter().enumerate() {
+            disp[d] = solved[r];
+        }
+
+        let residual = &stiff * &disp - &force;
+        let mut reactions = vec![];
+        let mut actions: Vec<Load> = self
+            .loads
+            .iter()
+            .map(|&load| match load {
+                Load::Point(p) => Load::Point(PointLoad {
+                    position: at(p.position),
+                    ..p
+                }),
+                Load::Moment(m) => Load::Moment(Couple {
+                    position: at(m.position),
+                    ..m
+                }),
+                Load::Distributed(_) => load,
+            })
+            .collect();
+        for (position, support) in &self.supports {
+            let n = node(*position);
+            let reaction = Reaction {
+                position: *position,
+                force: residual[2 * n],
+                moment: if *support == Support::Fixed {
+                    residual[2 * n + 1]
+                } else {
+                    0.
+                },
+            };
+            actions.push(Load::Point(PointLoad {
+                position: at(reaction.position),
+                mag: -reaction.force,
+            }));
+            actions.push(Load::Moment(Couple {
+                position: at(reaction.position),
+                mag: reaction.moment,
+            }));
+            reactions.push(reaction);
+        }
+
+        let mut analysis = Analysis {
+            length: self.length,
+            stations: vec![],
+            reactions,
+            actions,
+        };
+        for (n, &x) in positions.iter().enumerate() {
+            let concentrated = analysis.actions.iter().any(|load| match load {
+                Load::Point(p) => p.position == x,
+                Load::Moment(m) => m.position == x,
+                Load::Distributed(_) => false,
+            });
+            let sides: &[bool] = if n == 0 {
+                &[true]
+            } else if n == count - 1 {
+                &[false]
+            } else if concentrated {
+                &[false, true]
+            } else {
+                &[true]
+            };
+            for &inclusive in sides {
+                let (shear, moment) = analysis.internal(x, inclusive);
+                analysis.stations.push(Station {
+                    x,
+                    shear,
+                    moment,
+                    slope: disp[2 * n + 1],
+                    deflection: disp[2 * n],
+                });
+            }
+        }
+        Some(analysis)
+    }
+
+    /// This function lists the element boundaries: the ends, supports, load positions and a
+    /// uniform subdivision
+    fn positions(&self, divisions: usize) -> Vec<f64> {
+        let divisions = divisions.max(1);
+        let mut positions: Vec<f64> = (0..=divisions)
+            .map(|i| self.length * i as f64 / divisions as f64)
+            .collect();
+        positions.extend(self.supports.iter().map(|s| s.0));
+        for load in &self.loads {
+            match load {
+                Load::Point(p) => positions.push(p.position),
+                Load::Moment(m) => positions.push(m.position),
+                Load::Distributed(d) => {
+                    positions.push(d.start.max(0.));
+                    positions.push(d.end.min(self.length));
+                }
+            }
+        }
+        positions.sort_unstable_by(|a, b| a.total_cmp(b));
+        positions.dedup_by(|a, b| (*a - *b).abs() <= 1e-9 * self.length);
+        positions
+    }
+}
+
+impl Analysis {
+    /// Shear and sagging moment at `x`, including concentrated actions at `x` if `inclusive`
+    pub fn internal(&self, x: f64, inclusive: bool) -> (f64, f64) {
+        self.actions.iter().fold((0., 0.), |acc, load| {
+            let (v, m) = load.left_of(x, inclusive);
+            (acc.0 + v, acc.1 + m)
+        })
+    }
+
+    pub fn moment_at(&self, x: f64) -> f64 {
+        self.internal(x, true).1
+    }
+
+    pub fn shear_at(&self, x: f64) -> f64 {
+        self.internal(x, true).0
+    }
+
+    /// The station with the largest absolute moment
+    pub fn max_moment(&self) -> Station {
+        self.max_by(|s| s.moment)
+    }
+
+    /// The station with the largest absolute shear
+    pub fn max_shear(&self) -> Station {
+        self.max_by(|s| s.shear)
+    }
+
+    /// The station with the largest absolute deflection
+    pub fn max_deflection(&self) -> Station {
+        self.max_by(|s| s.deflection)
+    }
+
+    /// The station with the largest absolute value, taking a value that is not a number as
+    /// the largest
+    fn max_by(&self, value: impl Fn(&Station) -> f64) -> Station {
+        *self
+            .stations
+            .iter()
+            .max_by(|a, b| value(a).abs().total_cmp(&value(b).abs()))
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load::DistributedLoad;
+
+    #[test]
+    fn simply_supported_under_uniform_load() {
+        let (l, ei, w) = (10., 2e4, 3.);
+        let mut beam = Beam::new(l, ei);
+        beam.add_support(0., Support::Pinned);
+        beam.add_support(l, Support::Pinned);
+        beam.add_load(Load::Distributed(DistributedLoad {
+            start: 0.,
+            end: l,
+            mag: w,
+        }));
+        let analysis = beam.analyze(10).unwrap();
+        let middle = analysis.max_moment();
+        assert!((middle.x - l / 2.).abs() < 1e-9);
+        assert!((middle.moment - w * l * l / 8.).abs() < 1e-9);
+        let deflection = analysis.max_deflection().deflection;
+        assert!((deflection.abs() - 5. * w * l.powi(4) / (384. * ei)).abs() < 1e-9);
+        assert!((analysis.max_shear().shear.abs() - w * l / 2.).abs() < 1e-9);
+        for reaction in &analysis.reactions {
+            assert!((reaction.force - w * l / 2.).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn cantilever_under_point_load() {
+        let (l, ei, p) = (4., 5e3, 2.);
+        let mut beam = Beam::new(l, ei);
+        beam.add_support(0., Support::Fixed);
+        beam.add_load(Load::Point(PointLoad {
+            position: l,
+            mag: p,
+        }));
+        let analysis = beam.analyze(4).unwrap();
+        let tip = analysis.stations.last().unwrap();
+        assert!((tip.deflection.abs() - p * l.powi(3) / (3. * ei)).abs() < 1e-9);
+        assert!((tip.slope.abs() - p * l * l / (2. * ei)).abs() < 1e-9);
+        assert!((analysis.max_moment().moment + p * l).abs() < 1e-9);
+        assert!((analysis.reactions[0].moment.abs() - p * l).abs() < 1e-9);
+    }
+
+    #[test]
+    fn point_load_off_a_subdivision_by_rounding() {
+        // a third of 0.3 is one ulp short of 0.1, where the load is
+        let mut beam = Beam::new(0.3, 1.);
+        beam.add_support(0., Support::Pinned);
+        beam.add_support(0.3, Support::Pinned);
+        beam.add_load(Load::Point(PointLoad {
+            position: 0.1,
+            mag: 6.,
+        }));
+        let analysis = beam.analyze(3).unwrap();
+        let jump: Vec<_> = analysis
+            .stations
+            .iter()
+            .filter(|station| (station.x - 0.1).abs() < 1e-9)
+            .map(|station| station.shear)
+            .collect();
+        assert_eq!(jump.len(), 2);
+        assert!((jump[0] - 4.).abs() < 1e-9);
+        assert!((jump[1] + 2.).abs() < 1e-9);
+        assert!((analysis.max_moment().moment - 0.4).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,215 @@
+// Elastic lateral-torsional buckling of beams
+use crate::beam::Analysis;
+use crate::build::Section;
+use crate::load::Load;
+use crate::properties::Properties;
+use serde::Serialize;
+use std::f64::consts::PI;
+
+/// Effective length factors of an unbraced segment
+#[derive(Clone, Copy, Debug)]
+pub struct Restraint {
+    /// Effective length factor for lateral bending about the minor axis, k
+    pub lateral: f64,
+    /// Effective length factor for warping, kw
+    pub warping: f64,
+}
+
+impl Restraint {
+    /// Ends free to rotate laterally and to warp
+    pub fn pinned() -> Restraint {
+        Restraint {
+            lateral: 1.,
+            warping: 1.,
+        }
+    }
+
+    /// Ends fixed against lateral rotation and warping
+    pub fn fixed() -> Restraint {
+        Restraint {
+            lateral: 0.5,
+            warping: 0.5,
+        }
+    }
+}
+
+/// The result of a lateral-torsional buckling calculation
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct LateralTorsional {
+    /// Unbraced length
+    pub length: f64,
+    /// Moment gradient factor, Cb or C1
+    pub c1: f64,
+    /// Load height factor
+    pub c2: f64,
+    /// Height of the load above the shear centre, positive when destabilising
+    pub load_height: f64,
+    /// Elastic critical moment
+    pub mcr: f64,
+}
+
+/// This function computes the moment gradient factor of the segment between `start` and
+/// `end` from the absolute moments at its quarter points (AISC 360 Eq. F1-1)
+pub fn moment_gradient(analysis: &Analysis, start: f64, end: f64) -> f64 {
+    let at = |fraction: f64| analysis.moment_at(start + fraction * (end - start)).abs();
+    let max = analysis
+        .stations
+        .iter()
+        .filter(|s| s.x >= start && s.x <= end)
+        .map(|s| s.moment.abs())
+        .fold(at(0.).max(at(1.)), f64::max);
+    if max == 0. {
+        return 1.;
+    }
+    12.5 * max / (2.5 * max + 3. * at(0.25) + 4. * at(0.5) + 3. * at(0.75))
+}
+
+/// This function computes the elastic critical moment of the segment between lateral
+/// braces at `start` and `end` using the three factor formula (ENV 1993-1-1 Annex F).
+/// `load_y` is the y coordinate at which transverse loads are applied to the section.
+/// C2 is taken as 0.45 for distributed loading and 0.55 for concentrated loading, and zero
+/// when the segment carries no transverse load.
+pub fn lateral_torsional(
+    section: &Section,
+    analysis: &Analysis,
+    loads: &[Load],
+    start: f64,
+    end: f64,
+    restraint: Restraint,
+    load_y: f64,
+) -> LateralTorsional {
+    let props = section.properties();
+    let length = end - start;
+
+    let within = |x: f64| x > start && x < end;
+    let c2 = if loads.iter().any(|load| match load {
+        Load::Distributed(d) => d.start < end && d.end > start,
+        _ => false,
+    }) {
+        0.45
+    } else if loads.iter().any(|load| match load {
+        Load::Point(p) => within(p.position),
+        _ => false,
+    }) {
+        0.55
+    } else {
+        0.
+    };
+
+    let c1 = moment_gradient(analysis, start, end);
+    let load_height = load_y - props.shear_centre.1;
+    let mcr = critical_moment(
+        &props,
+        section.poisson(),
+        length,
+        restraint,
+        c1,
+        c2,
+        load_height,
+    );
+
+    LateralTorsional {
+        length,
+        c1,
+        c2,
+        load_height,
+        mcr,
+    }
+}
+
+/// This function evaluates the three factor formula for the elastic critical moment
+pub fn critical_moment(
+    props: &Properties,
+    poisson: f64,
+    length: f64,
+    restraint: Restraint,
+    c1: f64,
+    c2: f64,
+    load_height: f64,
+) -> f64 {
+    let elastic = props.elastic;
+    let shear = elastic / (2. * (1. + poisson));
+    let k = restraint.lateral;
+    let kw = restraint.warping;
+    let euler = PI * PI * elastic * props.i22 / (k * length).powi(2);
+    c1 * euler
+        * (((k / kw).powi(2) * props.warping / props.i22
+            + (k * length).powi(2) * shear * props.torsion / (PI * PI * elastic * props.i22)
+            + (c2 * load_height).powi(2))
+        .sqrt()
+            - c2 * load_height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beam::{Beam, Support};
+    use crate::load::{DistributedLoad, PointLoad};
+
+    fn simply_supported(load: Load) -> Analysis {
+        let mut beam = Beam::new(10., 1e4);
+        beam.add_support(0., Support::Pinned);
+        beam.add_support(10., Support::Pinned);
+        beam.add_load(load);
+        beam.analyze(8).unwrap()
+    }
+
+    #[test]
+    fn moment_gradient_of_uniform_load() {
+        let analysis = simply_supported(Load::Distributed(DistributedLoad {
+            start: 0.,
+            end: 10.,
+            mag: 1.,
+        }));
+        // 12.5 / (2.5 + 3 × 0.75 + 4 + 3 × 0.75)
+        let c1 = moment_gradient(&analysis, 0., 10.);
+        assert!((c1 - 12.5 / 11.).abs() < 1e-12);
+        assert!((c1 - 1.136).abs() < 1e-3);
+    }
+
+    #[test]
+    fn moment_gradient_of_central_load() {
+        let analysis = simply_supported(Load::Point(PointLoad {
+            position: 5.,
+            mag: 1.,
+        }));
+        // 12.5 / (2.5 + 3 × 0.5 + 4 + 3 × 0.5)
+        assert!((moment_gradient(&analysis, 0., 10.) - 12.5 / 9.5).abs() < 1e-12);
+        // the half between a support and the load is linear, from zero to the peak
+        assert!((moment_gradient(&analysis, 0., 5.) - 12.5 / 7.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn critical_moment_under_uniform_moment() {
+        let mut section = Section::new(0.3);
+        let n: Vec<_> = [
+            (-4., 6.),
+            (0., 6.),
+            (4., 6.),
+            (-4., -6.),
+            (0., -6.),
+            (4., -6.),
+        ]
+        .iter()
+        .map(|&(x, y)| section.add_node(x, y))
+        .collect();
+        for &(a, b) in &[(0, 1), (1, 2), (3, 4), (4, 5), (1, 4)] {
+            section.add_edge(n[a], n[b], Some(0.5), Some(200.));
+        }
+        let props = section.properties();
+        let length = 100.;
+        let shear = 200. / 2.6;
+        // π/L √(E Iz G J) √(1 + π² E Iw / (L² G J))
+        let expected = PI / length
+            * (200. * props.i22 * shear * props.torsion).sqrt()
+            * (1. + PI * PI * 200. * props.warping / (length * length * shear * props.torsion))
+                .sqrt();
+        let mcr = critical_moment(&props, 0.3, length, Restraint::pinned(), 1., 0., 0.);
+        assert!((mcr - expected).abs() < 1e-9 * expected);
+
+        // a load above the shear centre lowers the moment, one below raises it
+        let above = critical_moment(&props, 0.3, length, Restraint::pinned(), 1., 0.45, 6.);
+        let below = critical_moment(&props, 0.3, length, Restraint::pinned(), 1., 0.45, -6.);
+        assert!(above < mcr && mcr < below);
+    }
+}
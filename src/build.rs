@@ -1,25 +1,42 @@
 // Heavily based on https://crates.io/crates/trussx
-use geo::prelude::{Area, Centroid, MapCoords, Simplify, Translate};
-use geo::{polygon, LineString, MultiPolygon, Point, Polygon};
-use geo_booleanop::boolean::BooleanOp;
+use crate::cache::Cache;
+use crate::clipping::Clipper;
+use crate::concrete::{Rebar, Solid};
+use crate::grid::Grid;
+use crate::history::{Change, History};
+use crate::validate::Diagnostic;
+use geo::MultiPolygon;
 use geojson::{Feature, GeoJson, Geometry as JsonGeometry, Value};
 use petgraph::graph::{EdgeIndex, NodeIndex, UnGraph};
+use petgraph::visit::EdgeRef;
+use serde::{Deserialize, Serialize};
 use std::convert::From;
 use std::f64;
 use std::fmt::{Display, Formatter, Result};
 use std::fs::File;
-use std::io::prelude::*;
+use std::io::{self, prelude::*};
+use std::panic::AssertUnwindSafe;
 use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Node {
     /// The position of the node
-    x: f64,
-    y: f64,
+    pub x: f64,
+    pub y: f64,
+    /// A name for the node, which stays with it as indices change
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Element {
-    thickness: f64,
-    elastic: f64,
+    pub thickness: f64,
+    pub elastic: f64,
+    /// A name for the element, which stays with it as indices change and is shared by the
+    /// pieces it is split into
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
 }
 
 impl Display for Element {
@@ -28,15 +45,40 @@ impl Display for Element {
     }
 }
 pub struct State {
-    second_moment_area: f64,
-    rotation: f64,
+    pub second_moment_area: f64,
+    pub rotation: f64,
 }
+/// A neighbour of a node: angle, node position, neighbour position, thickness and index
+type Neighbour = (f64, [f64; 2], [f64; 2], f64, NodeIndex);
+/// Where a new element meets an existing element
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Crossing {
+    /// The elements cross at a point inside both
+    Point(EdgeIndex, (f64, f64)),
+    /// A node of the existing element lies on the new element
+    Node(NodeIndex),
+    /// A node of the new element lies on the existing element
+    End(EdgeIndex, NodeIndex),
+}
+
 pub struct Section {
-    /// A graph structure containing most of the information about the section
+    /// A graph structure containing most of the information about the section. Edits made
+    /// to it directly are picked up by the next build, but need `reindex` for the checks
+    /// made when drawing.
     pub graph: UnGraph<Node, Element>,
+    /// A solid concrete outline, for reinforced concrete sections
+    pub solid: Option<Solid>,
+    /// Discrete reinforcing bars
+    pub bars: Vec<Rebar>,
     poisson: f64,
-    /// A bool indicating whether or not results are current
-    results: bool,
+    /// Results kept between builds, with whether or not they are current
+    results: Mutex<Cache>,
+    /// A spatial index over the elements, for the checks made when drawing
+    grid: Grid,
+    /// The distance within which new and moved nodes snap to the section, if snapping is on
+    snap: Option<f64>,
+    /// The edit history, for undo and redo
+    pub(crate) history: History,
 }
 
 impl Section {
@@ -44,308 +86,621 @@ impl Section {
     pub fn new(poisson: f64) -> Section {
         Section {
             graph: petgraph::Graph::new_undirected(),
+            solid: None,
+            bars: vec![],
             poisson,
-            results: false,
+            results: Mutex::default(),
+            grid: Grid::new(),
+            snap: None,
+            history: History::default(),
         }
     }
+
+    /// This function turns snapping on, with the given tolerance, or off
+    pub fn set_snap(&mut self, tolerance: Option<f64>) {
+        self.snap = tolerance;
+    }
+
+    /// The snapping tolerance, if snapping is on
+    pub fn snap(&self) -> Option<f64> {
+        self.snap
+    }
+
+    /// This function adds a node. With snapping on, a node within the tolerance of an
+    /// existing node is that node, and one within the tolerance of an element splits the
+    /// element where it lands square to it.
     pub fn add_node(&mut self, x: f64, y: f64) -> NodeIndex {
-        /*         if point2line()
-         */
-        if near(self, x, y) {
-            panic!("No can do, too close");
+        self.history.begin();
+        let node = match self.snap_to(x, y, None) {
+            Some(node) => node,
+            None if near(self, x, y) => panic!("No can do, too close"),
+            None => self.create_node(x, y),
+        };
+        self.history.end("add node");
+        node
+    }
+
+    /// This function snaps a point to the section, leaving out a node being moved, and
+    /// returns the node it lands on, if it is within the snapping tolerance of any
+    fn snap_to(&mut self, x: f64, y: f64, moving: Option<NodeIndex>) -> Option<NodeIndex> {
+        let tolerance = self.snap?;
+        let closest = self
+            .graph
+            .node_indices()
+            .filter(|&node| Some(node) != moving)
+            .map(|node| {
+                let other = &self.graph[node];
+                ((other.x - x).hypot(other.y - y), node)
+            })
+            .filter(|&(distance, _)| distance <= tolerance)
+            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        if let Some((_, node)) = closest {
+            return Some(node);
         }
-        self.graph.add_node(Node { x, y })
+
+        let (found, _) = self
+            .edges_within(
+                (x - tolerance, y - tolerance),
+                (x + tolerance, y + tolerance),
+            )
+            .into_iter()
+            .filter_map(|edge| {
+                let (a, b) = self.graph.edge_endpoints(edge).unwrap();
+                if Some(a) == moving || Some(b) == moving {
+                    return None;
+                }
+                let (a, b) = (&self.graph[a], &self.graph[b]);
+                let distance = point2segment((a.x, a.y), (b.x, b.y), (x, y));
+                if distance <= tolerance {
+                    Some((edge, distance))
+                } else {
+                    None
+                }
+            })
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())?;
+        let (a, b) = self.graph.edge_endpoints(found).unwrap();
+        let (p, q) = (&self.graph[a], &self.graph[b]);
+        let (dx, dy) = (q.x - p.x, q.y - p.y);
+        let t = ((x - p.x) * dx + (y - p.y) * dy) / (dx * dx + dy * dy);
+        let (x, y) = (p.x + t * dx, p.y + t * dy);
+        let node = match moving {
+            Some(node) => {
+                self.place(node, x, y);
+                node
+            }
+            None => self.create_node(x, y),
+        };
+        self.split(a, b, vec![node]);
+        Some(node)
     }
-    /// This function creates a new element to connect two nodes
+
+    /// This function creates a new element to connect two nodes. The element is split
+    /// wherever it crosses or touches an existing element, which is split there too, and
+    /// any stretch lying along an existing element is left to that element. The pieces of
+    /// the new element that were created are returned in order from the first node.
     pub fn add_edge(
         &mut self,
         a: NodeIndex,
         b: NodeIndex,
         thickness: Option<f64>,
         elastic: Option<f64>,
-    ) -> Option<EdgeIndex> {
-        let check = intersects(self, a, b);
-        match check {
-            Some(points) => {
-                let new_node = self.add_node(points.2 .0, points.2 .1);
-                self.add_edge(new_node, points.1 .0, Some(points.0), None);
-                self.add_edge(new_node, points.1 .1, Some(points.0), None)
+    ) -> Vec<EdgeIndex> {
+        if a == b {
+            return vec![];
+        }
+        self.history.begin();
+        let pieces = self.join(
+            a,
+            b,
+            Element {
+                thickness: thickness.unwrap_or(2.),
+                elastic: elastic.unwrap_or(29000.),
+                label: None,
+            },
+        );
+        self.history.end("add edge");
+        pieces
+    }
+
+    /// This function joins two nodes with an element, split as `add_edge` splits it, and
+    /// returns the pieces created
+    fn join(&mut self, a: NodeIndex, b: NodeIndex, element: Element) -> Vec<EdgeIndex> {
+        let mut stops = vec![a, b];
+        let mut splits: Vec<((NodeIndex, NodeIndex), NodeIndex)> = vec![];
+        for crossing in intersects(self, a, b) {
+            match crossing {
+                Crossing::Point(edge, (x, y)) => {
+                    let node = self.create_node(x, y);
+                    stops.push(node);
+                    splits.push((self.graph.edge_endpoints(edge).unwrap(), node));
+                }
+                Crossing::Node(node) => stops.push(node),
+                Crossing::End(edge, node) => {
+                    splits.push((self.graph.edge_endpoints(edge).unwrap(), node))
+                }
             }
-            None => {
-                return Some(self.graph.add_edge(
-                    a,
-                    b,
-                    Element {
-                        thickness: thickness.unwrap_or(2.),
-                        elastic: elastic.unwrap_or(29000.),
-                    },
-                ))
+        }
+
+        // split the crossed elements, keeping their properties
+        splits.sort_by_key(|&((u, v), _)| (u, v));
+        for group in splits.chunk_by(|x, y| x.0 == y.0) {
+            let (u, v) = group[0].0;
+            self.split(u, v, group.iter().map(|&(_, node)| node).collect());
+        }
+
+        stops.sort_by(|&p, &q| {
+            self.distance(a, p)
+                .partial_cmp(&self.distance(a, q))
+                .unwrap()
+        });
+        stops.dedup();
+        let mut pieces = vec![];
+        for pair in stops.windows(2) {
+            if self.graph.find_edge(pair[0], pair[1]).is_none() {
+                pieces.push(self.insert_edge(pair[0], pair[1], element.clone()));
+            }
+        }
+        pieces
+    }
+
+    /// This function splits the element between two nodes at the given nodes, keeping its
+    /// properties. Removing an element moves the last one into its place, so the element
+    /// is found by its ends.
+    fn split(&mut self, u: NodeIndex, v: NodeIndex, mut nodes: Vec<NodeIndex>) {
+        let edge = match self.graph.find_edge(u, v) {
+            Some(edge) => edge,
+            None => return,
+        };
+        nodes.sort_by(|&p, &q| {
+            self.distance(u, p)
+                .partial_cmp(&self.distance(u, q))
+                .unwrap()
+        });
+        nodes.insert(0, u);
+        nodes.push(v);
+        let element = self.remove_edge(edge).unwrap();
+        for pair in nodes.windows(2) {
+            if self.graph.find_edge(pair[0], pair[1]).is_some() {
+                continue;
             }
+            self.insert_edge(pair[0], pair[1], element.clone());
+        }
+    }
+
+    /// This function adds a node and records it in the history
+    fn create_node(&mut self, x: f64, y: f64) -> NodeIndex {
+        let weight = Node { x, y, label: None };
+        let node = self.graph.add_node(weight.clone());
+        self.invalidate();
+        self.history.record(Change::AddNode(node, weight));
+        node
+    }
+
+    /// This function removes a node without elements and records it in the history
+    fn remove_node(&mut self, node: NodeIndex) {
+        if let Some(weight) = self.graph.remove_node(node) {
+            self.invalidate();
+            self.history.record(Change::RemoveNode(node, weight));
+        }
+    }
+
+    /// This function moves a node, refiling its elements, and records it in the history
+    fn place(&mut self, node: NodeIndex, x: f64, y: f64) {
+        let from = (self.graph[node].x, self.graph[node].y);
+        self.graph[node].x = x;
+        self.graph[node].y = y;
+        self.refile(node);
+        self.invalidate();
+        self.history.record(Change::MoveNode(node, from, (x, y)));
+    }
+
+    /// This function adds an element and records it in the history
+    fn insert_edge(&mut self, a: NodeIndex, b: NodeIndex, element: Element) -> EdgeIndex {
+        let edge = self.attach(a, b, element.clone());
+        self.history.record(Change::AddEdge(edge, a, b, element));
+        edge
+    }
+
+    /// This function removes an element and records it in the history
+    fn remove_edge(&mut self, edge: EdgeIndex) -> Option<Element> {
+        let (a, b) = self.graph.edge_endpoints(edge)?;
+        let element = self.detach(edge)?;
+        self.history
+            .record(Change::RemoveEdge(edge, a, b, element.clone()));
+        Some(element)
+    }
+
+    /// This function adds an element to the graph and the spatial index
+    pub(crate) fn attach(&mut self, a: NodeIndex, b: NodeIndex, element: Element) -> EdgeIndex {
+        let edge = self.graph.add_edge(a, b, element);
+        self.file(edge);
+        self.invalidate();
+        edge
+    }
+
+    /// This function removes an element from the graph and the spatial index. The graph
+    /// moves its last element into the place of the removed one, and so does the index.
+    pub(crate) fn detach(&mut self, edge: EdgeIndex) -> Option<Element> {
+        let last = EdgeIndex::new(self.graph.edge_count().checked_sub(1)?);
+        let element = self.graph.remove_edge(edge)?;
+        self.invalidate();
+        self.grid.remove(edge);
+        if last != edge {
+            self.grid.rename(last, edge);
         }
-        /*         if !intersects(self, a, b) {
-            Some(self.graph.add_edge(
-                a,
-                b,
-                Element {
-                    thickness: thickness.unwrap_or(0.1),
-                    elastic: elastic.unwrap_or(29000.),
-                },
-            ))
+        Some(element)
+    }
+
+    /// This function files the elements of a node in the spatial index again
+    pub(crate) fn refile(&mut self, node: NodeIndex) {
+        let edges: Vec<EdgeIndex> = self.graph.edges(node).map(|edge| edge.id()).collect();
+        for edge in edges {
+            self.file(edge);
+        }
+    }
+
+    /// This function files an element in the spatial index, padded by the reach of `near`
+    fn file(&mut self, edge: EdgeIndex) {
+        let (a, b) = self.graph.edge_endpoints(edge).unwrap();
+        let (a, b) = (&self.graph[a], &self.graph[b]);
+        let pad = 2. * self.graph[edge].thickness;
+        self.grid.insert(edge, (a.x, a.y), (b.x, b.y), pad);
+    }
+
+    /// This function rebuilds the spatial index, which is needed after the graph is edited
+    /// directly rather than through the methods of the section. The edit history is
+    /// cleared, as it cannot take back direct edits.
+    pub fn reindex(&mut self) {
+        self.history = History::default();
+        self.invalidate();
+        let extents: f64 = self
+            .graph
+            .edge_indices()
+            .map(|edge| {
+                let (a, b) = self.graph.edge_endpoints(edge).unwrap();
+                self.distance(a, b) + 4. * self.graph[edge].thickness
+            })
+            .sum();
+        let count = self.graph.edge_count();
+        self.grid = if count > 0 && extents > 0. {
+            Grid::with_cell(extents / count as f64)
         } else {
-            let one = (
-                self.graph.node_weight(a).unwrap().x,
-                self.graph.node_weight(a).unwrap().y,
-            );
+            Grid::new()
+        };
+        for edge in self.graph.edge_indices() {
+            self.file(edge);
+        }
+    }
 
-            let intersect = calc_intersect(
-                one: (f32, f32),
-                two: (f32, f32),
-                thr: (f32, f32),
-                fou: (f32, f32),
-            );
-            panic!("Don't cross the streams.");
-        } */
+    /// The elements that may lie within the box between two corners, or within the reach of
+    /// `near` of it. Every element is returned if the spatial index is out of date.
+    pub fn edges_within(&self, min: (f64, f64), max: (f64, f64)) -> Vec<EdgeIndex> {
+        if self.grid.len() == self.graph.edge_count() {
+            self.grid.query(min, max)
+        } else {
+            self.graph.edge_indices().collect()
+        }
+    }
+
+    /// The distance between two nodes
+    fn distance(&self, a: NodeIndex, b: NodeIndex) -> f64 {
+        let (a, b) = (&self.graph[a], &self.graph[b]);
+        ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt()
+    }
+
+    /// Poisson's ratio of the section material
+    pub fn poisson(&self) -> f64 {
+        self.poisson
+    }
+
+    /// Whether or not the built outline and properties are current
+    pub fn is_current(&self) -> bool {
+        self.cache().is_current()
+    }
+
+    /// The results kept between builds. A build that panicked leaves them to be rebuilt, as
+    /// does an edit made to the graph directly.
+    pub(crate) fn cache(&self) -> MutexGuard<'_, Cache> {
+        let mut cache = self
+            .results
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        cache.check(self);
+        cache
     }
 
-    /// This function moves a node
-    pub fn move_node(&mut self, a: NodeIndex, x: f64, y: f64) {
-        let node = self.graph.node_weight_mut(a);
+    /// This function marks the results as out of date after a change to the graph
+    pub(crate) fn invalidate(&mut self) {
+        match self.results.get_mut() {
+            Ok(cache) => cache.invalidate(),
+            Err(poisoned) => poisoned.into_inner().invalidate(),
+        }
+    }
 
-        match node {
-            None => {
-                panic!("This node does not exist.");
+    /// This function moves a node. With snapping on, a node moved within the tolerance of
+    /// another node is merged into it, and one moved within the tolerance of an element
+    /// splits the element where it lands square to it. The elements of the node are then
+    /// split wherever they cross or touch other elements, as `add_edge` splits a new element.
+    /// The index of the node after the move is returned, as merging removes a node and the
+    /// graph moves its last node into its place.
+    pub fn move_node(&mut self, a: NodeIndex, x: f64, y: f64) -> NodeIndex {
+        if self.graph.node_weight(a).is_none() {
+            panic!("This node does not exist.");
+        }
+        self.history.begin();
+        self.place(a, x, y);
+        let node = match self.snap_to(x, y, Some(a)) {
+            Some(node) if node != a => self.merge(a, node),
+            _ => a,
+        };
+        let neighbours: Vec<NodeIndex> = self.graph.neighbors(node).collect();
+        for other in neighbours {
+            if intersects(self, node, other).is_empty() {
+                continue;
             }
-            Some(node) => {
-                /*                 if near(Self, x, y) {
-                    panic!("No can do, too close");
-                } */
-                node.x = x;
-                node.y = y;
+            if let Some(edge) = self.graph.find_edge(node, other) {
+                let element = self.remove_edge(edge).unwrap();
+                self.join(node, other, element);
+            }
+        }
+        self.history.end("move node");
+        node
+    }
+
+    /// This function merges a node into another, joining its neighbours to the other node,
+    /// and returns the index of the other node after the merge
+    fn merge(&mut self, a: NodeIndex, into: NodeIndex) -> NodeIndex {
+        let links: Vec<(NodeIndex, Element)> = self
+            .graph
+            .edges(a)
+            .map(|edge| {
+                let other = if edge.source() == a {
+                    edge.target()
+                } else {
+                    edge.source()
+                };
+                (other, edge.weight().clone())
+            })
+            .collect();
+        let label = self.graph[a].label.clone();
+        self.delete_node(a);
+        let last = self.graph.node_count();
+        let moved = |node: NodeIndex| if node.index() == last { a } else { node };
+        let into = moved(into);
+        if self.graph[into].label.is_none() && label.is_some() {
+            self.label_node(into, label.as_deref());
+        }
+        for (other, element) in links {
+            let other = moved(other);
+            if other != into && self.graph.find_edge(other, into).is_none() {
+                self.insert_edge(other, into, element);
             }
         }
+        into
     }
 
     /// This function deletes a node
     pub fn delete_node(&mut self, a: NodeIndex) {
-        self.graph.remove_node(a);
+        self.history.begin();
+        while let Some(edge) = self.graph.edges(a).next().map(|edge| edge.id()) {
+            self.remove_edge(edge);
+        }
+        self.remove_node(a);
+        self.history.end("delete node");
     }
 
     /// This function deletes a member
     pub fn delete_member(&mut self, ab: EdgeIndex) {
-        self.graph.remove_edge(ab);
+        self.history.begin();
+        self.remove_edge(ab);
+        self.history.end("delete member");
     }
 
-    pub fn build(&self) {
-        /*         let point_holder = vec![];
-         */
-        let mut clusters: Vec<Vec<(f64, f64)>> = self
-            .graph
+    /// This function names a node, or clears its name, and returns whether it did. Names
+    /// are unique, so a name another node already has is refused. A node merged into
+    /// another by snapping passes its name on to the other node if that has none.
+    pub fn label_node(&mut self, node: NodeIndex, label: Option<&str>) -> bool {
+        if let Some(label) = label {
+            if self.node_by_label(label).is_some_and(|other| other != node) {
+                return false;
+            }
+        }
+        let label = label.map(String::from);
+        let old = std::mem::replace(&mut self.graph[node].label, label.clone());
+        self.history.begin();
+        self.history.record(Change::LabelNode(node, old, label));
+        self.history.end("label node");
+        true
+    }
+
+    /// This function names an element, or clears its name
+    pub fn label_edge(&mut self, edge: EdgeIndex, label: Option<&str>) {
+        let label = label.map(String::from);
+        let old = std::mem::replace(&mut self.graph[edge].label, label.clone());
+        self.history.begin();
+        self.history.record(Change::LabelEdge(edge, old, label));
+        self.history.end("label edge");
+    }
+
+    /// The current index of the node with the given name, if there is one
+    pub fn node_by_label(&self, label: &str) -> Option<NodeIndex> {
+        self.graph
             .node_indices()
-            .map(|node| {
-                let niter = self.graph.neighbors(node);
-                let mut neiter: Vec<(f64, [f64; 2], [f64; 2], f64, NodeIndex)> = niter
-                    .map(|neigh| {
-                        let x1 = self.graph.node_weight(node).unwrap().x;
-                        let y1 = self.graph.node_weight(node).unwrap().y;
-                        let x2 = self.graph.node_weight(neigh).unwrap().x;
-                        let y2 = self.graph.node_weight(neigh).unwrap().y;
-                        let angle = (y2 - y1).atan2(x2 - x1);
-                        (
-                            angle,
-                            [x1, y1],
-                            [x2, y2],
-                            self.graph
-                                .edge_weight(self.graph.find_edge(node, neigh).unwrap())
-                                .unwrap()
-                                .thickness,
-                            neigh,
-                        )
-                    })
-                    .collect();
-                neiter.sort_unstable_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
-                /*                 neiter.drain(|b:f32| &b.0);
-                 */
-                neiter.dedup_by(|a, b| {
-                    // if collinear
-                    if a.0.rem_euclid(f64::consts::PI)
-                        == b.0.rem_euclid(f64::consts::PI) - f64::consts::PI / 2.
-                        || a.0.rem_euclid(f64::consts::PI)
-                            == b.0.rem_euclid(f64::consts::PI) + f64::consts::PI / 2.
-                    {
-                        // make sure thicknesses are equal
-                        assert!(a.3.eq(&b.3));
-                        // continue with dedup
-                        a.0.eq(&b.0)
-                    } else {
-                        a.0.eq(&b.0)
-                    }
-                });
-
-                neiter.append(&mut vec![neiter[0]]);
-
-                let mut points = vec![];
-                /*                 println!("{:?}", neiter);
-                 */
-                for count in 0..(neiter.len() - 1) {
-                    let edge = self.graph.find_edge(node, neiter[count].4).unwrap();
-                    let t = neiter[count].3 / 2.;
-                    let t2 = neiter[count + 1].3 / 2.;
-
-                    let x1 = neiter[count].1[0];
-                    let y1 = neiter[count].1[1];
-                    let x2 = neiter[count].2[0];
-                    let y2 = neiter[count].2[1];
-                    let x3 = neiter[count + 1].2[0];
-                    let y3 = neiter[count + 1].2[1];
-
-                    let midx = (x1 + x2) / 2.;
-                    let midy = (y1 + y2) / 2.;
-                    let normal1 = normalize(x1, y1, x2, y2);
-
-                    let normal2 = [-normal1[0], -normal1[1]];
-                    let normal3 = normalize(x3, y3, x1, y1);
-
-                    let nei2nod_l = (
-                        midx + (-normal2[1] - normal2[0]) * t,
-                        midy + (normal2[0] - normal2[1]) * t,
-                    );
+            .find(|&node| self.graph[node].label.as_deref() == Some(label))
+    }
 
-                    let nei2nod_r = (
-                        midx + (normal2[1] - normal2[0]) * t,
-                        midy + (-normal2[0] - normal2[1]) * t,
-                    );
+    /// The current indices of the elements with the given name, which are the pieces of an
+    /// element split since it was named
+    pub fn edges_by_label(&self, label: &str) -> Vec<EdgeIndex> {
+        self.graph
+            .edge_indices()
+            .filter(|&edge| self.graph[edge].label.as_deref() == Some(label))
+            .collect()
+    }
 
-                    let nod2nei_l = (
-                        x1 + (-normal1[1] - normal1[0]) * t,
-                        y1 + (normal1[0] - normal1[1]) * t,
-                    );
-                    let nod2nei_r = (
-                        x1 + (normal1[1] - normal1[0]) * t,
-                        y1 + (-normal1[0] - normal1[1]) * t,
-                    );
-                    let nod2nex_r = (
-                        x1 + (-normal3[1] + normal3[0]) * t2,
-                        y1 + (normal3[0] + normal3[1]) * t2,
-                    );
-                    let nex2nod_l = (
-                        x3 + (-normal3[1] - normal3[0]) * t2,
-                        y3 + (normal3[0] - normal3[1]) * t2,
-                    );
+    /// This function offsets the plates about their centrelines and merges them into the
+    /// outline of the section
+    pub fn build(&self) -> MultiPolygon<f64> {
+        self.build_with(Clipper::BooleanOp)
+    }
 
-                    let lone_l = (
-                        x2 + (-normal1[1] - normal1[0]) * t,
-                        y2 + (normal1[0] - normal1[1]) * t,
-                    );
-                    let lone_r = (
-                        x2 + (normal2[1] - normal2[0]) * t,
-                        y2 + (-normal2[0] - normal2[1]) * t,
-                    );
-                    let one = nod2nei_l;
-                    let two = nei2nod_r;
-                    let thr = nod2nex_r;
-                    let fou = nex2nod_l;
-
-                    /*                     if !intersects2([one, two, thr, fou]) {
-                        println!("Lines don't intersect");
-                    } */
-                    let denom =
-                        (one.0 - two.0) * (thr.1 - fou.1) - (one.1 - two.1) * (thr.0 - fou.0);
-                    let part1 = one.0 * two.1 - one.1 * two.0;
-                    let part2 = thr.0 * fou.1 - thr.1 * fou.0;
-                    if self.graph.neighbors(node).count() > 1 {
-                        if (nex2nod_l.1 - nod2nex_r.1) / (nex2nod_l.0 - nod2nex_r.0)
-                            != (nod2nei_l.1 - nei2nod_r.1) / (nod2nei_l.0 - nei2nod_r.0)
-                        {
-                            let jp = (
-                                (part1 * (thr.0 - fou.0) - (one.0 - two.0) * part2) / denom,
-                                (part1 * (thr.1 - fou.1) - (one.1 - two.1) * part2) / denom,
-                            );
-                            points.append(&mut vec![nei2nod_l, nei2nod_r, jp]);
-                        } else {
-                            points.append(&mut vec![nei2nod_l, nei2nod_r]);
-                        }
-                    } else if self.graph.node_count() < 3 {
-                        points.append(&mut vec![nod2nei_l, nod2nei_r, lone_l, lone_r]);
-                    } else {
-                        points.append(&mut vec![nei2nod_l, nei2nod_r, nod2nei_l, nod2nei_r]);
-                    }
-                }
-                println!("{:?}", points);
-                points
-                /*                     Polygon::new(LineString::from(points) , vec![])
-                 */
+    /// This function builds the outline of the section if `validate` finds nothing that
+    /// spoils it, and otherwise returns everything `validate` finds. The plates are merged
+    /// with the overlay clipper, as geo-booleanop panics on some valid sections, and a merge
+    /// that fails all the same is reported as `Diagnostic::Unmerged`.
+    pub fn try_build(&self) -> std::result::Result<MultiPolygon<f64>, Vec<Diagnostic>> {
+        let diagnostics = self.validate();
+        if diagnostics.iter().any(Diagnostic::spoils) {
+            return Err(diagnostics);
+        }
+        std::panic::catch_unwind(AssertUnwindSafe(|| self.build_with(Clipper::Overlay)))
+            .map_err(|_| vec![Diagnostic::Unmerged])
+    }
+
+    /// This function builds the outline of the section, merging the plates with the given
+    /// polygon clipper
+    pub fn build_with(&self, clipper: Clipper) -> MultiPolygon<f64> {
+        self.cache().outline(self, clipper)
+    }
+
+    /// This function offsets the plates meeting at a node about their centrelines, giving
+    /// the corners of the part of the outline at the node. A node without plates, or with
+    /// only plates of no length, has no part.
+    pub(crate) fn cluster(&self, node: NodeIndex) -> Vec<(f64, f64)> {
+        let niter = self.graph.neighbors(node);
+        let mut neiter: Vec<Neighbour> = niter
+            .filter(|&neigh| self.distance(node, neigh) > 0.)
+            .map(|neigh| {
+                let x1 = self.graph.node_weight(node).unwrap().x;
+                let y1 = self.graph.node_weight(node).unwrap().y;
+                let x2 = self.graph.node_weight(neigh).unwrap().x;
+                let y2 = self.graph.node_weight(neigh).unwrap().y;
+                let angle = (y2 - y1).atan2(x2 - x1);
+                (
+                    angle,
+                    [x1, y1],
+                    [x2, y2],
+                    self.graph
+                        .edge_weight(self.graph.find_edge(node, neigh).unwrap())
+                        .unwrap()
+                        .thickness,
+                    neigh,
+                )
             })
-            .collect::<Vec<_>>();
-        clusters.sort_unstable_by(|a, b| a.len().partial_cmp(&b.len()).unwrap().reverse());
-        /*          clusters.sort_unstable_by(|a, b| a.exterior().into_points().len().partial_cmp(&b.exterior().into_points().len()).unwrap());
-         */
-        println!("hey{:?}", clusters);
-
-        let mut unionized: MultiPolygon<f64> = MultiPolygon(vec![]);
-
-        // merge clusters into single polygon
-        let mut cccount = 0;
-        while cccount < 1 {
-            /*             let shape = Polygon::new(LineString::from(clusters[cccount]), vec![]);
-             */
-            let shape = Polygon::new(LineString::from(clusters[cccount].clone()), vec![]);
-            unionized = shape.union(&unionized);
-            cccount += 1;
-        }
-        cccount += 1;
-        while cccount < 6 {
-            /*             let shape = Polygon::new(LineString::from(clusters[cccount]), vec![]);
-             */
-            let shape = Polygon::new(LineString::from(clusters[cccount].clone()), vec![]);
-            unionized = shape.union(&unionized);
-            cccount += 1;
-        }
-        /*         for poly in clusters {
-            unionized = poly.union(&unionized).simplify(&0.01);
-        } */
-
-        /*         unionized = unionized.simplify(&0.01);
-         */
-        let section_poly = unionized.0[0].clone();
-        // translate the cross-section to place the centroid at the origin
-        let translated = section_poly.translate(
-            -section_poly.centroid().unwrap().x(),
-            -section_poly.centroid().unwrap().y(),
-        );
+            .collect();
+        neiter.sort_unstable_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        neiter.dedup_by(|a, b| a.0.eq(&b.0));
+        if neiter.is_empty() {
+            return vec![];
+        }
 
-        let geo_points = translated.into_inner().0.into_points();
-        let perimeter_points: Vec<(f64, f64)> =
-            geo_points.iter().map(|point| point.x_y()).collect();
+        neiter.append(&mut vec![neiter[0]]);
 
-        /*            let area = translated.unsigned_area();
-         */
-        println!("{:#?}", section_poly);
+        let mut points = vec![];
+        for count in 0..(neiter.len() - 1) {
+            let t = neiter[count].3 / 2.;
+            let t2 = neiter[count + 1].3 / 2.;
 
-        /*         let test = clusters[4].clone();
-         */
-        let geojson_polygon: JsonGeometry = JsonGeometry::new(Value::from(&unionized));
+            let x1 = neiter[count].1[0];
+            let y1 = neiter[count].1[1];
+            let x2 = neiter[count].2[0];
+            let y2 = neiter[count].2[1];
+            let x3 = neiter[count + 1].2[0];
+            let y3 = neiter[count + 1].2[1];
 
-        let geojson = GeoJson::Feature(Feature {
-            bbox: None,
-            geometry: { Some(geojson_polygon) },
-            id: None,
-            properties: None,
-            foreign_members: None,
-        });
-        let geojson_string = geojson.to_string();
-        /*                     println!("{}", geojson_string); */
-        let path = Path::new("hello.geojson");
-        let display = path.display();
-        let mut file = match File::create(&path) {
-            Err(why) => panic!("couldn't create {}: {}", display, why),
-            Ok(file) => file,
-        };
-        match file.write_all(geojson_string.as_bytes()) {
-            Err(why) => panic!("couldn't write to {}: {}", display, why),
-            Ok(_) => println!("successfully wrote to {}", display),
+            let midx = (x1 + x2) / 2.;
+            let midy = (y1 + y2) / 2.;
+            let normal1 = normalize(x1, y1, x2, y2);
+
+            let normal2 = [-normal1[0], -normal1[1]];
+            let normal3 = normalize(x3, y3, x1, y1);
+
+            let nei2nod_l = (
+                midx + (-normal2[1] - normal2[0]) * t,
+                midy + (normal2[0] - normal2[1]) * t,
+            );
+
+            let nei2nod_r = (
+                midx + (normal2[1] - normal2[0]) * t,
+                midy + (-normal2[0] - normal2[1]) * t,
+            );
+
+            let nod2nei_l = (
+                x1 + (-normal1[1] - normal1[0]) * t,
+                y1 + (normal1[0] - normal1[1]) * t,
+            );
+            let nod2nei_r = (
+                x1 + (normal1[1] - normal1[0]) * t,
+                y1 + (-normal1[0] - normal1[1]) * t,
+            );
+            let nod2nex_r = (
+                x1 + (-normal3[1] + normal3[0]) * t2,
+                y1 + (normal3[0] + normal3[1]) * t2,
+            );
+            let nex2nod_l = (
+                x3 + (-normal3[1] - normal3[0]) * t2,
+                y3 + (normal3[0] - normal3[1]) * t2,
+            );
+
+            let lone_l = (
+                x2 + (-normal1[1] - normal1[0]) * t,
+                y2 + (normal1[0] - normal1[1]) * t,
+            );
+            let lone_r = (
+                x2 + (normal2[1] - normal2[0]) * t,
+                y2 + (-normal2[0] - normal2[1]) * t,
+            );
+            let one = nod2nei_l;
+            let two = nei2nod_r;
+            let thr = nod2nex_r;
+            let fou = nex2nod_l;
+
+            let denom = (one.0 - two.0) * (thr.1 - fou.1) - (one.1 - two.1) * (thr.0 - fou.0);
+            let part1 = one.0 * two.1 - one.1 * two.0;
+            let part2 = thr.0 * fou.1 - thr.1 * fou.0;
+            if neiter.len() > 2 {
+                if in_line(neiter[count].0, neiter[count + 1].0) && t != t2 {
+                    // plates in line with different thicknesses step square at the node
+                    let step = |t: f64| (x1 - normal1[1] * t, y1 + normal1[0] * t);
+                    points.append(&mut vec![nei2nod_l, nei2nod_r, step(t), step(t2)]);
+                } else if (nex2nod_l.1 - nod2nex_r.1) / (nex2nod_l.0 - nod2nex_r.0)
+                    != (nod2nei_l.1 - nei2nod_r.1) / (nod2nei_l.0 - nei2nod_r.0)
+                {
+                    let jp = (
+                        (part1 * (thr.0 - fou.0) - (one.0 - two.0) * part2) / denom,
+                        (part1 * (thr.1 - fou.1) - (one.1 - two.1) * part2) / denom,
+                    );
+                    points.append(&mut vec![nei2nod_l, nei2nod_r, jp]);
+                } else {
+                    points.append(&mut vec![nei2nod_l, nei2nod_r]);
+                }
+            } else if self.graph.node_count() < 3 {
+                points.append(&mut vec![nod2nei_l, nod2nei_r, lone_l, lone_r]);
+            } else {
+                points.append(&mut vec![nei2nod_l, nei2nod_r, nod2nei_l, nod2nei_r]);
+            }
         }
+        points
     }
 }
+
+/// This function writes an outline to a GeoJSON file
+pub fn write_geojson(outline: &MultiPolygon<f64>, path: &Path) -> io::Result<()> {
+    let geojson_polygon: JsonGeometry = JsonGeometry::new(Value::from(outline));
+
+    let geojson = GeoJson::Feature(Feature {
+        bbox: None,
+        geometry: { Some(geojson_polygon) },
+        id: None,
+        properties: None,
+        foreign_members: None,
+    });
+    let geojson_string = geojson.to_string();
+    File::create(path)?.write_all(geojson_string.as_bytes())?;
+    println!("successfully wrote to {}", path.display());
+    Ok(())
+}
+
 pub fn normalize(x0: f64, y0: f64, x1: f64, y1: f64) -> [f64; 2] {
     [
         (x1 - x0) / ((x1 - x0) * (x1 - x0) + (y1 - y0) * (y1 - y0)).sqrt(),
@@ -353,124 +708,357 @@ pub fn normalize(x0: f64, y0: f64, x1: f64, y1: f64) -> [f64; 2] {
     ]
 }
 
-pub fn intersects(
-    section: &Section,
-    node1: NodeIndex,
-    node2: NodeIndex,
-) -> Option<(f64, (NodeIndex, NodeIndex), (f64, f64))> {
-    let (mut x3, mut x4, mut y3, mut y4): (f64, f64, f64, f64);
-    let x1 = section.graph.node_weight(node1).unwrap().x;
-    let y1 = section.graph.node_weight(node1).unwrap().y;
-    let x2 = section.graph.node_weight(node2).unwrap().x;
-    let y2 = section.graph.node_weight(node2).unwrap().y;
-    for edge in section.graph.edge_indices() {
-        match section.graph.edge_endpoints(edge) {
-            Some(nodes) => {
-                x3 = section.graph.node_weight(nodes.0).unwrap().x;
-                y3 = section.graph.node_weight(nodes.0).unwrap().y;
-                x4 = section.graph.node_weight(nodes.1).unwrap().x;
-                y4 = section.graph.node_weight(nodes.1).unwrap().y;
-                let test1 = ((x1 - x3) * (y3 - y4) - (y1 - y3) * (x3 - x4))
-                    / ((x1 - x2) * (y3 - y4) - (y1 - y2) * (x3 - x4));
-                let test2 = ((x2 - x1) * (y1 - y3) - (y2 - y1) * (x1 - x3))
-                    / ((x1 - x2) * (y3 - y4) - (y1 - y2) * (x3 - x4));
-                if test1 >= 0.
-                    && test1 <= 1.
-                    && test2 >= 0.
-                    && test2 <= 1.
-                    && (x1, y1) != (x3, y3)
-                    && (x1, y1) != (x4, y4)
-                    && (x2, y2) != (x3, y3)
-                    && (x2, y2) != (x4, y4)
-                {
-                    let denom = (x1 - x2) * (y3 - y4) - (x1 - y2) * (x3 - x4);
-                    let part1 = x1 * y2 - x1 * x2;
-                    let part2 = x3 * y4 - y3 * x4;
-                    return Some((
-                        section.graph.edge_weight(edge).unwrap().thickness,
-                        nodes,
-                        (
-                            (part1 * (x3 - x4) - (x1 - x2) * part2) / denom,
-                            (part1 * (y3 - y4) - (x1 - y2) * part2) / denom,
-                        ),
-                    ));
+/// This function finds where a new element between two nodes would meet the existing
+/// elements, other than at the nodes they share. Points closer than a billionth of the
+/// longer element are taken as one, so that elements lying along each other meet at the
+/// ends of their common stretch.
+pub fn intersects(section: &Section, node1: NodeIndex, node2: NodeIndex) -> Vec<Crossing> {
+    let point = |n: NodeIndex| (section.graph[n].x, section.graph[n].y);
+    let (p1, p2) = (point(node1), point(node2));
+    let mut crossings = vec![];
+    let min = (p1.0.min(p2.0), p1.1.min(p2.1));
+    let max = (p1.0.max(p2.0), p1.1.max(p2.1));
+    for edge in section.edges_within(min, max) {
+        let (node3, node4) = section.graph.edge_endpoints(edge).unwrap();
+        let (p3, p4) = (point(node3), point(node4));
+        let tolerance = 1e-9 * distance(p1, p2).max(distance(p3, p4));
+
+        // nodes of one element lying on the other, which covers elements lying along each
+        // other and T-junctions
+        let mut touching = false;
+        for &node in &[node3, node4] {
+            if node != node1 && node != node2 && on_segment(p1, p2, point(node), tolerance) {
+                if !crossings.contains(&Crossing::Node(node)) {
+                    crossings.push(Crossing::Node(node));
                 }
+                touching = true;
             }
-            None => {
-                println!("No edges.");
+        }
+        for &node in &[node1, node2] {
+            if node != node3 && node != node4 && on_segment(p3, p4, point(node), tolerance) {
+                crossings.push(Crossing::End(edge, node));
+                touching = true;
             }
         }
+        if touching {
+            continue;
+        }
+
+        // otherwise a crossing inside both elements
+        let r = (p2.0 - p1.0, p2.1 - p1.1);
+        let s = (p4.0 - p3.0, p4.1 - p3.1);
+        let denom = r.0 * s.1 - r.1 * s.0;
+        if denom == 0. {
+            continue;
+        }
+        let q = (p3.0 - p1.0, p3.1 - p1.1);
+        let t = (q.0 * s.1 - q.1 * s.0) / denom;
+        let u = (q.0 * r.1 - q.1 * r.0) / denom;
+        let inside = |t: f64, length: f64| t * length > tolerance && (1. - t) * length > tolerance;
+        if inside(t, distance(p1, p2)) && inside(u, distance(p3, p4)) {
+            crossings.push(Crossing::Point(edge, (p1.0 + t * r.0, p1.1 + t * r.1)));
+        }
     }
-    None
+    crossings
 }
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt()
+}
+
+/// Whether a point lies on a segment, short of its ends
+fn on_segment(end1: (f64, f64), end2: (f64, f64), point: (f64, f64), tolerance: f64) -> bool {
+    let length = distance(end1, end2);
+    let along =
+        ((point.0 - end1.0) * (end2.0 - end1.0) + (point.1 - end1.1) * (end2.1 - end1.1)) / length;
+    length > 0.
+        && point2line(end1, end2, point) <= tolerance
+        && along > tolerance
+        && along < length - tolerance
+}
+
+/// Whether a point lies within twice the thickness of an element from its centreline
 pub fn near(section: &Section, x0: f64, y0: f64) -> bool {
-    let (mut end1, mut end2): ((f64, f64), (f64, f64));
-    for edge in section.graph.edge_indices() {
-        let nodes = section.graph.edge_endpoints(edge).unwrap();
-        end1 = (
-            section.graph.node_weight(nodes.0).unwrap().x,
-            section.graph.node_weight(nodes.0).unwrap().y,
-        );
-        end2 = (
-            section.graph.node_weight(nodes.1).unwrap().x,
-            section.graph.node_weight(nodes.1).unwrap().y,
-        );
+    section
+        .edges_within((x0, y0), (x0, y0))
+        .into_iter()
+        .any(|edge| {
+            let (a, b) = section.graph.edge_endpoints(edge).unwrap();
+            let end1 = (section.graph[a].x, section.graph[a].y);
+            let end2 = (section.graph[b].x, section.graph[b].y);
+            point2segment(end1, end2, (x0, y0)) < 2. * section.graph[edge].thickness
+        })
+}
 
-        if point2line(end1, end2, (x0, y0))
-            < 2. * section.graph.edge_weight(edge).unwrap().thickness
-        {
-            return true;
-        }
-    }
-    false
+/// Whether two neighbours of a node, given by their angles, lie in line on either side of it,
+/// where the builder joins plates of different thicknesses with a step
+pub fn in_line(a: f64, b: f64) -> bool {
+    ((a - b).rem_euclid(2. * f64::consts::PI) - f64::consts::PI).abs() < 1e-9
 }
 
-/* pub fn intersects2(
-    [(x1, y1), (x2, y2), (x3, y3), (x4, y4)]: [(f64, f64); 4],
-) -> Option<(f64, f64)> {
-    let test1 = (x1 - x3) * (y3 - y4)
-        - (y1 - y3) * (x3 - x4) / (x1 - x2) * (y3 - y4)
-        - (y1 - y2) * (x3 - x4);
-    let test2 = (x2 - x1) * (y1 - y3)
-        - (y2 - y1) * (x1 - x3) / (x1 - x2) * (y3 - y4)
-        - (y1 - y2) * (x3 - x4);
-    if test1 >= 0. && test1 <= 1. && test2 >= 0. && test2 <= 1.
-    /*         && (x1, y1) != (x3, y3)
-    && (x1, y1) != (x4, y4)
-    && (x2, y2) != (x3, y3)
-    && (x2, y2) != (x4, y4) */
-    {
-        let denom = (x1 - x2) * (y3 - y4) - (x1 - y2) * (x3 - x4);
-        let part1 = x1 * y2 - x1 * x2;
-        let part2 = x3 * y4 - y3 * x4;
-        Some((
-            (part1 * (x3 - x4) - (x1 - x2) * part2) / denom,
-            (part1 * (y3 - y4) - (x1 - y2) * part2) / denom,
-        ))
-    /*         return true; */
+pub(crate) fn point2segment(end1: (f64, f64), end2: (f64, f64), point: (f64, f64)) -> f64 {
+    let length = distance(end1, end2);
+    if length == 0. {
+        return distance(end1, point);
+    }
+    let along =
+        ((point.0 - end1.0) * (end2.0 - end1.0) + (point.1 - end1.1) * (end2.1 - end1.1)) / length;
+    if along <= 0. {
+        distance(end1, point)
+    } else if along >= length {
+        distance(end2, point)
     } else {
-        None
-    }
-
-    /*     false
-     */
-} */
-pub fn calc_intersect(
-    one: (f32, f32),
-    two: (f32, f32),
-    thr: (f32, f32),
-    fou: (f32, f32),
-) -> (f32, f32) {
-    let denom = (one.0 - two.0) * (thr.1 - fou.1) - (one.1 - two.1) * (thr.0 - fou.0);
-    let part1 = one.0 * two.1 - one.1 * two.0;
-    let part2 = thr.0 * fou.1 - thr.1 * fou.0;
-
-    (
-        (part1 * (thr.0 - fou.0) - (one.0 - two.0) * part2) / denom,
-        (part1 * (thr.1 - fou.1) - (one.1 - two.1) * part2) / denom,
-    )
+        point2line(end1, end2, point)
+    }
 }
+
 fn point2line(end1: (f64, f64), end2: (f64, f64), point: (f64, f64)) -> f64 {
     ((end2.0 - end1.0) * (end1.1 - point.1) - (end1.0 - point.0) * (end2.1 - end1.1)).abs()
         / ((end2.0 - end1.0) * (end2.0 - end1.0) + (end2.1 - end1.1) * (end2.1 - end1.1)).sqrt()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo::prelude::Area;
+
+    /// The node at a position
+    fn at(section: &Section, x: f64, y: f64) -> NodeIndex {
+        section
+            .graph
+            .node_indices()
+            .find(|&n| (section.graph[n].x, section.graph[n].y) == (x, y))
+            .unwrap()
+    }
+
+    fn connected(section: &Section, a: (f64, f64), b: (f64, f64)) -> bool {
+        let (a, b) = (at(section, a.0, a.1), at(section, b.0, b.1));
+        section.graph.find_edge(a, b).is_some()
+    }
+
+    #[test]
+    fn web_through_flanges() {
+        let mut section = Section::new(0.3);
+        let corners: Vec<_> = [(-9., 9.), (9., 9.), (-9., -9.), (9., -9.)]
+            .iter()
+            .map(|&(x, y)| section.add_node(x, y))
+            .collect();
+        section.add_edge(corners[0], corners[1], None, None);
+        section.add_edge(corners[2], corners[3], None, None);
+        let (top, bottom) = (section.add_node(0., 14.), section.add_node(0., -14.));
+        let pieces = section.add_edge(top, bottom, None, None);
+
+        assert_eq!(pieces.len(), 3);
+        assert_eq!(section.graph.node_count(), 8);
+        assert_eq!(section.graph.edge_count(), 7);
+        for &(a, b) in &[
+            ((-9., 9.), (0., 9.)),
+            ((0., 9.), (9., 9.)),
+            ((-9., -9.), (0., -9.)),
+            ((0., -9.), (9., -9.)),
+            ((0., 14.), (0., 9.)),
+            ((0., 9.), (0., -9.)),
+            ((0., -9.), (0., -14.)),
+        ] {
+            assert!(connected(&section, a, b));
+        }
+
+        // the same section drawn a plate at a time
+        let mut drawn = Section::new(0.3);
+        let n: Vec<_> = [
+            (-9., 9.),
+            (0., 9.),
+            (9., 9.),
+            (-9., -9.),
+            (0., -9.),
+            (9., -9.),
+            (0., 14.),
+            (0., -14.),
+        ]
+        .iter()
+        .map(|&(x, y)| drawn.graph.add_node(Node { x, y, label: None }))
+        .collect();
+        drawn.reindex();
+        for &(a, b) in &[(0, 1), (1, 2), (3, 4), (4, 5), (6, 1), (1, 4), (4, 7)] {
+            drawn.add_edge(n[a], n[b], None, None);
+        }
+        for &clipper in &[Clipper::BooleanOp, Clipper::Overlay] {
+            let (area, expected) = (
+                section.build_with(clipper).area().abs(),
+                drawn.build_with(clipper).area().abs(),
+            );
+            assert!((area - expected).abs() < 1e-9 * expected);
+        }
+    }
+
+    #[test]
+    fn t_junction() {
+        let mut section = Section::new(0.3);
+        let (foot, head) = (section.add_node(0., -10.), section.add_node(0., 0.));
+        section.add_edge(foot, head, None, None);
+        let (left, right) = (section.add_node(-9., 0.), section.add_node(9., 0.));
+        let pieces = section.add_edge(left, right, None, None);
+
+        assert_eq!(pieces.len(), 2);
+        assert_eq!(section.graph.edge_count(), 3);
+        assert_eq!(section.graph.neighbors(head).count(), 3);
+        assert!(section.graph.find_edge(left, head).is_some());
+        assert!(section.graph.find_edge(head, right).is_some());
+    }
+
+    #[test]
+    fn collinear_overlap() {
+        let mut section = Section::new(0.3);
+        let (a, b) = (section.add_node(0., 0.), section.add_node(10., 0.));
+        section.add_edge(a, b, Some(1.), None);
+        let (c, d) = (section.add_node(-5., 0.), section.add_node(15., 0.));
+        let pieces = section.add_edge(c, d, None, None);
+
+        // the stretch along the existing element is left to it
+        assert_eq!(pieces.len(), 2);
+        assert_eq!(section.graph.edge_count(), 3);
+        let along = section.graph.find_edge(a, b).unwrap();
+        assert_eq!(section.graph[along].thickness, 1.);
+        assert!(section.graph.find_edge(c, a).is_some());
+        assert!(section.graph.find_edge(b, d).is_some());
+        // adding it again changes nothing
+        assert!(section.add_edge(c, d, None, None).is_empty());
+        assert_eq!(section.graph.edge_count(), 3);
+    }
+
+    #[test]
+    fn index_follows_deleted_elements() {
+        let mut section = Section::new(0.3);
+        let n: Vec<_> = [(0., 0.), (10., 0.), (20., 0.), (30., 0.)]
+            .iter()
+            .map(|&(x, y)| section.add_node(x, y))
+            .collect();
+        for pair in n.windows(2) {
+            section.add_edge(pair[0], pair[1], Some(1.), None);
+        }
+        section.delete_member(EdgeIndex::new(0));
+        assert_eq!(section.grid.len(), section.graph.edge_count());
+        for edge in section.graph.edge_indices() {
+            let (a, b) = section.graph.edge_endpoints(edge).unwrap();
+            let middle = (
+                (section.graph[a].x + section.graph[b].x) / 2.,
+                (section.graph[a].y + section.graph[b].y) / 2.,
+            );
+            assert_eq!(section.edges_within(middle, middle), vec![edge]);
+        }
+        assert!(!near(&section, 5., 0.));
+        assert!(near(&section, 25., 0.));
+    }
+
+    #[test]
+    fn snap_merge() {
+        let mut section = Section::new(0.3);
+        section.set_snap(Some(0.5));
+        let (a, b) = (section.add_node(0., 0.), section.add_node(10., 0.));
+        section.add_edge(a, b, Some(1.), None);
+        assert_eq!(section.add_node(10.2, 0.1), b);
+        assert_eq!(section.graph.node_count(), 2);
+
+        let (c, d) = (section.add_node(20., 5.), section.add_node(30., 5.));
+        section.add_edge(c, d, Some(1.), None);
+        let merged = section.move_node(c, 10.3, 0.2);
+        assert_eq!(section.graph.node_count(), 3);
+        assert_eq!(
+            (section.graph[merged].x, section.graph[merged].y),
+            (10., 0.)
+        );
+        let d = at(&section, 30., 5.);
+        assert!(section.graph.find_edge(merged, d).is_some());
+        assert!(section
+            .graph
+            .find_edge(merged, at(&section, 0., 0.))
+            .is_some());
+    }
+
+    #[test]
+    fn snap_split() {
+        let mut section = Section::new(0.3);
+        section.set_snap(Some(0.5));
+        let (a, b) = (section.add_node(0., 0.), section.add_node(10., 0.));
+        section.add_edge(a, b, Some(1.), None);
+        let c = section.add_node(5., 0.3);
+        assert_eq!((section.graph[c].x, section.graph[c].y), (5., 0.));
+        assert!(section.graph.find_edge(a, c).is_some());
+        assert!(section.graph.find_edge(c, b).is_some());
+        assert!(section.graph.find_edge(a, b).is_none());
+
+        let (d, e) = (section.add_node(2., 10.), section.add_node(2., 20.));
+        section.add_edge(d, e, Some(1.), None);
+        let d = section.move_node(d, 2.2, 0.4);
+        assert_eq!((section.graph[d].x, section.graph[d].y), (2.2, 0.));
+        assert_eq!(section.graph.neighbors(d).count(), 3);
+        assert_eq!(section.graph.edge_count(), 4);
+    }
+
+    #[test]
+    fn move_across_a_plate_splits_both() {
+        let mut section = Section::new(0.3);
+        let (a, b) = (section.add_node(-10., 0.), section.add_node(10., 0.));
+        section.add_edge(a, b, Some(1.), None);
+        let (c, d) = (section.add_node(0., 5.), section.add_node(0., 15.));
+        section.add_edge(c, d, Some(1.), None);
+        let c = section.move_node(c, 0., -5.);
+        assert_eq!(section.graph.node_count(), 5);
+        assert_eq!(section.graph.edge_count(), 4);
+        let middle = at(&section, 0., 0.);
+        assert_eq!(section.graph.neighbors(middle).count(), 4);
+        assert!(section.graph.find_edge(c, middle).is_some());
+        assert!(section.validate().is_empty());
+    }
+
+    #[test]
+    fn labels_follow_deleted_nodes() {
+        let mut section = Section::new(0.3);
+        let nodes: Vec<_> = (0..4)
+            .map(|i| section.add_node(10. * i as f64, 0.))
+            .collect();
+        assert!(section.label_node(nodes[1], Some("one")));
+        assert!(section.label_node(nodes[3], Some("three")));
+        assert!(!section.label_node(nodes[2], Some("one")));
+        assert_eq!(section.graph[nodes[2]].label, None);
+        assert!(section.label_node(nodes[1], Some("one")));
+
+        // the last node takes the index of the deleted one
+        section.delete_node(nodes[0]);
+        assert_eq!(section.node_by_label("three"), Some(nodes[0]));
+        assert_eq!(section.node_by_label("one"), Some(nodes[1]));
+        section.delete_node(nodes[1]);
+        assert_eq!(section.node_by_label("one"), None);
+        assert_eq!(section.node_by_label("three"), Some(nodes[0]));
+        assert!(section.label_node(nodes[1], Some("one")));
+    }
+
+    #[test]
+    fn merge_passes_the_label_on() {
+        let mut section = Section::new(0.3);
+        section.set_snap(Some(0.5));
+        let (a, b) = (section.add_node(0., 0.), section.add_node(10., 0.));
+        section.label_node(a, Some("corner"));
+        let a = section.move_node(a, 10.2, 0.);
+        assert_eq!(section.node_by_label("corner"), Some(a));
+        section.undo();
+        assert_eq!(section.node_by_label("corner"), Some(at(&section, 0., 0.)));
+
+        // the surviving node keeps its own name
+        let c = section.add_node(20., 0.);
+        section.label_node(b, Some("end"));
+        section.label_node(c, Some("far"));
+        let c = section.move_node(c, 10., 0.3);
+        assert_eq!(section.node_by_label("end"), Some(c));
+        assert_eq!(section.node_by_label("far"), None);
+    }
+
+    #[test]
+    fn duplicate_labels_in_a_model() {
+        let model = "nodes = [[0, 0], [10, 0]]\n\
+                     plates = [{ start = 0, end = 1 }]\n\
+                     labels = [{ node = 0, label = \"a\" }, { node = 1, label = \"a\" }]\n";
+        let error = toml::from_str::<Section>(model).err().unwrap();
+        assert!(error.to_string().contains("duplicate label a"));
+    }
+}
@@ -0,0 +1,251 @@
+// Outlines and properties kept between builds, so that a section changed a little at a time
+// is rebuilt a little at a time
+use crate::build::Section;
+use crate::clipping::{self, Clipper};
+use crate::properties::Properties;
+use geo::{LineString, MultiPolygon, Polygon};
+use geo_booleanop::boolean::BooleanOp;
+use petgraph::graph::NodeIndex;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// The parts of the outline at each node, merged pairwise up a binary tree. A part is built
+/// again only when the node, its neighbours or their plates change, and then only the unions
+/// above it are merged again.
+#[derive(Default)]
+pub(crate) struct Cache {
+    /// The clipper of the outline, if it is current
+    current: Option<Clipper>,
+    /// The clipper the tree was merged with, unless a merge was cut short
+    clipper: Option<Clipper>,
+    /// What the part of the outline at each node was built from, by node index
+    keys: Vec<Vec<u64>>,
+    /// The unions, with the root at index 1, the children of index i at 2i and 2i + 1, and
+    /// the parts of the outline at each node as the leaves
+    tree: Vec<MultiPolygon<f64>>,
+    /// The properties, if they are current
+    pub(crate) properties: Option<Properties>,
+    /// The fingerprint of the graph the results are current for, which catches edits made
+    /// to the graph directly
+    fingerprint: u64,
+}
+
+impl Cache {
+    pub(crate) fn invalidate(&mut self) {
+        self.current = None;
+        self.properties = None;
+    }
+
+    /// This function marks the results as out of date if the graph is not the one they
+    /// were built from
+    pub(crate) fn check(&mut self, section: &Section) {
+        let fingerprint = fingerprint(section);
+        if fingerprint != self.fingerprint {
+            self.invalidate();
+            self.fingerprint = fingerprint;
+        }
+    }
+
+    pub(crate) fn is_current(&self) -> bool {
+        self.current.is_some() && self.properties.is_some()
+    }
+
+    /// This function brings the outline up to date and returns it
+    pub(crate) fn outline(&mut self, section: &Section, clipper: Clipper) -> MultiPolygon<f64> {
+        if self.current == Some(clipper) {
+            return self.tree[1].clone();
+        }
+        // a merge that panics leaves the tree part built, so the outline is not current until
+        // the root is merged again. The properties are worked out from the plates alone.
+        self.current = None;
+        let count = section.graph.node_count();
+        let leaves = count.next_power_of_two().max(2);
+        if self.clipper.take() != Some(clipper) || self.keys.len() != leaves {
+            self.keys = vec![vec![]; leaves];
+            self.tree = vec![MultiPolygon(vec![]); 2 * leaves];
+        }
+
+        let mut stale = vec![false; 2 * leaves];
+        for i in 0..leaves {
+            let key = if i < count {
+                key(section, NodeIndex::new(i))
+            } else {
+                vec![]
+            };
+            if key != self.keys[i] {
+                let cluster = if i < count {
+                    section.cluster(NodeIndex::new(i))
+                } else {
+                    vec![]
+                };
+                self.tree[leaves + i] = if cluster.is_empty() {
+                    MultiPolygon(vec![])
+                } else {
+                    MultiPolygon(vec![Polygon::new(LineString::from(cluster), vec![])])
+                };
+                self.keys[i] = key;
+                stale[(leaves + i) / 2] = true;
+            }
+        }
+        for i in (1..leaves).rev() {
+            if !stale[i] {
+                continue;
+            }
+            let (left, right) = (&self.tree[2 * i], &self.tree[2 * i + 1]);
+            self.tree[i] = if right.0.is_empty() {
+                left.clone()
+            } else if left.0.is_empty() {
+                right.clone()
+            } else {
+                match clipper {
+                    Clipper::BooleanOp => left.union(right),
+                    Clipper::Overlay => clipping::union(left, right),
+                }
+            };
+            stale[i / 2] = true;
+        }
+
+        self.clipper = Some(clipper);
+        self.current = Some(clipper);
+        self.tree[1].clone()
+    }
+}
+
+/// What the part of the outline at a node is built from: the positions of the node and its
+/// neighbours, the thicknesses of their plates, and whether the section has fewer than three
+/// nodes
+fn key(section: &Section, node: NodeIndex) -> Vec<u64> {
+    let here = &section.graph[node];
+    let mut key = vec![
+        here.x.to_bits(),
+        here.y.to_bits(),
+        (section.graph.node_count() < 3) as u64,
+    ];
+    for neighbour in section.graph.neighbors(node) {
+        let there = &section.graph[neighbour];
+        let edge = section.graph.find_edge(node, neighbour).unwrap();
+        key.extend(&[
+            there.x.to_bits(),
+            there.y.to_bits(),
+            section.graph[edge].thickness.to_bits(),
+        ]);
+    }
+    key
+}
+
+/// A hash of the positions of the nodes and the ends, thicknesses and moduli of the plates
+fn fingerprint(section: &Section) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for node in section.graph.raw_nodes() {
+        (node.weight.x.to_bits(), node.weight.y.to_bits()).hash(&mut hasher);
+    }
+    for edge in section.graph.raw_edges() {
+        (
+            edge.source().index(),
+            edge.target().index(),
+            edge.weight.thickness.to_bits(),
+            edge.weight.elastic.to_bits(),
+        )
+            .hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use petgraph::graph::UnGraph;
+
+    fn channel() -> (Section, Vec<NodeIndex>) {
+        let mut section = Section::new(0.3);
+        let nodes: Vec<_> = [(10., 10.), (0., 10.), (0., -10.), (10., -10.)]
+            .iter()
+            .map(|&(x, y)| section.add_node(x, y))
+            .collect();
+        for pair in nodes.windows(2) {
+            section.add_edge(pair[0], pair[1], Some(1.), None);
+        }
+        (section, nodes)
+    }
+
+    /// A section with a copy of the graph and nothing kept from earlier builds
+    fn fresh(graph: &UnGraph<crate::build::Node, crate::build::Element>) -> Section {
+        let mut section = Section::new(0.3);
+        section.graph = graph.clone();
+        section.reindex();
+        section
+    }
+
+    #[test]
+    fn direct_edits_are_rebuilt() {
+        let (mut section, nodes) = channel();
+        let area = section.properties().area;
+        section.build();
+        assert!(section.is_current());
+
+        section.graph[nodes[0]].x = 20.;
+        assert!(!section.is_current());
+        assert!((section.properties().area - (area + 10.)).abs() < 1e-9);
+        assert_eq!(section.build(), fresh(&section.graph).build());
+
+        section
+            .graph
+            .edge_weights_mut()
+            .for_each(|e| e.thickness = 2.);
+        assert_eq!(section.build(), fresh(&section.graph).build());
+        assert!((section.properties().area - 2. * (area + 10.)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn edits_rebuild_as_a_fresh_build() {
+        for &clipper in &[Clipper::BooleanOp, Clipper::Overlay] {
+            let (mut section, nodes) = channel();
+            section.build_with(clipper);
+            section.move_node(nodes[1], -2., 12.);
+            assert_eq!(
+                section.build_with(clipper),
+                fresh(&section.graph).build_with(clipper)
+            );
+            let (a, b) = (section.add_node(4., 0.), section.add_node(8., 0.));
+            section.add_edge(a, b, Some(0.5), None);
+            assert_eq!(
+                section.build_with(clipper),
+                fresh(&section.graph).build_with(clipper)
+            );
+            // the last node moves into the place of the deleted one
+            section.delete_node(nodes[0]);
+            assert_eq!(
+                section.build_with(clipper),
+                fresh(&section.graph).build_with(clipper)
+            );
+            section.undo();
+            section.undo();
+            assert_eq!(
+                section.build_with(clipper),
+                fresh(&section.graph).build_with(clipper)
+            );
+        }
+    }
+
+    #[test]
+    fn panicked_merge_is_rebuilt() {
+        // geo-booleanop loses an event in the sweep merging this chain
+        let mut section = Section::new(0.3);
+        let nodes: Vec<_> = [
+            (19.916, 5.2332),
+            (1.7184, -2.5336),
+            (-15.632, -1.0808),
+            (-3.9732, -16.9844),
+        ]
+        .iter()
+        .map(|&(x, y)| section.add_node(x, y))
+        .collect();
+        for &(a, b) in &[(3, 1), (3, 2), (0, 2)] {
+            section.add_edge(nodes[a], nodes[b], Some(0.1), None);
+        }
+        let outline = section.build_with(Clipper::Overlay);
+        assert!(!outline.0.is_empty());
+        let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| section.build()));
+        assert_eq!(section.build_with(Clipper::Overlay), outline);
+    }
+}
@@ -0,0 +1,89 @@
+// A small catalogue of rolled I-sections modelled by their plate centrelines
+use crate::build::Section;
+
+/// Nominal dimensions of a doubly symmetric I-section
+#[derive(Clone, Copy, Debug)]
+pub struct Shape {
+    pub name: &'static str,
+    /// Overall depth, d
+    pub depth: f64,
+    /// Flange width, bf
+    pub width: f64,
+    /// Flange thickness, tf
+    pub flange: f64,
+    /// Web thickness, tw
+    pub web: f64,
+    /// Modulus of elasticity in the units of the dimensions
+    pub elastic: f64,
+}
+
+const fn w(name: &'static str, depth: f64, width: f64, flange: f64, web: f64) -> Shape {
+    Shape {
+        name,
+        depth,
+        width,
+        flange,
+        web,
+        elastic: 29000.,
+    }
+}
+
+const fn ipe(name: &'static str, depth: f64, width: f64, flange: f64, web: f64) -> Shape {
+    Shape {
+        name,
+        depth,
+        width,
+        flange,
+        web,
+        elastic: 210000.,
+    }
+}
+
+/// AISC W shapes in inches and ksi, and European IPE sections in mm and N/mm²
+pub const SHAPES: [Shape; 12] = [
+    w("W8X31", 8.00, 8.00, 0.435, 0.285),
+    w("W10X33", 9.73, 7.96, 0.435, 0.290),
+    w("W12X26", 12.2, 6.49, 0.380, 0.230),
+    w("W14X22", 13.7, 5.00, 0.335, 0.230),
+    w("W16X26", 15.7, 5.50, 0.345, 0.250),
+    w("W18X35", 17.7, 6.00, 0.425, 0.300),
+    w("W21X44", 20.7, 6.50, 0.450, 0.350),
+    w("W24X55", 23.6, 7.01, 0.505, 0.395),
+    ipe("IPE200", 200., 100., 8.5, 5.6),
+    ipe("IPE300", 300., 150., 10.7, 7.1),
+    ipe("IPE400", 400., 180., 13.5, 8.6),
+    ipe("IPE500", 500., 200., 16.0, 10.2),
+];
+
+/// This function looks up a shape by name, ignoring case
+pub fn lookup(name: &str) -> Option<Shape> {
+    SHAPES
+        .iter()
+        .find(|shape| shape.name.eq_ignore_ascii_case(name))
+        .copied()
+}
+
+impl Shape {
+    /// This function models the shape as five plates meeting at the flange centrelines,
+    /// centred on the origin. Root fillets are neglected.
+    pub fn section(&self, poisson: f64) -> Section {
+        let mut section = Section::new(poisson);
+        let (h, b) = ((self.depth - self.flange) / 2., self.width / 2.);
+        let top = [
+            section.add_node(-b, h),
+            section.add_node(0., h),
+            section.add_node(b, h),
+        ];
+        let bottom = [
+            section.add_node(-b, -h),
+            section.add_node(0., -h),
+            section.add_node(b, -h),
+        ];
+        for nodes in [top, bottom].iter() {
+            section.add_edge(nodes[0], nodes[1], Some(self.flange), Some(self.elastic));
+            section.add_edge(nodes[1], nodes[2], Some(self.flange), Some(self.elastic));
+        }
+        section.add_edge(top[1], bottom[1], Some(self.web), Some(self.elastic));
+        section
+    }
+}
@@ -1,461 +1,506 @@
-// I copy-pasted this code from: https://docs.rs/crate/clipping/0.1.1
-use std::fmt;
-use std::ptr;
-
-use rand;
-
-#[derive(Debug)]
-/// Node in a circular doubly linked list
-struct Vertex {
-    pt: [f64; 2],
-    next: *mut Vertex,
-    prev: *mut Vertex,
-    neighbour: *mut Vertex,
-    entry: bool,
-    alpha: f64,
-    inter: bool,
-    checked: bool,
+// Boolean operations on polygons by overlaying their edges, without raw pointers
+use geo::{LineString, MultiPolygon, Polygon};
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+/// The boolean operations on two sets of polygons
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operation {
+    Union,
+    Intersection,
+    /// The first set less the second
+    Difference,
+    Xor,
 }
 
-impl Vertex {
-    pub fn new(pt: [f64; 2], alpha: f64, inter: bool, entry: bool, checked: bool) -> *mut Self {
-        let vertex = Box::new(Self {
-            pt: pt,
-            next: ptr::null_mut(),
-            prev: ptr::null_mut(),
-            neighbour: ptr::null_mut(),
-            entry: entry,
-            alpha: alpha,
-            inter: inter,
-            checked: checked,
-        });
-        let ptr = Box::into_raw(vertex);
-
-        unsafe {
-            ptr.as_mut().unwrap().next = ptr;
-            ptr.as_mut().unwrap().prev = ptr;
-        }
-
-        ptr
-    }
+/// The polygon clipping used to merge the plates of a section into its outline
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Clipper {
+    /// The sweep line clipper of the geo-booleanop crate
+    BooleanOp,
+    /// The overlay clipper of this module
+    Overlay,
+}
 
-    pub fn clone(vertex: *mut Vertex) -> *mut Self {
-        unsafe {
-            Self::new(
-                vertex.as_ref().unwrap().pt,
-                vertex.as_ref().unwrap().alpha,
-                vertex.as_ref().unwrap().inter,
-                vertex.as_ref().unwrap().entry,
-                vertex.as_ref().unwrap().checked,
-            )
+impl Operation {
+    fn apply(self, a: bool, b: bool) -> bool {
+        match self {
+            Operation::Union => a || b,
+            Operation::Intersection => a && b,
+            Operation::Difference => a && !b,
+            Operation::Xor => a != b,
         }
     }
+}
 
-    /// Test if the vertex is inside the polygon
-    pub fn is_inside(&self, poly: &CPolygon) -> bool {
-        let mut w = 0;
-
-        let infinity = [
-            -100000. - (rand::random::<f64>() % 100.),
-            -100000. - (rand::random::<f64>() % 100.),
-        ];
+pub fn union(a: &MultiPolygon<f64>, b: &MultiPolygon<f64>) -> MultiPolygon<f64> {
+    clip(a, b, Operation::Union)
+}
 
-        for q in poly.iter() {
-            unsafe {
-                let pt_q = q.as_ref().unwrap().pt;
-                let pt_q_next = poly.next(q.as_ref().unwrap().next).as_ref().unwrap().pt;
+pub fn intersection(a: &MultiPolygon<f64>, b: &MultiPolygon<f64>) -> MultiPolygon<f64> {
+    clip(a, b, Operation::Intersection)
+}
 
-                match intersect(self.pt, infinity, pt_q, pt_q_next) {
-                    Some(_) => {
-                        if !q.as_ref().unwrap().inter {
-                            w += 1;
-                        }
-                    }
-                    None => {}
-                }
-            }
-        }
+pub fn difference(a: &MultiPolygon<f64>, b: &MultiPolygon<f64>) -> MultiPolygon<f64> {
+    clip(a, b, Operation::Difference)
+}
 
-        w % 2 != 0
+/// This function combines two sets of polygons, which may overlap themselves and have holes.
+/// The edges of both are split wherever they meet, with points closer than a billionth of
+/// the size of the polygons merged and edges lying along each other taken as one. Each piece
+/// is kept when the result of the operation differs on its two sides, as found from the
+/// winding numbers of both sets, and the pieces kept are then joined into rings.
+pub fn clip(
+    a: &MultiPolygon<f64>,
+    b: &MultiPolygon<f64>,
+    operation: Operation,
+) -> MultiPolygon<f64> {
+    let rings: Vec<(usize, Vec<(f64, f64)>)> = rings(a, 0).chain(rings(b, 1)).collect();
+    let all: Vec<(f64, f64)> = rings.iter().flat_map(|(_, r)| r.iter().cloned()).collect();
+    if all.is_empty() {
+        return MultiPolygon(vec![]);
     }
-
-    pub fn set_checked(&mut self) {
-        self.checked = true;
-        if !self.neighbour.is_null() {
-            unsafe {
-                if !self.neighbour.as_ref().unwrap().checked {
-                    self.neighbour.as_mut().unwrap().set_checked();
-                }
+    let (min, max) = all.iter().fold((all[0], all[0]), |(min, max), p| {
+        (
+            (min.0.min(p.0), min.1.min(p.1)),
+            (max.0.max(p.0), max.1.max(p.1)),
+        )
+    });
+    let tolerance = 1e-9 * (max.0 - min.0).max(max.1 - min.1).max(f64::MIN_POSITIVE);
+
+    let mut overlay = Overlay {
+        points: vec![],
+        tolerance,
+    };
+    let mut edges = vec![];
+    for (owner, ring) in &rings {
+        let corners: Vec<usize> = ring.iter().map(|&p| overlay.snap(p)).collect();
+        for i in 0..corners.len() {
+            let (s, e) = (corners[i], corners[(i + 1) % corners.len()]);
+            if s != e {
+                edges.push((*owner, s, e));
             }
         }
     }
-}
-
-impl fmt::Display for Vertex {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        unsafe {
-            let pt = self.pt;
-            let npt = self.next.as_ref().unwrap().pt;
-            let ppt = self.prev.as_ref().unwrap().pt;
-
-            write!(f, "{:?} <-> {:?} <-> {:?}", ppt, pt, npt)
+    let pieces = overlay.split(&edges);
+
+    // the change in the winding number of each set across each piece, from right to left
+    // when going from its lower point to its higher
+    let mut windings: HashMap<(usize, usize), [i32; 2]> = HashMap::new();
+    for (owner, s, e) in pieces {
+        let step = if s < e { 1 } else { -1 };
+        windings.entry((s.min(e), s.max(e))).or_insert([0; 2])[owner] += step;
+    }
+    let mut keys: Vec<(usize, usize)> = windings.keys().cloned().collect();
+    keys.sort_unstable();
+
+    let mut kept = vec![];
+    for &(s, e) in &keys {
+        let step = windings[&(s, e)];
+        let right = [0, 1].map(|owner| overlay.winding(&windings, (s, e), owner));
+        let inside_right = operation.apply(right[0] != 0, right[1] != 0);
+        let inside_left = operation.apply(right[0] + step[0] != 0, right[1] + step[1] != 0);
+        match (inside_left, inside_right) {
+            (true, false) => kept.push((s, e)),
+            (false, true) => kept.push((e, s)),
+            _ => {}
         }
     }
+    overlay.polygons(&kept)
 }
 
-pub struct CPolygon {
-    fst: *mut Vertex,
+/// The rings of a set of polygons with their owner, the exteriors running anticlockwise and
+/// the holes clockwise, so that the inside is always on the left
+fn rings(
+    polygons: &MultiPolygon<f64>,
+    owner: usize,
+) -> impl Iterator<Item = (usize, Vec<(f64, f64)>)> + '_ {
+    polygons.0.iter().flat_map(move |polygon| {
+        let exterior = std::iter::once((polygon.exterior(), true));
+        exterior
+            .chain(polygon.interiors().iter().map(|ring| (ring, false)))
+            .filter_map(move |(ring, outer)| {
+                let mut points: Vec<(f64, f64)> = ring.0.iter().map(|c| (c.x, c.y)).collect();
+                if points.len() > 1 && points[0] == points[points.len() - 1] {
+                    points.pop();
+                }
+                if points.len() < 3 {
+                    return None;
+                }
+                if (area(&points) > 0.) != outer {
+                    points.reverse();
+                }
+                Some((owner, points))
+            })
+    })
 }
 
-impl CPolygon {
-    /// Create a new empty polygon
-    pub fn new() -> Self {
-        Self {
-            fst: ptr::null_mut(),
-        }
-    }
-
-    /// Create a polygon from a list of points
-    pub fn from_vec(vec: &Vec<[f64; 2]>) -> Self {
-        let mut poly = Self::new();
-
-        for v in vec {
-            let vertex = Vertex::new(*v, 0., false, true, false);
-            poly.add(vertex);
-        }
-
-        poly
-    }
+/// Twice the signed area of a ring, positive when it runs anticlockwise
+fn area(ring: &[(f64, f64)]) -> f64 {
+    (0..ring.len())
+        .map(|i| {
+            let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+            a.0 * b.1 - b.0 * a.1
+        })
+        .sum()
+}
 
-    fn iter(&self) -> CPolyIter {
-        CPolyIter::new(self)
-    }
+fn cross(o: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+}
 
-    /// Add a vertex at the end of the polygon
-    fn add(&mut self, vertex: *mut Vertex) {
-        if self.fst.is_null() {
-            self.fst = vertex;
-        } else {
-            unsafe {
-                let next = self.fst;
-                let prev = next.as_ref().unwrap().prev;
-
-                next.as_mut().unwrap().prev = vertex;
-                vertex.as_mut().unwrap().next = next;
-                vertex.as_mut().unwrap().prev = prev;
-                prev.as_mut().unwrap().next = vertex;
-            }
-        }
-    }
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt()
+}
 
-    /// Insert and sort a vertex between a specified pair of vertices
-    fn insert(&mut self, vertex: *mut Vertex, start: *mut Vertex, end: *mut Vertex) {
-        unsafe {
-            let mut curr = start;
+/// The points of the overlay, referred to by their index
+struct Overlay {
+    points: Vec<(f64, f64)>,
+    tolerance: f64,
+}
 
-            while curr != end && curr.as_ref().unwrap().alpha < vertex.as_ref().unwrap().alpha {
-                curr = curr.as_ref().unwrap().next;
+impl Overlay {
+    /// The index of a point, merged with any point already within the tolerance
+    fn snap(&mut self, p: (f64, f64)) -> usize {
+        match self
+            .points
+            .iter()
+            .position(|&q| distance(p, q) <= self.tolerance)
+        {
+            Some(index) => index,
+            None => {
+                self.points.push(p);
+                self.points.len() - 1
             }
-
-            vertex.as_mut().unwrap().next = curr;
-            let prev = curr.as_ref().unwrap().prev;
-            vertex.as_mut().unwrap().prev = prev;
-            prev.as_mut().unwrap().next = vertex;
-            curr.as_mut().unwrap().prev = vertex;
         }
     }
 
-    /// Return the next non intersecting vertex after the one specified
-    fn next(&self, v: *mut Vertex) -> *mut Vertex {
-        unsafe {
-            let mut c = v;
-            while c.as_ref().unwrap().inter {
-                c = c.as_ref().unwrap().next;
-            }
-            c
-        }
+    /// Whether a point lies on an edge, short of its ends
+    fn lies_on(&self, s: usize, e: usize, p: usize) -> bool {
+        let (ps, pe, pp) = (self.points[s], self.points[e], self.points[p]);
+        let length = distance(ps, pe);
+        let along = ((pp.0 - ps.0) * (pe.0 - ps.0) + (pp.1 - ps.1) * (pe.1 - ps.1)) / length;
+        p != s
+            && p != e
+            && cross(ps, pe, pp).abs() / length <= self.tolerance
+            && along > 0.
+            && along < length
     }
 
-    /// Return the first unchecked intersection point in the polygon
-    fn first_intersect(&self) -> *mut Vertex {
-        let mut ve = ptr::null_mut();
-
-        for v in self.iter() {
-            ve = v;
-            unsafe {
-                if v.as_ref().unwrap().inter && !v.as_ref().unwrap().checked {
-                    break;
+    /// This function splits the edges wherever they cross, touch or lie along each other
+    fn split(&mut self, edges: &[(usize, usize, usize)]) -> Vec<(usize, usize, usize)> {
+        let mut cuts: Vec<Vec<usize>> = vec![vec![]; edges.len()];
+        for i in 0..edges.len() {
+            for j in i + 1..edges.len() {
+                let (_, s1, e1) = edges[i];
+                let (_, s2, e2) = edges[j];
+                // ends lying on the other edge, which covers overlapping edges
+                for &p in &[s2, e2] {
+                    if self.lies_on(s1, e1, p) {
+                        cuts[i].push(p);
+                    }
                 }
-            }
-        }
-
-        ve
-    }
-
-    /// Return the polygon points as a list of points, clear consecutive equals points
-    pub fn points(&self) -> Vec<[f64; 2]> {
-        let mut poly = vec![];
-
-        for vertex in self.iter() {
-            unsafe {
-                let pt = vertex.as_ref().unwrap().pt;
-                if vertex == self.fst {
-                    poly.push(pt);
-                } else {
-                    if vertex == self.fst.as_ref().unwrap().prev {
-                        if self.fst.as_ref().unwrap().pt != pt {
-                            poly.push(pt);
-                        }
-                    } else {
-                        let prev = vertex.as_ref().unwrap().prev.as_ref().unwrap().pt;
-
-                        if prev != pt {
-                            poly.push(pt);
-                        }
+                for &p in &[s1, e1] {
+                    if self.lies_on(s2, e2, p) {
+                        cuts[j].push(p);
                     }
                 }
-            }
-        }
-
-        poly
-    }
-
-    /// Check if an unchecked intersection remain in the polygon
-    fn unprocessed(&self) -> bool {
-        for v in self.iter() {
-            unsafe {
-                if v.as_ref().unwrap().inter && !v.as_ref().unwrap().checked {
-                    return true;
+                // a crossing inside both edges
+                let (a, b, c, d) = (
+                    self.points[s1],
+                    self.points[e1],
+                    self.points[s2],
+                    self.points[e2],
+                );
+                let (l1, l2) = (distance(a, b), distance(c, d));
+                let (dc, dd) = (cross(a, b, c) / l1, cross(a, b, d) / l1);
+                let (da, db) = (cross(c, d, a) / l2, cross(c, d, b) / l2);
+                let clear = |x: f64, y: f64| x.abs() > self.tolerance && y.abs() > self.tolerance;
+                if dc * dd < 0. && da * db < 0. && clear(dc, dd) && clear(da, db) {
+                    let t = dc / (dc - dd);
+                    let p = self.snap((c.0 + (d.0 - c.0) * t, c.1 + (d.1 - c.1) * t));
+                    for &(k, s, e) in &[(i, s1, e1), (j, s2, e2)] {
+                        if p != s && p != e {
+                            cuts[k].push(p);
+                        }
+                    }
                 }
             }
         }
-
-        false
-    }
-
-    fn phase_one(&mut self, poly: &mut CPolygon) {
-        for s in self.iter() {
-            unsafe {
-                if !s.as_ref().unwrap().inter {
-                    for c in poly.iter() {
-                        if !c.as_ref().unwrap().inter {
-                            let s_pt = s.as_ref().unwrap().pt;
-                            let s_next_pt =
-                                self.next(s.as_ref().unwrap().next).as_ref().unwrap().pt;
-                            let c_pt = c.as_ref().unwrap().pt;
-                            let c_next_pt =
-                                poly.next(c.as_ref().unwrap().next).as_ref().unwrap().pt;
-
-                            match intersect(s_pt, s_next_pt, c_pt, c_next_pt) {
-                                Some((i, alpha_s, alpha_c)) => {
-                                    let mut is = Vertex::new(i, alpha_s, true, false, false);
-                                    let mut ic = Vertex::new(i, alpha_c, true, false, false);
-                                    is.as_mut().unwrap().neighbour = ic;
-                                    ic.as_mut().unwrap().neighbour = is;
-
-                                    let s_next = self.next(s.as_ref().unwrap().next);
-                                    self.insert(is, s, s_next);
-
-                                    let c_next = poly.next(c.as_ref().unwrap().next);
-                                    poly.insert(ic, c, c_next);
-                                }
-                                None => {}
-                            }
-                        }
-                    }
+        let mut pieces = vec![];
+        for (k, &(owner, s, e)) in edges.iter().enumerate() {
+            let (ps, pe) = (self.points[s], self.points[e]);
+            let position = |p: usize| {
+                let q = self.points[p];
+                (q.0 - ps.0) * (pe.0 - ps.0) + (q.1 - ps.1) * (pe.1 - ps.1)
+            };
+            let mut stops = vec![s];
+            let mut inner = cuts[k].clone();
+            inner.sort_by(|&p, &q| position(p).partial_cmp(&position(q)).unwrap());
+            inner.dedup();
+            stops.extend(inner);
+            stops.push(e);
+            for pair in stops.windows(2) {
+                if pair[0] != pair[1] {
+                    pieces.push((owner, pair[0], pair[1]));
                 }
             }
         }
+        pieces
     }
 
-    fn phase_two(&mut self, poly: &mut CPolygon, mut s_entry: bool, mut c_entry: bool) {
-        unsafe {
-            s_entry ^= self.fst.as_ref().unwrap().is_inside(poly);
-            for s in self.iter() {
-                if s.as_ref().unwrap().inter {
-                    s.as_mut().unwrap().entry = s_entry;
-                    s_entry = !s_entry;
-                }
+    /// The winding number of a set on the right of a piece, found by casting a ray from its
+    /// middle to the right and counting the other pieces of the set it crosses. Points on the
+    /// ray count as above it, so the count does not depend on chance.
+    fn winding(
+        &self,
+        windings: &HashMap<(usize, usize), [i32; 2]>,
+        piece: (usize, usize),
+        owner: usize,
+    ) -> i32 {
+        let (s, e) = (self.points[piece.0], self.points[piece.1]);
+        let length = distance(s, e);
+        let along = ((e.0 - s.0) / length, (e.1 - s.1) / length);
+        let middle = ((s.0 + e.0) / 2., (s.1 + e.1) / 2.);
+        // a frame with the ray along x and the piece along y
+        let frame = |p: (f64, f64)| {
+            let (x, y) = (p.0 - middle.0, p.1 - middle.1);
+            (x * along.1 - y * along.0, x * along.0 + y * along.1)
+        };
+        let mut winding = 0;
+        for (&(a, b), step) in windings {
+            if (a, b) == piece || step[owner] == 0 {
+                continue;
             }
-
-            c_entry ^= poly.fst.as_ref().unwrap().is_inside(self);
-            for c in poly.iter() {
-                if c.as_ref().unwrap().inter {
-                    c.as_mut().unwrap().entry = c_entry;
-                    c_entry = !c_entry;
-                }
+            let (pa, pb) = (frame(self.points[a]), frame(self.points[b]));
+            if pa.1 <= 0. && pb.1 > 0. && cross(pa, pb, (0., 0.)) > 0. {
+                winding += step[owner];
+            } else if pb.1 <= 0. && pa.1 > 0. && cross(pa, pb, (0., 0.)) < 0. {
+                winding -= step[owner];
             }
         }
+        winding
     }
 
-    fn phase_three(&mut self) -> Vec<Vec<[f64; 2]>> {
-        let mut list = vec![];
-
-        while self.unprocessed() {
-            unsafe {
-                let mut curr = self.first_intersect();
-                let mut clipped = CPolygon::new();
-                clipped.add(Vertex::clone(curr));
-
-                loop {
-                    curr.as_mut().unwrap().set_checked();
-                    if curr.as_ref().unwrap().entry {
-                        loop {
-                            curr = curr.as_ref().unwrap().next;
-                            clipped.add(Vertex::clone(curr));
-                            if curr.as_ref().unwrap().inter {
-                                break;
-                            }
-                        }
-                    } else {
-                        loop {
-                            curr = curr.as_ref().unwrap().prev;
-                            clipped.add(Vertex::clone(curr));
-                            if curr.as_ref().unwrap().inter {
-                                break;
-                            }
-                        }
+    /// This function joins directed pieces into rings and nests the holes in the exteriors.
+    /// Where several pieces leave a point, the ring turns as far left as it can, so that
+    /// rings touching at a point are kept apart.
+    fn polygons(&self, pieces: &[(usize, usize)]) -> MultiPolygon<f64> {
+        let mut leaving: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (k, &(s, _)) in pieces.iter().enumerate() {
+            leaving.entry(s).or_default().push(k);
+        }
+        let heading = |k: usize| {
+            let (s, e) = (self.points[pieces[k].0], self.points[pieces[k].1]);
+            (e.1 - s.1).atan2(e.0 - s.0)
+        };
+        let mut used = vec![false; pieces.len()];
+        let mut rings = vec![];
+        for first in 0..pieces.len() {
+            if used[first] {
+                continue;
+            }
+            let mut ring = vec![pieces[first].0];
+            let mut current = first;
+            used[first] = true;
+            loop {
+                let (_, end) = pieces[current];
+                let back = heading(current) + PI;
+                let next = leaving.get(&end).and_then(|ks| {
+                    ks.iter()
+                        .cloned()
+                        .filter(|&k| !used[k] || k == first)
+                        .map(|k| (k, (back - heading(k)).rem_euclid(2. * PI)))
+                        .filter(|&(_, turn)| turn > 0.)
+                        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+                        .map(|(k, _)| k)
+                });
+                match next {
+                    Some(k) if k == first => break,
+                    Some(k) => {
+                        ring.push(end);
+                        used[k] = true;
+                        current = k;
                     }
-
-                    curr = curr.as_ref().unwrap().neighbour;
-                    if curr.as_ref().unwrap().checked {
+                    None => {
+                        ring.clear();
                         break;
                     }
                 }
-
-                list.push(clipped.points());
+            }
+            let ring = self.simplify(ring);
+            if ring.len() > 2 {
+                rings.push(ring);
             }
         }
 
-        if list.is_empty() {
-            let mut clipped = vec![];
-            for s in self.iter() {
-                unsafe {
-                    clipped.push(s.as_ref().unwrap().pt);
-                }
+        let (mut exteriors, holes): (Vec<_>, Vec<_>) =
+            rings.into_iter().partition(|r| area(r) > 0.);
+        exteriors.sort_by(|a, b| area(a).partial_cmp(&area(b)).unwrap());
+        let mut interiors: Vec<Vec<LineString<f64>>> = vec![vec![]; exteriors.len()];
+        for hole in holes {
+            // the smallest exterior holding the middle of an edge of the hole
+            let probe = ((hole[0].0 + hole[1].0) / 2., (hole[0].1 + hole[1].1) / 2.);
+            // holes are given anticlockwise too, like those from geo-booleanop
+            if let Some(k) = exteriors.iter().position(|r| contains(r, probe)) {
+                let mut hole = hole;
+                hole.reverse();
+                interiors[k].push(closed(&hole));
             }
-            list.push(clipped);
         }
-
-        list
-    }
-
-    pub fn clip(
-        &mut self,
-        poly: &mut CPolygon,
-        s_entry: bool,
-        c_entry: bool,
-    ) -> Vec<Vec<[f64; 2]>> {
-        self.phase_one(poly);
-        self.phase_two(poly, s_entry, c_entry);
-        self.phase_three()
-    }
-
-    pub fn union(&mut self, poly: &mut CPolygon) -> Vec<Vec<[f64; 2]>> {
-        self.clip(poly, false, false)
-    }
-
-    pub fn intersection(&mut self, poly: &mut CPolygon) -> Vec<Vec<[f64; 2]>> {
-        self.clip(poly, true, true)
+        MultiPolygon(
+            exteriors
+                .iter()
+                .zip(interiors)
+                .map(|(exterior, holes)| Polygon::new(closed(exterior), holes))
+                .collect(),
+        )
     }
 
-    pub fn difference(&mut self, poly: &mut CPolygon) -> Vec<Vec<[f64; 2]>> {
-        self.clip(poly, false, true)
+    /// The points of a ring, leaving out those in line with their neighbours
+    fn simplify(&self, ring: Vec<usize>) -> Vec<(f64, f64)> {
+        let mut points: Vec<(f64, f64)> = ring.iter().map(|&p| self.points[p]).collect();
+        let mut k = 0;
+        while k < points.len() && points.len() > 2 {
+            let n = points.len();
+            let (a, b, c) = (points[(k + n - 1) % n], points[k], points[(k + 1) % n]);
+            if cross(a, b, c).abs() <= self.tolerance * distance(a, c) {
+                points.remove(k);
+                k = k.saturating_sub(1);
+            } else {
+                k += 1;
+            }
+        }
+        points
     }
 }
 
-impl Drop for CPolygon {
-    fn drop(&mut self) {
-        let mut curr = self.fst;
+fn closed(ring: &[(f64, f64)]) -> LineString<f64> {
+    let mut points = ring.to_vec();
+    points.push(ring[0]);
+    LineString::from(points)
+}
 
-        if !curr.is_null() {
-            loop {
-                unsafe {
-                    let next = curr.as_ref().unwrap().next;
-                    Box::from_raw(curr);
-                    curr = next;
-                    if curr == self.fst {
-                        break;
-                    }
-                }
+/// Whether a point lies inside a ring, by the crossing number of a ray to the right. Points
+/// on the ray count as above it.
+fn contains(ring: &[(f64, f64)], p: (f64, f64)) -> bool {
+    let mut inside = false;
+    for i in 0..ring.len() {
+        let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+        if (a.1 <= p.1) != (b.1 <= p.1) {
+            let x = a.0 + (p.1 - a.1) / (b.1 - a.1) * (b.0 - a.0);
+            if x > p.0 {
+                inside = !inside;
             }
         }
     }
+    inside
 }
 
-struct CPolyIter {
-    first: *mut Vertex,
-    curr: *mut Vertex,
-    fst: bool,
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo_booleanop::boolean::BooleanOp;
 
-impl CPolyIter {
-    pub fn new(poly: &CPolygon) -> Self {
-        Self {
-            first: poly.fst,
-            curr: poly.fst,
-            fst: true,
-        }
+    fn ring(points: &[(f64, f64)]) -> LineString<f64> {
+        LineString::from(points.to_vec())
     }
-}
 
-impl Iterator for CPolyIter {
-    type Item = *mut Vertex;
+    fn rectangle(x0: f64, y0: f64, x1: f64, y1: f64) -> MultiPolygon<f64> {
+        MultiPolygon(vec![Polygon::new(
+            ring(&[(x0, y0), (x1, y0), (x1, y1), (x0, y1)]),
+            vec![],
+        )])
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.fst {
-            if self.curr.is_null() {
-                None
-            } else {
-                let i = self.curr;
-                unsafe {
-                    self.curr = self.curr.as_ref().unwrap().next;
-                }
-                self.fst = false;
-                Some(i)
-            }
-        } else {
-            if self.curr == self.first {
-                None
-            } else {
-                let i = self.curr;
-                unsafe {
-                    self.curr = self.curr.as_ref().unwrap().next;
-                }
-                Some(i)
-            }
-        }
+    /// The area of the exteriors less that of the holes, whichever way the rings run
+    fn area(polygons: &MultiPolygon<f64>) -> f64 {
+        let shoelace = |line: &LineString<f64>| {
+            line.0
+                .windows(2)
+                .map(|w| w[0].x * w[1].y - w[1].x * w[0].y)
+                .sum::<f64>()
+                .abs()
+                / 2.
+        };
+        polygons
+            .0
+            .iter()
+            .map(|p| shoelace(p.exterior()) - p.interiors().iter().map(shoelace).sum::<f64>())
+            .sum()
     }
-}
 
-/// Test the intersection of two line and get the intersection point and alphas
-fn intersect(
-    s1: [f64; 2],
-    s2: [f64; 2],
-    c1: [f64; 2],
-    c2: [f64; 2],
-) -> Option<([f64; 2], f64, f64)> {
-    let den = (c2[1] - c1[1]) * (s2[0] - s1[0]) - (c2[0] - c1[0]) * (s2[1] - s1[1]);
-
-    if den == 0. {
-        return None;
+    fn holes(polygons: &MultiPolygon<f64>) -> usize {
+        polygons.0.iter().map(|p| p.interiors().len()).sum()
     }
 
-    let us = ((c2[0] - c1[0]) * (s1[1] - c1[1]) - (c2[1] - c1[1]) * (s1[0] - c1[0])) / den;
-    let uc = ((s2[0] - s1[0]) * (s1[1] - c1[1]) - (s2[1] - s1[1]) * (s1[0] - c1[0])) / den;
+    /// Pairs of polygons meeting in the ways the clipper must handle
+    fn cases() -> Vec<(MultiPolygon<f64>, MultiPolygon<f64>)> {
+        let framed = MultiPolygon(vec![Polygon::new(
+            ring(&[(0., 0.), (10., 0.), (10., 10.), (0., 10.)]),
+            vec![ring(&[(3., 3.), (3., 7.), (7., 7.), (7., 3.)])],
+        )]);
+        vec![
+            // overlapping
+            (rectangle(0., 0., 4., 4.), rectangle(2., 1., 6., 3.)),
+            // sharing an edge
+            (rectangle(0., 0., 4., 4.), rectangle(4., 0., 8., 4.)),
+            // sharing part of an edge and a corner
+            (rectangle(0., 0., 4., 4.), rectangle(4., 2., 8., 4.)),
+            // one inside the other, touching its boundary
+            (rectangle(0., 0., 4., 4.), rectangle(0., 1., 2., 3.)),
+            // apart
+            (rectangle(0., 0., 1., 1.), rectangle(2., 2., 3., 3.)),
+            // across a hole
+            (framed.clone(), rectangle(2., 4., 8., 6.)),
+            // filling a hole exactly
+            (framed, rectangle(3., 3., 7., 7.)),
+        ]
+    }
 
-    if (us == 0. || us == 1.) && (0. <= uc && uc <= 1.)
-        || (uc == 0. || uc == 1.) && (0. <= us && us <= 1.)
-    {
-        // degenerate case
-        return None;
-    } else if (0. < us && us < 1.) && (0. < uc && uc < 1.) {
-        let x = s1[0] + us * (s2[0] - s1[0]);
-        let y = s1[1] + us * (s2[1] - s1[1]);
+    #[test]
+    fn union_matches_booleanop() {
+        for (a, b) in cases() {
+            let ours = union(&a, &b);
+            let theirs = a.union(&b);
+            assert!(
+                (area(&ours) - area(&theirs)).abs() < 1e-9,
+                "{:?} {:?}",
+                a,
+                b
+            );
+            assert_eq!(ours.0.len(), theirs.0.len());
+            assert_eq!(holes(&ours), holes(&theirs));
+        }
+    }
 
-        let pt = [x, y];
-        return Some((pt, us, uc));
+    #[test]
+    fn difference_matches_booleanop() {
+        for (a, b) in cases() {
+            let ours = difference(&a, &b);
+            let theirs = a.difference(&b);
+            assert!(
+                (area(&ours) - area(&theirs)).abs() < 1e-9,
+                "{:?} {:?}",
+                a,
+                b
+            );
+            assert_eq!(ours.0.len(), theirs.0.len());
+            assert_eq!(holes(&ours), holes(&theirs));
+        }
     }
 
-    None
+    #[test]
+    fn intersection_matches_booleanop() {
+        for (a, b) in cases() {
+            let ours = intersection(&a, &b);
+            let theirs = a.intersection(&b);
+            assert!(
+                (area(&ours) - area(&theirs)).abs() < 1e-9,
+                "{:?} {:?}",
+                a,
+                b
+            );
+        }
+    }
 }
@@ -0,0 +1,311 @@
+// Composite steel-concrete beams with a concrete slab on the top flange
+use crate::build::Section;
+use crate::concrete::{Concrete, Transformed};
+use crate::fibre::FibreSection;
+use crate::law::{ElasticPlastic, ParabolaRectangle};
+use crate::properties::{absolute_moment, area_above};
+use geo::{LineString, MultiPolygon, Polygon};
+use serde::Serialize;
+
+/// A steel section acting compositely with a rectangular concrete slab
+pub struct Composite {
+    pub steel: Section,
+    /// Yield strength of the steel section, Fy
+    pub yield_strength: f64,
+    pub concrete: Concrete,
+    /// Thickness of the slab
+    pub thickness: f64,
+    /// Gap between the top of the steel and the underside of the slab, such as a deck
+    pub haunch: f64,
+    /// Effective width of the slab
+    pub width: f64,
+    /// Creep coefficient, multiplying the modular ratio by (1 + creep) for long-term loads
+    pub creep: f64,
+    /// Total strength of the shear connectors, ΣQn, or None for full shear connection
+    pub connection: Option<f64>,
+}
+
+/// The plastic moment of a composite section under sagging bending
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Plastic {
+    pub moment: f64,
+    /// Compression force in the slab
+    pub concrete_force: f64,
+    /// Depth of the concrete stress block, a
+    pub block: f64,
+    /// y coordinate of the plastic neutral axis, in the slab when the steel is all in tension
+    pub axis: f64,
+    /// Ratio of the slab force to that for full shear connection
+    pub connection: f64,
+}
+
+/// This function computes the effective width of a slab on an interior beam as the lesser
+/// of a quarter of the span and the beam spacing (AISC 360-16 I3.1a)
+pub fn effective_width(span: f64, spacing: f64) -> f64 {
+    (span / 4.).min(spacing)
+}
+
+impl Composite {
+    /// This function attaches a slab of `thickness` directly on top of a steel section, with
+    /// its effective width found from the span and the beam spacing
+    pub fn new(
+        steel: Section,
+        yield_strength: f64,
+        concrete: Concrete,
+        thickness: f64,
+        span: f64,
+        spacing: f64,
+    ) -> Composite {
+        Composite {
+            steel,
+            yield_strength,
+            concrete,
+            thickness,
+            haunch: 0.,
+            width: effective_width(span, spacing),
+            creep: 1.,
+            connection: None,
+        }
+    }
+
+    /// y coordinate of the top of the steel section
+    fn steel_top(&self) -> f64 {
+        self.steel
+            .segments()
+            .iter()
+            .flat_map(|seg| seg.corners().to_vec())
+            .map(|c| c.1)
+            .fold(f64::MIN, f64::max)
+    }
+
+    /// The outline of the slab, centred over the steel section
+    pub fn slab(&self) -> Vec<(f64, f64)> {
+        let centre = self.steel.properties().centroid.0;
+        let bottom = self.steel_top() + self.haunch;
+        let top = bottom + self.thickness;
+        let half = self.width / 2.;
+        vec![
+            (centre - half, bottom),
+            (centre + half, bottom),
+            (centre + half, top),
+            (centre - half, top),
+        ]
+    }
+
+    /// Ratio of the steel modulus to the concrete modulus for short-term loads
+    pub fn modular_ratio(&self) -> f64 {
+        self.steel.properties().elastic / self.concrete.elastic
+    }
+
+    /// Transformed properties in units of steel for short-term loads
+    pub fn short_term(&self) -> Transformed {
+        self.transformed(self.modular_ratio())
+    }
+
+    /// Transformed properties in units of steel for long-term loads
+    pub fn long_term(&self) -> Transformed {
+        self.transformed(self.modular_ratio() * (1. + self.creep))
+    }
+
+    /// This function computes the transformed properties for sagging bending with a modular
+    /// ratio, neglecting concrete below the elastic neutral axis
+    pub fn transformed(&self, ratio: f64) -> Transformed {
+        let props = self.steel.properties();
+        let (area, (xs, ys)) = (props.area, props.centroid);
+        let bottom = self.steel_top() + self.haunch;
+        let top = bottom + self.thickness;
+        let width = self.width / ratio;
+
+        // first moment about a trial axis, which decreases as the axis rises
+        let first = |y: f64| {
+            let y0 = y.max(bottom);
+            area * (ys - y) + width * ((top - y).powi(2) - (y0 - y).powi(2)) / 2.
+        };
+        let lowest = self
+            .steel
+            .segments()
+            .iter()
+            .flat_map(|seg| seg.corners().to_vec())
+            .map(|c| c.1)
+            .fold(f64::MAX, f64::min);
+        let (mut low, mut high) = (lowest, top);
+        for _ in 0..100 {
+            let mid = (low + high) / 2.;
+            if first(mid) > 0. {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        let y = (low + high) / 2.;
+        let y0 = y.max(bottom);
+        let slab = width * (top - y0);
+
+        Transformed {
+            area: area + slab,
+            centroid: (
+                (area * xs + slab * xs) / (area + slab),
+                (area * ys + slab * (top + y0) / 2.) / (area + slab),
+            ),
+            inertia: props.ixx
+                + area * (ys - y).powi(2)
+                + width * ((top - y).powi(3) - (y0 - y).powi(3)) / 3.,
+            depth: top - y,
+        }
+    }
+
+    /// This function computes the plastic moment for sagging bending, with the slab force
+    /// limited by the concrete, the steel and the shear connection (AISC 360-16 I3.2a)
+    pub fn plastic_moment(&self) -> Plastic {
+        let segments = self.steel.segments();
+        let steel: f64 = segments.iter().map(|seg| seg.area()).sum();
+        let fy = self.yield_strength;
+        let crushing = 0.85 * self.concrete.strength * self.width * self.thickness;
+        let full = crushing.min(steel * fy);
+        let force = match self.connection {
+            Some(strength) => strength.min(full),
+            None => full,
+        };
+        let block = force / (0.85 * self.concrete.strength * self.width);
+        let slab_top = self.steel_top() + self.haunch + self.thickness;
+
+        // the steel above the axis carries the compression not taken by the slab
+        let compression = ((steel * fy - force) / (2. * fy)).max(0.);
+        let top = self.steel_top();
+        // with no steel in compression the axis lies in the slab, at the foot of the block
+        let axis = if compression <= 0. {
+            slab_top - block
+        } else {
+            let (mut low, mut high) = (
+                segments
+                    .iter()
+                    .flat_map(|seg| seg.corners().to_vec())
+                    .map(|c| c.1)
+                    .fold(f64::MAX, f64::min),
+                top,
+            );
+            for _ in 0..100 {
+                let mid = (low + high) / 2.;
+                if area_above(&segments, |p| p.1, mid) > compression {
+                    low = mid;
+                } else {
+                    high = mid;
+                }
+            }
+            (low + high) / 2.
+        };
+
+        Plastic {
+            moment: fy * absolute_moment(&segments, |p| p.1, axis)
+                + force * (slab_top - block / 2. - axis),
+            concrete_force: force,
+            block,
+            axis,
+            connection: if full > 0. { force / full } else { 0. },
+        }
+    }
+
+    /// This function discretises the steel and the slab into fibres of side `size`, with
+    /// elastic-perfectly plastic steel limited to a strain of 0.05 and parabola-rectangle
+    /// concrete at 0.85 f'c
+    pub fn fibres(&self, size: f64) -> FibreSection {
+        let mut fibres = FibreSection::new();
+        let steel = fibres.add_law(Box::new(ElasticPlastic {
+            elastic: self.steel.properties().elastic,
+            yield_strength: self.yield_strength,
+            ultimate_strain: 0.05,
+        }));
+        let mut law = ParabolaRectangle::new(0.85 * self.concrete.strength);
+        law.ultimate_strain = self.concrete.ultimate_strain;
+        let concrete = fibres.add_law(Box::new(law));
+        fibres.add_outline(&self.steel.build(), size, steel);
+        let slab = MultiPolygon(vec![Polygon::new(LineString::from(self.slab()), vec![])]);
+        fibres.add_outline(&slab, size, concrete);
+        fibres
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog;
+
+    /// A W18X35 of grade 50 steel under a slab of `width` and `thickness`
+    fn beam(width: f64, thickness: f64, strength: f64) -> Composite {
+        let steel = catalog::lookup("W18X35").unwrap().section(0.3);
+        Composite::new(
+            steel,
+            50.,
+            Concrete::ksi(strength),
+            thickness,
+            4. * width,
+            width,
+        )
+    }
+
+    // the plates of a W18X35 at their centrelines, a flange of area 2.55 at ±8.6375 and a
+    // web 0.3 thick between them
+    const H: f64 = (17.7 - 0.425) / 2.;
+    const FLANGE: f64 = 6. * 0.425;
+    const WEB: f64 = 0.3;
+    const STEEL: f64 = 2. * FLANGE + 2. * H * WEB;
+
+    /// Plastic moment of the steel alone about an axis in the web at `y`
+    fn web_moment(y: f64) -> f64 {
+        50. * (FLANGE * (H - y)
+            + WEB * (H - y).powi(2) / 2.
+            + WEB * (H + y).powi(2) / 2.
+            + FLANGE * (H + y))
+    }
+
+    #[test]
+    fn axis_in_the_slab() {
+        // the slab holds the whole yield force of the steel, a = As Fy / 0.85 f'c b and
+        // Mn = As Fy (d / 2 + t - a / 2)
+        let composite = beam(120., 5., 4.);
+        assert_eq!(composite.width, 120.);
+        let plastic = composite.plastic_moment();
+        let force = STEEL * 50.;
+        let block = force / (0.85 * 4. * 120.);
+        let slab_top = H + 0.425 / 2. + 5.;
+        assert!((plastic.concrete_force - force).abs() < 1e-9);
+        assert!((plastic.block - block).abs() < 1e-9);
+        assert!((plastic.axis - (slab_top - block)).abs() < 1e-9);
+        assert!((plastic.moment - force * (slab_top - block / 2.)).abs() < 1e-6);
+        assert_eq!(plastic.connection, 1.);
+    }
+
+    #[test]
+    fn axis_in_the_steel() {
+        // the slab crushes, and the web above the axis makes up the compression
+        let composite = beam(12., 3., 3.);
+        let plastic = composite.plastic_moment();
+        let crushing = 0.85 * 3. * 12. * 3.;
+        let axis = H - ((STEEL * 50. - crushing) / 100. - FLANGE) / WEB;
+        assert!(axis > -H && axis < H);
+        assert!((plastic.concrete_force - crushing).abs() < 1e-9);
+        assert!((plastic.block - 3.).abs() < 1e-9);
+        assert!((plastic.axis - axis).abs() < 1e-9);
+        let slab_top = H + 0.425 / 2. + 3.;
+        let moment = web_moment(axis) + crushing * (slab_top - 1.5 - axis);
+        assert!((plastic.moment - moment).abs() < 1e-6);
+        assert_eq!(plastic.connection, 1.);
+    }
+
+    #[test]
+    fn partial_connection() {
+        // the connectors limit the slab force to ΣQn
+        let mut composite = beam(120., 5., 4.);
+        composite.connection = Some(200.);
+        let plastic = composite.plastic_moment();
+        let block = 200. / (0.85 * 4. * 120.);
+        let axis = H - ((STEEL * 50. - 200.) / 100. - FLANGE) / WEB;
+        assert!((plastic.concrete_force - 200.).abs() < 1e-9);
+        assert!((plastic.block - block).abs() < 1e-9);
+        assert!((plastic.axis - axis).abs() < 1e-9);
+        let slab_top = H + 0.425 / 2. + 5.;
+        let moment = web_moment(axis) + 200. * (slab_top - block / 2. - axis);
+        assert!((plastic.moment - moment).abs() < 1e-6);
+        assert!((plastic.connection - 200. / (STEEL * 50.)).abs() < 1e-12);
+    }
+}
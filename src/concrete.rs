@@ -0,0 +1,478 @@
+// Reinforced concrete sections: transformed properties and flexural strength (ACI 318-19)
+use crate::build::Section;
+use crate::fibre::{clip_half_plane, ring_properties, FibreSection};
+use crate::law::{ElasticPlastic, ParabolaRectangle};
+use geo::{LineString, MultiPolygon, Polygon};
+use serde::{Deserialize, Serialize};
+
+/// Concrete properties
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Concrete {
+    /// Specified compressive strength, f'c
+    pub strength: f64,
+    /// Modulus of elasticity, Ec
+    pub elastic: f64,
+    /// Modulus of rupture, fr
+    pub rupture: f64,
+    /// Ratio of the depth of the equivalent stress block to the neutral axis depth, β1
+    pub beta1: f64,
+    /// Maximum usable compressive strain, εcu
+    pub ultimate_strain: f64,
+}
+
+impl Concrete {
+    /// Normalweight concrete with f'c in N/mm² (ACI 318-19 19.2.2, 19.2.3 and 22.2.2.4.3)
+    pub fn mpa(strength: f64) -> Concrete {
+        Concrete {
+            strength,
+            elastic: 4700. * strength.sqrt(),
+            rupture: 0.62 * strength.sqrt(),
+            beta1: (0.85 - 0.05 * (strength - 28.) / 7.).clamp(0.65, 0.85),
+            ultimate_strain: 0.003,
+        }
+    }
+
+    /// Normalweight concrete with f'c in ksi
+    pub fn ksi(strength: f64) -> Concrete {
+        let psi = strength * 1000.;
+        Concrete {
+            strength,
+            elastic: 57. * psi.sqrt(),
+            rupture: 7.5 * psi.sqrt() / 1000.,
+            beta1: (0.85 - 0.05 * (strength - 4.)).clamp(0.65, 0.85),
+            ultimate_strain: 0.003,
+        }
+    }
+}
+
+/// A solid concrete outline
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Solid {
+    /// The vertices of the outline, without the closing point
+    pub outline: Vec<(f64, f64)>,
+    pub concrete: Concrete,
+}
+
+/// A reinforcing bar, treated as a point area
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Rebar {
+    pub x: f64,
+    pub y: f64,
+    pub area: f64,
+    pub elastic: f64,
+    pub yield_strength: f64,
+}
+
+/// Properties of the transformed section about an axis parallel to the bending axis, in
+/// units of concrete
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Transformed {
+    pub area: f64,
+    pub centroid: (f64, f64),
+    /// Second moment of area about the neutral axis
+    pub inertia: f64,
+    /// Depth of the neutral axis below the extreme compression fibre
+    pub depth: f64,
+}
+
+/// The nominal flexural strength from the equivalent rectangular stress block
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Ultimate {
+    /// Axial force, compression positive
+    pub axial: f64,
+    pub moment: f64,
+    /// Depth of the neutral axis below the extreme compression fibre, c
+    pub depth: f64,
+    /// Depth of the stress block, a
+    pub block: f64,
+    /// Net tensile strain in the extreme tension reinforcement, εt
+    pub tension_strain: f64,
+    /// Strength reduction factor for tied members (ACI 318-19 Table 21.2.2)
+    pub phi: f64,
+}
+
+impl Section {
+    /// This function sets the solid concrete outline of the section
+    pub fn set_solid(&mut self, outline: Vec<(f64, f64)>, concrete: Concrete) {
+        self.solid = Some(Solid { outline, concrete });
+    }
+
+    /// This function adds a reinforcing bar and returns its index
+    pub fn add_bar(&mut self, bar: Rebar) -> usize {
+        self.bars.push(bar);
+        self.bars.len() - 1
+    }
+
+    /// This function adds a layer of `count` bars evenly spaced from `start` to `end`
+    pub fn add_layer(
+        &mut self,
+        start: (f64, f64),
+        end: (f64, f64),
+        count: usize,
+        area: f64,
+        elastic: f64,
+        yield_strength: f64,
+    ) {
+        for i in 0..count {
+            let t = if count > 1 {
+                i as f64 / (count - 1) as f64
+            } else {
+                0.5
+            };
+            self.add_bar(Rebar {
+                x: start.0 + t * (end.0 - start.0),
+                y: start.1 + t * (end.1 - start.1),
+                area,
+                elastic,
+                yield_strength,
+            });
+        }
+    }
+
+    /// This function computes the uncracked transformed properties for bending about an
+    /// axis at `angle` from x, with the fibres to the left of the axis in compression
+    pub fn uncracked(&self, angle: f64) -> Option<Transformed> {
+        let solid = self.solid.as_ref()?;
+        let ec = solid.concrete.elastic;
+        let (area, centre) = ring_properties(&solid.outline);
+        let (_, q, i) = moments(&solid.outline, angle);
+
+        let mut transformed = area;
+        let (mut x, mut y, mut first) = (area * centre.0, area * centre.1, q);
+        for bar in &self.bars {
+            let extra = (bar.elastic / ec - 1.) * bar.area;
+            transformed += extra;
+            x += extra * bar.x;
+            y += extra * bar.y;
+            first += extra * distance(bar.x, bar.y, angle);
+        }
+        let axis = first / transformed;
+        let inertia = i - 2. * axis * q
+            + axis * axis * area
+            + self
+                .bars
+                .iter()
+                .map(|bar| {
+                    (bar.elastic / ec - 1.)
+                        * bar.area
+                        * (distance(bar.x, bar.y, angle) - axis).powi(2)
+                })
+                .sum::<f64>();
+
+        Some(Transformed {
+            area: transformed,
+            centroid: (x / transformed, y / transformed),
+            inertia,
+            depth: top(&solid.outline, angle) - axis,
+        })
+    }
+
+    /// This function computes the cracked transformed properties, neglecting concrete in
+    /// tension. Returns None if there is no concrete or no reinforcement.
+    pub fn cracked(&self, angle: f64) -> Option<Transformed> {
+        let solid = self.solid.as_ref()?;
+        if self.bars.is_empty() {
+            return None;
+        }
+        let ec = solid.concrete.elastic;
+        let ratio = |bar: &Rebar, axis: f64| {
+            if distance(bar.x, bar.y, angle) > axis {
+                bar.elastic / ec - 1.
+            } else {
+                bar.elastic / ec
+            }
+        };
+        // first moment of the transformed area about a trial neutral axis, which
+        // decreases as the axis rises
+        let first = |axis: f64| {
+            let (area, q, _) = moments(&compression(&solid.outline, angle, axis), angle);
+            q - axis * area
+                + self
+                    .bars
+                    .iter()
+                    .map(|bar| ratio(bar, axis) * bar.area * (distance(bar.x, bar.y, angle) - axis))
+                    .sum::<f64>()
+        };
+
+        let upper = top(&solid.outline, angle);
+        let (mut low, mut high) = (bottom(&solid.outline, angle), upper);
+        for _ in 0..100 {
+            let mid = (low + high) / 2.;
+            if first(mid) > 0. {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        let axis = (low + high) / 2.;
+
+        let zone = compression(&solid.outline, angle, axis);
+        let (area, centre) = ring_properties(&zone);
+        let (_, q, i) = moments(&zone, angle);
+        let mut transformed = area;
+        let (mut x, mut y) = (area * centre.0, area * centre.1);
+        let mut inertia = i - 2. * axis * q + axis * axis * area;
+        for bar in &self.bars {
+            let weight = ratio(bar, axis) * bar.area;
+            transformed += weight;
+            x += weight * bar.x;
+            y += weight * bar.y;
+            inertia += weight * (distance(bar.x, bar.y, angle) - axis).powi(2);
+        }
+
+        Some(Transformed {
+            area: transformed,
+            centroid: (x / transformed, y / transformed),
+            inertia,
+            depth: upper - axis,
+        })
+    }
+
+    /// This function computes the cracking moment from the modulus of rupture and the
+    /// uncracked transformed section
+    pub fn cracking_moment(&self, angle: f64) -> Option<f64> {
+        let solid = self.solid.as_ref()?;
+        let props = self.uncracked(angle)?;
+        let axis = top(&solid.outline, angle) - props.depth;
+        Some(solid.concrete.rupture * props.inertia / (axis - bottom(&solid.outline, angle)))
+    }
+
+    /// This function computes the nominal strength under an axial force (compression
+    /// positive) using the equivalent rectangular stress block (ACI 318-19 22.2), with
+    /// moments taken about the centroid of the gross concrete section. Returns None if the
+    /// axial force exceeds the axial strength in tension or compression.
+    pub fn ultimate(&self, axial: f64, angle: f64) -> Option<Ultimate> {
+        let solid = self.solid.as_ref()?;
+        let concrete = solid.concrete;
+        let upper = top(&solid.outline, angle);
+        let height = upper - bottom(&solid.outline, angle);
+        let (_, centre) = ring_properties(&solid.outline);
+        let reference = distance(centre.0, centre.1, angle);
+
+        // axial force and moment for a neutral axis depth c
+        let forces = |c: f64| {
+            let axis = upper - c;
+            let block = upper - concrete.beta1 * c;
+            let zone = compression(&solid.outline, angle, block);
+            let (area, q, _) = moments(&zone, angle);
+            let force = 0.85 * concrete.strength * area;
+            let mut axial = force;
+            let mut moment = 0.85 * concrete.strength * (q - reference * area);
+            for bar in &self.bars {
+                let d = distance(bar.x, bar.y, angle);
+                let strain = concrete.ultimate_strain * (axis - d) / c;
+                let mut stress =
+                    (bar.elastic * strain).clamp(-bar.yield_strength, bar.yield_strength);
+                if d >= block && stress < 0. {
+                    // the bar displaces concrete within the stress block
+                    stress += 0.85 * concrete.strength;
+                }
+                axial -= stress * bar.area;
+                moment -= stress * bar.area * (d - reference);
+            }
+            (axial, moment)
+        };
+
+        let (mut low, mut high) = (1e-9 * height, 100. * height);
+        if forces(low).0 > axial || forces(high).0 < axial {
+            return None;
+        }
+        for _ in 0..100 {
+            let mid = (low + high) / 2.;
+            if forces(mid).0 < axial {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        let c = (low + high) / 2.;
+        let (axial, moment) = forces(c);
+
+        let axis = upper - c;
+        let extreme = self.bars.iter().min_by(|a, b| {
+            distance(a.x, a.y, angle)
+                .partial_cmp(&distance(b.x, b.y, angle))
+                .unwrap()
+        });
+        let (tension_strain, phi) = match extreme {
+            Some(bar) => {
+                let strain = concrete.ultimate_strain * (axis - distance(bar.x, bar.y, angle)) / c;
+                let yield_strain = bar.yield_strength / bar.elastic;
+                (
+                    strain,
+                    (0.65 + 0.25 * (strain - yield_strain) / 0.003).clamp(0.65, 0.9),
+                )
+            }
+            None => (0., 0.65),
+        };
+
+        Some(Ultimate {
+            axial,
+            moment,
+            depth: c,
+            block: (concrete.beta1 * c).min(height),
+            tension_strain,
+            phi,
+        })
+    }
+
+    /// This function discretises the concrete into fibres of side `size` following the
+    /// parabola-rectangle law at 0.85 f'c, with the bars as elastic-perfectly plastic fibres
+    /// limited to a strain of 0.05. Each bar also carries a concrete fibre of negative area,
+    /// so the concrete it displaces is not counted twice. Returns None if there is no
+    /// concrete outline.
+    pub fn concrete_fibres(&self, size: f64) -> Option<FibreSection> {
+        let solid = self.solid.as_ref()?;
+        let mut fibres = FibreSection::new();
+        let mut law = ParabolaRectangle::new(0.85 * solid.concrete.strength);
+        law.ultimate_strain = solid.concrete.ultimate_strain;
+        let concrete = fibres.add_law(Box::new(law));
+        let outline = MultiPolygon(vec![Polygon::new(
+            LineString::from(solid.outline.clone()),
+            vec![],
+        )]);
+        fibres.add_outline(&outline, size, concrete);
+        for bar in &self.bars {
+            let steel = fibres.add_law(Box::new(ElasticPlastic {
+                elastic: bar.elastic,
+                yield_strength: bar.yield_strength,
+                ultimate_strain: 0.05,
+            }));
+            fibres.add_fibre(bar.x, bar.y, bar.area, steel);
+            fibres.add_fibre(bar.x, bar.y, -bar.area, concrete);
+        }
+        Some(fibres)
+    }
+}
+
+/// Distance of a point from the bending axis through the origin, positive to its left
+fn distance(x: f64, y: f64, angle: f64) -> f64 {
+    -x * angle.sin() + y * angle.cos()
+}
+
+fn top(outline: &[(f64, f64)], angle: f64) -> f64 {
+    outline
+        .iter()
+        .map(|p| distance(p.0, p.1, angle))
+        .fold(f64::MIN, f64::max)
+}
+
+fn bottom(outline: &[(f64, f64)], angle: f64) -> f64 {
+    outline
+        .iter()
+        .map(|p| distance(p.0, p.1, angle))
+        .fold(f64::MAX, f64::min)
+}
+
+/// The part of an outline lying at or beyond `axis` from the bending axis
+fn compression(outline: &[(f64, f64)], angle: f64, axis: f64) -> Vec<(f64, f64)> {
+    clip_half_plane(outline, (angle.sin(), -angle.cos()), -axis)
+}
+
+/// This function computes the area and the first and second moments of area of a ring
+/// about the bending axis through the origin
+fn moments(points: &[(f64, f64)], angle: f64) -> (f64, f64, f64) {
+    let (mut area, mut first, mut second) = (0., 0., 0.);
+    let rotate = |p: (f64, f64)| {
+        (
+            p.0 * angle.cos() + p.1 * angle.sin(),
+            distance(p.0, p.1, angle),
+        )
+    };
+    for i in 0..points.len() {
+        let (u0, d0) = rotate(points[i]);
+        let (u1, d1) = rotate(points[(i + 1) % points.len()]);
+        let cross = u0 * d1 - u1 * d0;
+        area += cross / 2.;
+        first += cross * (d0 + d1) / 6.;
+        second += cross * (d0 * d0 + d0 * d1 + d1 * d1) / 12.;
+    }
+    if area < 0. {
+        (-area, -first, -second)
+    } else {
+        (area, first, second)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A beam 12 wide and 24 deep in 4 ksi concrete, with grade 60 bars of `area` at a
+    /// depth of 21.5
+    fn beam(area: f64) -> Section {
+        let mut section = Section::new(0.2);
+        section.set_solid(
+            vec![(0., 0.), (12., 0.), (12., 24.), (0., 24.)],
+            Concrete::ksi(4.),
+        );
+        section.add_bar(Rebar {
+            x: 6.,
+            y: 2.5,
+            area,
+            elastic: 29000.,
+            yield_strength: 60.,
+        });
+        section
+    }
+
+    #[test]
+    fn stress_block_factors() {
+        assert_eq!(Concrete::ksi(4.).beta1, 0.85);
+        assert!((Concrete::ksi(5.).beta1 - 0.80).abs() < 1e-12);
+        assert_eq!(Concrete::ksi(8.).beta1, 0.65);
+        assert_eq!(Concrete::mpa(28.).beta1, 0.85);
+        assert!((Concrete::mpa(35.).beta1 - 0.80).abs() < 1e-12);
+        assert_eq!(Concrete::mpa(56.).beta1, 0.65);
+    }
+
+    #[test]
+    fn singly_reinforced_beam() {
+        // a = As fy / 0.85 f'c b, Mn = As fy (d - a / 2)
+        let (b, d, area, fy, fc) = (12., 21.5, 3., 60., 4.);
+        let a = area * fy / (0.85 * fc * b);
+        let ultimate = beam(area).ultimate(0., 0.).unwrap();
+        assert!(ultimate.axial.abs() < 1e-9);
+        assert!((ultimate.block - a).abs() < 1e-9);
+        assert!((ultimate.depth - a / 0.85).abs() < 1e-9);
+        assert!((ultimate.moment - area * fy * (d - a / 2.)).abs() < 1e-6);
+        // tension controlled, εt = 0.003 (d - c) / c beyond 0.005
+        let c = a / 0.85;
+        assert!((ultimate.tension_strain - 0.003 * (d - c) / c).abs() < 1e-12);
+        assert_eq!(ultimate.phi, 0.9);
+
+        // heavily reinforced, the bars do not yield and the section is compression controlled
+        let heavy = beam(12.).ultimate(0., 0.).unwrap();
+        assert!(heavy.tension_strain < 60. / 29000.);
+        assert_eq!(heavy.phi, 0.65);
+    }
+
+    #[test]
+    fn cracked_and_uncracked() {
+        let section = beam(3.);
+        let concrete = Concrete::ksi(4.);
+        let n = 29000. / concrete.elastic;
+        let (b, d, area) = (12., 21.5, 3.);
+
+        // b (kd)² / 2 = n As (d - kd)
+        let kd = (-n * area + ((n * area).powi(2) + 2. * b * n * area * d).sqrt()) / b;
+        let cracked = section.cracked(0.).unwrap();
+        assert!((cracked.depth - kd).abs() < 1e-9);
+        let inertia = b * kd.powi(3) / 3. + n * area * (d - kd).powi(2);
+        assert!((cracked.inertia - inertia).abs() < 1e-6);
+
+        // the bars add (n - 1) As to the gross section
+        let extra = (n - 1.) * area;
+        let transformed = b * 24. + extra;
+        let y = (b * 24. * 12. + extra * 2.5) / transformed;
+        let uncracked = section.uncracked(0.).unwrap();
+        assert!((uncracked.area - transformed).abs() < 1e-9);
+        assert!((uncracked.centroid.1 - y).abs() < 1e-9);
+        assert!((uncracked.depth - (24. - y)).abs() < 1e-9);
+        let inertia =
+            b * 24f64.powi(3) / 12. + b * 24. * (12. - y).powi(2) + extra * (y - 2.5).powi(2);
+        assert!((uncracked.inertia - inertia).abs() < 1e-6);
+        let mcr = section.cracking_moment(0.).unwrap();
+        assert!((mcr - concrete.rupture * inertia / y).abs() < 1e-6);
+    }
+}
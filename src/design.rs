@@ -0,0 +1,43 @@
+// Types shared by the design code modules
+use serde::Serialize;
+
+/// Required strengths, compression and sagging moments positive
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct Demands {
+    pub axial: f64,
+    pub moment_x: f64,
+    pub moment_y: f64,
+    pub shear: f64,
+}
+
+/// How a section was made, which decides some resistance factors and buckling curves
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Fabrication {
+    /// Hot rolled, as the shapes of the catalogue
+    Rolled,
+    /// Welded from plates
+    Welded,
+}
+
+/// One clause of the check with its intermediate values
+#[derive(Clone, Debug, Serialize)]
+pub struct Entry {
+    pub clause: String,
+    pub title: String,
+    #[serde(serialize_with = "crate::results::pairs")]
+    pub values: Vec<(String, f64)>,
+    /// Demand to design strength ratio, for clauses that give a strength
+    pub ratio: Option<f64>,
+}
+
+impl Entry {
+    pub fn new(clause: &str, title: &str, values: &[(&str, f64)], ratio: Option<f64>) -> Entry {
+        Entry {
+            clause: clause.to_string(),
+            title: title.to_string(),
+            values: values.iter().map(|v| (v.0.to_string(), v.1)).collect(),
+            ratio,
+        }
+    }
+}
@@ -0,0 +1,935 @@
+// SVG and PNG drawings of sections and beams
+use crate::beam::{Analysis, Beam, Station, Support};
+use crate::build::Section;
+use crate::fibre::{clip_half_plane, ring, ring_properties};
+use crate::load::Load;
+use crate::raster::{Colour, Image};
+use crate::stress::{Component, LoadState, StressField};
+use geo::MultiPolygon;
+use std::fmt::Write as FmtWrite;
+use std::fs::File;
+use std::io::{self, prelude::*};
+use std::path::Path;
+
+/// A drawing canvas mapping model coordinates onto an SVG image with y pointing up
+pub struct Svg {
+    pub width: f64,
+    pub height: f64,
+    /// Model coordinates of the lower left corner of the drawing area
+    origin: (f64, f64),
+    /// Pixels per model unit
+    scale: f64,
+    margin: f64,
+    elements: Vec<String>,
+}
+
+impl Svg {
+    /// This function fits the model bounds `(xmin, ymin, xmax, ymax)` into an image `width`
+    /// pixels wide, keeping the aspect ratio
+    pub fn new(bounds: (f64, f64, f64, f64), width: f64, margin: f64) -> Svg {
+        let (xmin, ymin, xmax, ymax) = bounds;
+        let span = (xmax - xmin).max(f64::EPSILON);
+        let scale = (width - 2. * margin) / span;
+        Svg {
+            width,
+            height: (ymax - ymin) * scale + 2. * margin,
+            origin: (xmin, ymin),
+            scale,
+            margin,
+            elements: vec![],
+        }
+    }
+
+    /// This function creates an image of a given size in pixels, with the origin at the
+    /// lower left corner and y pointing up
+    pub fn blank(width: f64, height: f64) -> Svg {
+        Svg {
+            width,
+            height,
+            origin: (0., 0.),
+            scale: 1.,
+            margin: 0.,
+            elements: vec![],
+        }
+    }
+
+    /// This function maps a model point onto the image
+    pub fn map(&self, p: (f64, f64)) -> (f64, f64) {
+        (
+            self.margin + (p.0 - self.origin.0) * self.scale,
+            self.height - self.margin - (p.1 - self.origin.1) * self.scale,
+        )
+    }
+
+    /// This function maps a point on the image back to model coordinates
+    pub fn unmap(&self, p: (f64, f64)) -> (f64, f64) {
+        (
+            self.origin.0 + (p.0 - self.margin) / self.scale,
+            self.origin.1 + (self.height - self.margin - p.1) / self.scale,
+        )
+    }
+
+    /// This function converts a model length to pixels
+    pub fn length(&self, length: f64) -> f64 {
+        length * self.scale
+    }
+
+    /// This function adds raw SVG markup
+    pub fn push(&mut self, element: String) {
+        self.elements.push(element);
+    }
+
+    pub fn line(&mut self, a: (f64, f64), b: (f64, f64), stroke: &str, width: f64) {
+        let (a, b) = (self.map(a), self.map(b));
+        self.push(format!(
+            "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"{}\" stroke-width=\"{:.2}\"/>",
+            a.0, a.1, b.0, b.1, stroke, width
+        ));
+    }
+
+    pub fn polyline(&mut self, points: &[(f64, f64)], stroke: &str, width: f64) {
+        let path = self.points(points);
+        self.push(format!(
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{:.2}\"/>",
+            path, stroke, width
+        ));
+    }
+
+    pub fn polygon(&mut self, points: &[(f64, f64)], fill: &str, stroke: &str, width: f64) {
+        let path = self.points(points);
+        self.push(format!(
+            "<polygon points=\"{}\" fill=\"{}\" stroke=\"{}\" stroke-width=\"{:.2}\"/>",
+            path, fill, stroke, width
+        ));
+    }
+
+    /// This function draws rings as one shape filled by the even-odd rule, so that rings
+    /// inside others are left empty
+    pub fn path(&mut self, rings: &[Vec<(f64, f64)>], fill: &str, stroke: &str, width: f64) {
+        let path = rings
+            .iter()
+            .filter(|ring| !ring.is_empty())
+            .map(|ring| format!("M {} Z", self.points(ring)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        self.push(format!(
+            "<path d=\"{}\" fill=\"{}\" fill-rule=\"evenodd\" stroke=\"{}\" stroke-width=\"{:.2}\"/>",
+            path, fill, stroke, width
+        ));
+    }
+
+    /// This function draws a circle with a radius in pixels
+    pub fn circle(&mut self, centre: (f64, f64), radius: f64, fill: &str, stroke: &str) {
+        let c = self.map(centre);
+        self.push(format!(
+            "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{:.2}\" fill=\"{}\" stroke=\"{}\"/>",
+            c.0, c.1, radius, fill, stroke
+        ));
+    }
+
+    /// This function writes text offset from a model point by `offset` pixels
+    pub fn text(&mut self, at: (f64, f64), offset: (f64, f64), text: &str, size: f64, fill: &str) {
+        let p = self.map(at);
+        self.push(format!(
+            "<text x=\"{:.2}\" y=\"{:.2}\" font-family=\"sans-serif\" font-size=\"{}\" fill=\"{}\">{}</text>",
+            p.0 + offset.0,
+            p.1 + offset.1,
+            size,
+            fill,
+            text
+        ));
+    }
+
+    fn points(&self, points: &[(f64, f64)]) -> String {
+        let mut path = String::new();
+        for p in points {
+            let p = self.map(*p);
+            write!(path, "{:.2},{:.2} ", p.0, p.1).unwrap();
+        }
+        path.trim_end().to_string()
+    }
+
+    /// This function renders the document
+    pub fn render(&self) -> String {
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\" viewBox=\"0 0 {:.0} {:.0}\">\n<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n",
+            self.width, self.height, self.width, self.height
+        );
+        for element in &self.elements {
+            svg.push_str(element);
+            svg.push('\n');
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// This function writes the document to a file
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        File::create(path)?.write_all(self.render().as_bytes())?;
+        println!("successfully wrote to {}", path.display());
+        Ok(())
+    }
+}
+
+/// This function finds the bounds of an outline and the node graph of a section
+pub fn bounds(section: &Section, outline: &MultiPolygon<f64>) -> (f64, f64, f64, f64) {
+    let outline_points = outline
+        .0
+        .iter()
+        .flat_map(|polygon| polygon.exterior().0.iter().map(|c| (c.x, c.y)));
+    let node_points = section.graph.node_indices().map(|n| {
+        let node = &section.graph[n];
+        (node.x, node.y)
+    });
+    outline_points
+        .chain(node_points)
+        .fold((f64::MAX, f64::MAX, f64::MIN, f64::MIN), |acc, p| {
+            (
+                acc.0.min(p.0),
+                acc.1.min(p.1),
+                acc.2.max(p.0),
+                acc.3.max(p.1),
+            )
+        })
+}
+
+/// This function draws the outline of a section, with its holes
+pub fn draw_outline(svg: &mut Svg, outline: &MultiPolygon<f64>, fill: &str) {
+    for polygon in &outline.0 {
+        let rings: Vec<Vec<(f64, f64)>> = std::iter::once(polygon.exterior())
+            .chain(polygon.interiors())
+            .map(ring)
+            .collect();
+        svg.path(&rings, fill, "black", 1.);
+    }
+}
+
+impl Section {
+    /// This function draws the outline, the node graph with node indices and plate
+    /// thicknesses, the centroid with the principal axes and the shear centre
+    pub fn draw(&self, width: f64) -> Svg {
+        let outline = self.build();
+        let mut svg = Svg::new(bounds(self, &outline), width, 40.);
+        draw_outline(&mut svg, &outline, "#d9d9d9");
+
+        for edge in self.graph.edge_indices() {
+            let (a, b) = self.graph.edge_endpoints(edge).unwrap();
+            let (a, b) = (&self.graph[a], &self.graph[b]);
+            let thickness = self.graph[edge].thickness;
+            svg.line((a.x, a.y), (b.x, b.y), "#1f77b4", 1.5);
+            svg.text(
+                ((a.x + b.x) / 2., (a.y + b.y) / 2.),
+                (4., -4.),
+                &format!("t={}", thickness),
+                10.,
+                "#1f77b4",
+            );
+        }
+        for node in self.graph.node_indices() {
+            let n = &self.graph[node];
+            svg.circle((n.x, n.y), 3., "#1f77b4", "none");
+            svg.text(
+                (n.x, n.y),
+                (5., 12.),
+                &node.index().to_string(),
+                11.,
+                "black",
+            );
+        }
+
+        if self.graph.edge_count() > 0 {
+            let props = self.properties();
+            let (xmin, ymin, xmax, ymax) = bounds(self, &outline);
+            let reach = 0.6 * (xmax - xmin).max(ymax - ymin);
+            let c = props.centroid;
+            for (angle, label) in &[
+                (props.phi, "1"),
+                (props.phi + std::f64::consts::FRAC_PI_2, "2"),
+            ] {
+                let d = (reach * angle.cos(), reach * angle.sin());
+                svg.line(
+                    (c.0 - d.0, c.1 - d.1),
+                    (c.0 + d.0, c.1 + d.1),
+                    "#2ca02c",
+                    1.,
+                );
+                svg.text((c.0 + d.0, c.1 + d.1), (4., 0.), label, 11., "#2ca02c");
+            }
+            svg.circle(c, 5., "none", "#2ca02c");
+            svg.text(c, (6., -6.), "C", 11., "#2ca02c");
+            svg.circle(props.shear_centre, 4., "#d62728", "none");
+            svg.text(props.shear_centre, (6., 12.), "S", 11., "#d62728");
+        }
+        svg
+    }
+}
+
+/// This function maps a value scaled to 0..1 onto a colour, diverging from blue through
+/// white to red for signed quantities and running from blue through yellow to red otherwise
+pub fn colour_map(t: f64, signed: bool) -> Colour {
+    let t = t.clamp(0., 1.);
+    let stops: &[(f64, [f64; 3])] = if signed {
+        &[
+            (0., [49., 54., 149.]),
+            (0.5, [255., 255., 255.]),
+            (1., [165., 0., 38.]),
+        ]
+    } else {
+        &[
+            (0., [49., 54., 149.]),
+            (0.33, [116., 173., 209.]),
+            (0.66, [254., 224., 144.]),
+            (1., [215., 48., 39.]),
+        ]
+    };
+    let i = stops
+        .iter()
+        .rposition(|s| s.0 <= t)
+        .unwrap()
+        .min(stops.len() - 2);
+    let (a, b) = (stops[i], stops[i + 1]);
+    let f = (t - a.0) / (b.0 - a.0);
+    let mix = |k: usize| (a.1[k] + f * (b.1[k] - a.1[k])).round() as u8;
+    [mix(0), mix(1), mix(2)]
+}
+
+fn hex(colour: Colour) -> String {
+    format!("#{:02x}{:02x}{:02x}", colour[0], colour[1], colour[2])
+}
+
+fn label(component: Component) -> &'static str {
+    match component {
+        Component::Normal => "Normal stress",
+        Component::Shear => "Shear stress",
+        Component::VonMises => "von Mises stress",
+    }
+}
+
+/// The part of the outline in a cell of a contour, as its exterior and holes
+type Cell = Vec<Vec<(f64, f64)>>;
+
+/// A stress field evaluated over the cells of a grid covering the outline
+struct Contour {
+    field: StressField,
+    cells: Vec<(Cell, f64)>,
+    /// Range of the colour scale
+    range: (f64, f64),
+    signed: bool,
+}
+
+impl Contour {
+    fn new(
+        section: &Section,
+        outline: &MultiPolygon<f64>,
+        load: LoadState,
+        component: Component,
+    ) -> Contour {
+        let field = section.stresses(load);
+        let (xmin, ymin, xmax, ymax) = bounds(section, outline);
+        let size = (xmax - xmin).max(ymax - ymin) / 100.;
+        let mut cells = vec![];
+        for polygon in &outline.0 {
+            let rings: Vec<Vec<(f64, f64)>> = std::iter::once(polygon.exterior())
+                .chain(polygon.interiors())
+                .map(ring)
+                .collect();
+            let nx = ((xmax - xmin) / size).ceil() as usize;
+            let ny = ((ymax - ymin) / size).ceil() as usize;
+            for i in 0..nx {
+                for j in 0..ny {
+                    let (x0, y0) = (xmin + i as f64 * size, ymin + j as f64 * size);
+                    let cell: Cell = rings
+                        .iter()
+                        .map(|ring| {
+                            let ring = clip_half_plane(ring, (-1., 0.), -x0);
+                            let ring = clip_half_plane(&ring, (1., 0.), x0 + size);
+                            let ring = clip_half_plane(&ring, (0., -1.), -y0);
+                            clip_half_plane(&ring, (0., 1.), y0 + size)
+                        })
+                        .collect();
+                    // the holes are taken away from the exterior
+                    let (mut area, mut moment) = (0., (0., 0.));
+                    for (k, ring) in cell.iter().enumerate() {
+                        let (a, centre) = ring_properties(ring);
+                        let a = if k == 0 { a } else { -a };
+                        area += a;
+                        moment = (moment.0 + a * centre.0, moment.1 + a * centre.1);
+                    }
+                    if area > 1e-9 * size * size {
+                        let value = field.value(component, moment.0 / area, moment.1 / area);
+                        cells.push((cell, value));
+                    }
+                }
+            }
+        }
+        let signed = component == Component::Normal;
+        let extreme = cells.iter().map(|c| c.1.abs()).fold(0., f64::max);
+        let range = if signed {
+            (-extreme, extreme)
+        } else {
+            (0., extreme)
+        };
+        Contour {
+            field,
+            cells,
+            range,
+            signed,
+        }
+    }
+
+    fn colour(&self, value: f64) -> Colour {
+        let span = self.range.1 - self.range.0;
+        let t = if span > 0. {
+            (value - self.range.0) / span
+        } else {
+            0.5
+        };
+        colour_map(t, self.signed)
+    }
+}
+
+/// This function tests whether a point lies inside an outline, by the even-odd rule
+fn inside(outline: &MultiPolygon<f64>, p: (f64, f64)) -> bool {
+    let mut inside = false;
+    for polygon in &outline.0 {
+        for line in std::iter::once(polygon.exterior()).chain(polygon.interiors()) {
+            let points = ring(line);
+            for i in 0..points.len() {
+                let (a, b) = (points[i], points[(i + 1) % points.len()]);
+                if (a.1 > p.1) != (b.1 > p.1) && p.0 < a.0 + (p.1 - a.1) * (b.0 - a.0) / (b.1 - a.1)
+                {
+                    inside = !inside;
+                }
+            }
+        }
+    }
+    inside
+}
+
+impl Section {
+    /// This function draws the section coloured by a stress component under a load state,
+    /// with a colour legend and the neutral axis
+    pub fn draw_stress(&self, load: LoadState, component: Component, width: f64) -> Svg {
+        let outline = self.build();
+        let (xmin, ymin, xmax, ymax) = bounds(self, &outline);
+        // leave room on the right for the legend
+        let legend = 0.35 * (xmax - xmin).max(ymax - ymin);
+        let mut svg = Svg::new((xmin, ymin, xmax + legend, ymax), width, 40.);
+        let contour = Contour::new(self, &outline, load, component);
+
+        for (cell, value) in &contour.cells {
+            let fill = hex(contour.colour(*value));
+            svg.path(cell, &fill, &fill, 0.5);
+        }
+        draw_outline(&mut svg, &outline, "none");
+
+        if component == Component::Normal {
+            if let Some((point, direction)) = contour.field.neutral_axis() {
+                let reach = (xmax - xmin).hypot(ymax - ymin);
+                let a = (point.0 - reach * direction.0, point.1 - reach * direction.1);
+                let b = (point.0 + reach * direction.0, point.1 + reach * direction.1);
+                let (a, b) = (svg.map(a), svg.map(b));
+                svg.push(format!(
+                    "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"black\" stroke-width=\"1\" stroke-dasharray=\"6,3\"/>",
+                    a.0, a.1, b.0, b.1
+                ));
+            }
+        }
+
+        // legend
+        let left = svg.map((xmax, ymax)).0 + 0.3 * svg.length(legend);
+        let (top, height) = (40., svg.height - 80.);
+        let steps = 50;
+        for i in 0..steps {
+            let t = 1. - (i as f64 + 0.5) / steps as f64;
+            let value = contour.range.0 + t * (contour.range.1 - contour.range.0);
+            svg.push(format!(
+                "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"16\" height=\"{:.2}\" fill=\"{}\"/>",
+                left,
+                top + height * i as f64 / steps as f64,
+                height / steps as f64 + 0.5,
+                hex(contour.colour(value))
+            ));
+        }
+        for i in 0..=4 {
+            let t = i as f64 / 4.;
+            let value = contour.range.1 - t * (contour.range.1 - contour.range.0);
+            svg.push(format!(
+                "<text x=\"{:.2}\" y=\"{:.2}\" font-family=\"sans-serif\" font-size=\"10\">{:.4e}</text>",
+                left + 20.,
+                top + t * height + 4.,
+                value
+            ));
+        }
+        svg.push(format!(
+            "<text x=\"40\" y=\"20\" font-family=\"sans-serif\" font-size=\"13\">{}</text>",
+            label(component)
+        ));
+        svg
+    }
+
+    /// This function rasterises the section coloured by a stress component under a load
+    /// state, with a colour legend and the neutral axis
+    pub fn stress_image(&self, load: LoadState, component: Component, width: usize) -> Image {
+        let outline = self.build();
+        let (xmin, ymin, xmax, ymax) = bounds(self, &outline);
+        let legend = 0.35 * (xmax - xmin).max(ymax - ymin);
+        let frame = Svg::new((xmin, ymin, xmax + legend, ymax), width as f64, 40.);
+        let contour = Contour::new(self, &outline, load, component);
+        let mut image = Image::new(width, frame.height.ceil() as usize, [255, 255, 255]);
+
+        for j in 0..image.height {
+            for i in 0..image.width {
+                let p = frame.unmap((i as f64 + 0.5, j as f64 + 0.5));
+                if inside(&outline, p) {
+                    let value = contour.field.value(component, p.0, p.1);
+                    image.set(i as i64, j as i64, contour.colour(value));
+                }
+            }
+        }
+        let pixel = |p: (f64, f64)| {
+            let q = frame.map(p);
+            (q.0.round() as i64, q.1.round() as i64)
+        };
+        for polygon in &outline.0 {
+            for line in std::iter::once(polygon.exterior()).chain(polygon.interiors()) {
+                let points = ring(line);
+                for k in 0..points.len() {
+                    let (a, b) = (points[k], points[(k + 1) % points.len()]);
+                    image.line(pixel(a), pixel(b), [0, 0, 0]);
+                }
+            }
+        }
+        if component == Component::Normal {
+            if let Some((point, direction)) = contour.field.neutral_axis() {
+                let reach = (xmax - xmin).hypot(ymax - ymin);
+                let a = pixel((point.0 - reach * direction.0, point.1 - reach * direction.1));
+                let b = pixel((point.0 + reach * direction.0, point.1 + reach * direction.1));
+                image.line(a, b, [0, 0, 0]);
+            }
+        }
+
+        let left = (frame.map((xmax, ymax)).0 + 0.3 * frame.length(legend)) as i64;
+        let (top, height) = (40, image.height as i64 - 80);
+        for j in 0..height {
+            let t = 1. - j as f64 / height as f64;
+            let value = contour.range.0 + t * (contour.range.1 - contour.range.0);
+            image.fill_rect(left, top + j, 16, 1, contour.colour(value));
+        }
+        for k in 0..=4 {
+            let t = k as f64 / 4.;
+            let value = contour.range.1 - t * (contour.range.1 - contour.range.0);
+            let y = top + (t * height as f64) as i64 - 5;
+            image.text(left + 22, y, &format!("{:.3e}", value), 2, [0, 0, 0]);
+        }
+        image
+    }
+}
+
+/// Left and right edges of the plotting area of beam figures, in pixels
+const PLOT_LEFT: f64 = 70.;
+const PLOT_RIGHT: f64 = 30.;
+/// Heights of the beam schematic and of each diagram, in pixels
+const SCHEMATIC: f64 = 150.;
+const PANEL: f64 = 160.;
+
+/// A diagram of a beam: its title, the plotted quantity and the colour
+type Panel = (&'static str, fn(&Station) -> f64, &'static str);
+
+impl Beam {
+    /// This function draws the beam with its supports and loads above the shear, moment,
+    /// slope and deflection diagrams of an analysis, with the maxima annotated
+    pub fn draw(&self, analysis: &Analysis, width: f64) -> Svg {
+        let mut svg = Svg::blank(width, SCHEMATIC + 4. * PANEL + 20.);
+        let right = width - PLOT_RIGHT;
+        let x = |position: f64| PLOT_LEFT + position / self.length * (right - PLOT_LEFT);
+
+        // the beam
+        let base = svg.height - 90.;
+        svg.line((x(0.), base), (x(self.length), base), "black", 4.);
+        for (position, support) in &self.supports {
+            let p = x(*position);
+            match support {
+                Support::Pinned => {
+                    svg.polygon(
+                        &[(p, base - 2.), (p - 9., base - 18.), (p + 9., base - 18.)],
+                        "none",
+                        "black",
+                        1.5,
+                    );
+                    svg.line((p - 14., base - 18.), (p + 14., base - 18.), "black", 1.5);
+                }
+                Support::Fixed => {
+                    // hatched wall on the side away from the span
+                    let side = if *position < self.length / 2. {
+                        -1.
+                    } else {
+                        1.
+                    };
+                    svg.line((p, base - 16.), (p, base + 16.), "black", 2.5);
+                    for k in 0..5 {
+                        let y = base - 16. + 8. * k as f64;
+                        svg.line((p, y), (p + side * 7., y + 7.), "black", 1.);
+                    }
+                }
+            }
+        }
+
+        let largest = self
+            .loads
+            .iter()
+            .map(|load| match load {
+                Load::Point(p) => p.mag.abs(),
+                _ => 0.,
+            })
+            .fold(0., f64::max);
+        for load in &self.loads {
+            match load {
+                Load::Point(point) => {
+                    let length = if largest > 0. {
+                        20. + 30. * point.mag.abs() / largest
+                    } else {
+                        30.
+                    };
+                    let p = x(point.position);
+                    arrow(&mut svg, p, base + 4., length, point.mag >= 0.);
+                    svg.text(
+                        (p, base + 4. + length),
+                        (4., 0.),
+                        &format!("{}", point.mag),
+                        11.,
+                        "black",
+                    );
+                }
+                Load::Distributed(dist) => {
+                    let (a, b) = (x(dist.start), x(dist.end));
+                    let top = base + 34.;
+                    svg.line((a, top), (b, top), "#1f77b4", 1.);
+                    let count = ((b - a) / 25.).ceil().max(1.) as usize;
+                    for k in 0..=count {
+                        let p = a + (b - a) * k as f64 / count as f64;
+                        svg.push(arrow_markup(
+                            &svg,
+                            p,
+                            base + 4.,
+                            30.,
+                            dist.mag >= 0.,
+                            "#1f77b4",
+                        ));
+                    }
+                    svg.text(
+                        ((a + b) / 2., top),
+                        (-10., -6.),
+                        &format!("{}/len", dist.mag),
+                        11.,
+                        "#1f77b4",
+                    );
+                }
+                Load::Moment(moment) => {
+                    let p = x(moment.position);
+                    let (start, end) = if moment.mag >= 0. { (0., 1.) } else { (1., 0.) };
+                    let arc = |t: f64| {
+                        let angle = std::f64::consts::PI * (0.25 + 1.5 * t);
+                        (p + 16. * angle.cos(), base + 16. * angle.sin())
+                    };
+                    let points: Vec<(f64, f64)> = (0..=12).map(|k| arc(k as f64 / 12.)).collect();
+                    svg.polyline(&points, "#9467bd", 1.5);
+                    let (tip, before) = (arc(end), arc(end + (start - end) * 0.15));
+                    svg.line(before, tip, "#9467bd", 3.);
+                    svg.text(
+                        (p, base + 18.),
+                        (6., 0.),
+                        &format!("{}", moment.mag),
+                        11.,
+                        "#9467bd",
+                    );
+                }
+            }
+        }
+        for reaction in &analysis.reactions {
+            svg.text(
+                (x(reaction.position), base - 18.),
+                (-14., 14.),
+                &format!("R={:.4}", reaction.force),
+                10.,
+                "#d62728",
+            );
+        }
+
+        let panels: [Panel; 4] = [
+            ("Shear V(x)", |s| s.shear, "#1f77b4"),
+            ("Moment M(x)", |s| s.moment, "#d62728"),
+            ("Slope θ(x)", |s| s.slope, "#2ca02c"),
+            ("Deflection δ(x)", |s| s.deflection, "#9467bd"),
+        ];
+        for (i, (title, value, colour)) in panels.iter().enumerate() {
+            let bottom = svg.height - SCHEMATIC - (i as f64 + 1.) * PANEL;
+            diagram(&mut svg, analysis, &x, bottom, title, *value, colour);
+        }
+        svg
+    }
+}
+
+/// This function draws one diagram of an analysis in the band above `bottom`
+fn diagram(
+    svg: &mut Svg,
+    analysis: &Analysis,
+    x: &dyn Fn(f64) -> f64,
+    bottom: f64,
+    title: &str,
+    value: fn(&Station) -> f64,
+    colour: &str,
+) {
+    let extreme = analysis
+        .stations
+        .iter()
+        .map(|s| value(s).abs())
+        .fold(0., f64::max);
+    let half = (PANEL - 50.) / 2.;
+    let axis = bottom + 15. + half;
+    let y = |v: f64| {
+        if extreme > 0. {
+            axis + v / extreme * half
+        } else {
+            axis
+        }
+    };
+
+    let mut points = vec![(x(0.), axis)];
+    points.extend(analysis.stations.iter().map(|s| (x(s.x), y(value(s)))));
+    points.push((x(analysis.length), axis));
+    let path = svg.points(&points);
+    svg.push(format!(
+        "<polygon points=\"{}\" fill=\"{c}\" fill-opacity=\"0.35\" stroke=\"{c}\" stroke-width=\"1.5\"/>",
+        path,
+        c = colour
+    ));
+    svg.line((x(0.), axis), (x(analysis.length), axis), "black", 1.);
+    svg.text(
+        (PLOT_LEFT, bottom + PANEL - 12.),
+        (-60., 0.),
+        title,
+        12.,
+        "black",
+    );
+
+    if let Some(max) = analysis
+        .stations
+        .iter()
+        .max_by(|a, b| value(a).abs().total_cmp(&value(b).abs()))
+    {
+        let point = (x(max.x), y(value(max)));
+        svg.circle(point, 3., colour, "none");
+        let label = format!("{:.4e} at x={:.4}", value(max), max.x);
+        let vertical = if value(max) >= 0. { -6. } else { 14. };
+        // keep labels near the right end inside the image
+        let horizontal = if point.0 > 0.6 * svg.width {
+            -6. - 6. * label.len() as f64
+        } else {
+            6.
+        };
+        svg.text(point, (horizontal, vertical), &label, 11., "black");
+    }
+}
+
+/// This function draws a vertical arrow whose tip touches `tip`, pointing down if `down`
+fn arrow(svg: &mut Svg, x: f64, tip: f64, length: f64, down: bool) {
+    let markup = arrow_markup(svg, x, tip, length, down, "black");
+    svg.push(markup);
+}
+
+fn arrow_markup(svg: &Svg, x: f64, tip: f64, length: f64, down: bool, colour: &str) -> String {
+    let (tip, tail) = if down {
+        (tip, tip + length)
+    } else {
+        (tip + length, tip)
+    };
+    let head = if down { 6. } else { -6. };
+    let (a, b) = (svg.map((x, tail)), svg.map((x, tip)));
+    let (l, r) = (svg.map((x - 4., tip + head)), svg.map((x + 4., tip + head)));
+    format!(
+        "<g stroke=\"{c}\" fill=\"{c}\"><line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke-width=\"1.5\"/><polygon points=\"{:.2},{:.2} {:.2},{:.2} {:.2},{:.2}\"/></g>",
+        a.0, a.1, b.0, b.1, b.0, b.1, l.0, l.1, r.0, r.1,
+        c = colour
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load::{DistributedLoad, PointLoad};
+
+    /// An unequal angle with legs 6 and 4 long and 0.5 thick
+    fn angle() -> Section {
+        let mut section = Section::new(0.3);
+        let n: Vec<_> = [(0., 6.), (0., 0.), (4., 0.)]
+            .iter()
+            .map(|&(x, y)| section.add_node(x, y))
+            .collect();
+        section.add_edge(n[0], n[1], Some(0.5), Some(200.));
+        section.add_edge(n[1], n[2], Some(0.5), Some(200.));
+        section
+    }
+
+    /// The value of an attribute of an SVG element
+    fn attribute(element: &str, name: &str) -> f64 {
+        let start = element.find(&format!(" {}=\"", name)).unwrap() + name.len() + 3;
+        let end = start + element[start..].find('"').unwrap();
+        element[start..end].parse().unwrap()
+    }
+
+    #[test]
+    fn map_and_unmap() {
+        let svg = Svg::new((-2., 1., 8., 6.), 540., 20.);
+        assert_eq!(svg.height, 5. * 50. + 40.);
+        assert_eq!(svg.map((-2., 1.)), (20., svg.height - 20.));
+        assert_eq!(svg.map((8., 6.)), (520., 20.));
+        let p = svg.unmap(svg.map((3.5, 2.25)));
+        assert!((p.0 - 3.5).abs() < 1e-12 && (p.1 - 2.25).abs() < 1e-12);
+    }
+
+    #[test]
+    fn section_drawing() {
+        let section = angle();
+        let svg = section.draw(400.);
+        let markup = svg.render();
+        assert!(markup.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(markup.ends_with("</svg>\n"));
+
+        // the outline as one even-odd path
+        let outline = markup
+            .lines()
+            .filter(|l| l.starts_with("<path d=\"M ") && l.contains("fill=\"#d9d9d9\""))
+            .count();
+        assert_eq!(outline, 1);
+        // the plates with their thicknesses and the numbered nodes
+        assert_eq!(markup.matches(">t=0.5</text>").count(), 2);
+        for index in 0..3 {
+            assert!(markup.contains(&format!(">{}</text>", index)));
+        }
+
+        // the centroid
+        let props = section.properties();
+        let c = svg.map(props.centroid);
+        let centroid = markup
+            .lines()
+            .find(|l| l.starts_with("<circle") && l.contains("stroke=\"#2ca02c\""))
+            .unwrap();
+        assert!((attribute(centroid, "cx") - c.0).abs() < 0.01);
+        assert!((attribute(centroid, "cy") - c.1).abs() < 0.01);
+        assert!(markup.contains(">C</text>") && markup.contains(">S</text>"));
+
+        // the principal axes cross at the centroid, along φ and at right angles to it
+        let axes: Vec<_> = markup
+            .lines()
+            .filter(|l| l.starts_with("<line") && l.contains("stroke=\"#2ca02c\""))
+            .map(|l| {
+                let a = svg.unmap((attribute(l, "x1"), attribute(l, "y1")));
+                let b = svg.unmap((attribute(l, "x2"), attribute(l, "y2")));
+                (a, b)
+            })
+            .collect();
+        assert_eq!(axes.len(), 2);
+        for (k, (a, b)) in axes.iter().enumerate() {
+            let middle = ((a.0 + b.0) / 2., (a.1 + b.1) / 2.);
+            assert!((middle.0 - props.centroid.0).abs() < 0.01);
+            assert!((middle.1 - props.centroid.1).abs() < 0.01);
+            let direction = (b.1 - a.1).atan2(b.0 - a.0);
+            let expected = props.phi + k as f64 * std::f64::consts::FRAC_PI_2;
+            assert!((direction - expected).sin().abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn stress_contours() {
+        let section = angle();
+        let load = LoadState {
+            moment_x: 10.,
+            ..LoadState::default()
+        };
+        let markup = section.draw_stress(load, Component::Normal, 400.).render();
+        assert!(markup.contains(">Normal stress</text>"));
+        assert_eq!(markup.matches("stroke-dasharray").count(), 1);
+        // the legend runs from the largest tension to as much compression, just within the
+        // stress at the extreme fibre
+        let legend: Vec<f64> = markup
+            .lines()
+            .filter(|l| l.starts_with("<text") && l.contains("font-size=\"10\">"))
+            .map(|l| {
+                l[l.find('>').unwrap() + 1..l.find("</").unwrap()]
+                    .parse()
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(legend.len(), 5);
+        assert_eq!(legend[0], -legend[4]);
+        assert_eq!(legend[2], 0.);
+        let field = section.stresses(load);
+        let extreme = ring(section.build().0[0].exterior())
+            .iter()
+            .map(|p| field.normal(p.0, p.1).abs())
+            .fold(0., f64::max);
+        assert!(legend[0] < extreme && legend[0] > 0.95 * extreme);
+        let markup = section.draw_stress(load, Component::Shear, 400.).render();
+        assert!(markup.contains(">Shear stress</text>"));
+        assert!(!markup.contains("stroke-dasharray"));
+
+        // compression is blue and tension red, away from the neutral axis
+        let image = section.stress_image(load, Component::Normal, 300);
+        let (xmin, ymin, xmax, ymax) = bounds(&section, &section.build());
+        let legend = 0.35 * (xmax - xmin).max(ymax - ymin);
+        let frame = Svg::new((xmin, ymin, xmax + legend, ymax), 300., 40.);
+        assert_eq!(image.height, frame.height.ceil() as usize);
+        assert_eq!(image.width, 300);
+        let pixel = |p: (f64, f64)| {
+            let (x, y) = frame.map(p);
+            image.pixels[y as usize * image.width + x as usize]
+        };
+        for &p in &[(0., 5.9), (0.1, 0.5), (3.9, -0.1), (3.9, 0.1)] {
+            let (colour, stress) = (pixel(p), field.normal(p.0, p.1));
+            assert!(stress.abs() > 0.2 * extreme);
+            assert_eq!(colour[0] > colour[2], stress > 0.);
+        }
+        // outside the section is left white
+        assert_eq!(pixel((2., 3.)), [255, 255, 255]);
+    }
+
+    #[test]
+    fn beam_diagrams() {
+        let mut beam = Beam::new(10., 2e4);
+        beam.add_support(0., Support::Pinned);
+        beam.add_support(10., Support::Pinned);
+        beam.add_load(Load::Distributed(DistributedLoad {
+            start: 0.,
+            end: 10.,
+            mag: 3.,
+        }));
+        beam.add_load(Load::Point(PointLoad {
+            position: 5.,
+            mag: 4.,
+        }));
+        let analysis = beam.analyze(10).unwrap();
+        let svg = beam.draw(&analysis, 600.);
+        assert_eq!(svg.height, SCHEMATIC + 4. * PANEL + 20.);
+        let markup = svg.render();
+
+        // a pinned support at each end, and the loads with their magnitudes
+        assert_eq!(
+            markup
+                .lines()
+                .filter(|l| l.starts_with("<polygon") && l.contains("fill=\"none\""))
+                .count(),
+            2
+        );
+        assert!(markup.contains(">4</text>") && markup.contains(">3/len</text>"));
+        assert_eq!(markup.matches(">R=17.0000</text>").count(), 2);
+
+        // the four diagrams, with the maximum moment wL²/8 + PL/4 at midspan
+        for title in &["Shear V(x)", "Moment M(x)", "Slope θ(x)", "Deflection δ(x)"] {
+            assert!(markup.contains(&format!(">{}</text>", title)));
+        }
+        assert_eq!(markup.matches("fill-opacity=\"0.35\"").count(), 4);
+        let moment = 3. * 100. / 8. + 4. * 10. / 4.;
+        assert!(markup.contains(&format!(">{:.4e} at x=5.0000</text>", moment)));
+    }
+}
@@ -0,0 +1,862 @@
+// Reading and writing cross-sections in the DXF ASCII format
+use crate::build::{Element, Node, Section};
+use geo::algorithm::contains::Contains;
+use geo::{LineString, MultiPolygon, Point, Polygon};
+use petgraph::graph::NodeIndex;
+use std::f64::consts::{FRAC_PI_2, PI};
+use std::fmt::{Display, Formatter};
+use std::fs::{self, File};
+use std::io::{self, prelude::*};
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum DxfError {
+    Io(std::io::Error),
+    /// A malformed group, with the line number where it starts
+    Parse(usize, String),
+}
+
+impl Display for DxfError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DxfError::Io(why) => write!(f, "{}", why),
+            DxfError::Parse(line, why) => write!(f, "line {}: {}", line, why),
+        }
+    }
+}
+
+/// The geometry of a drawing entity, in world coordinates
+#[derive(Clone, Debug)]
+pub enum Geometry {
+    Line((f64, f64), (f64, f64)),
+    /// Vertices with the bulge of the segment leaving them, tan(θ/4) of its included angle
+    Polyline {
+        vertices: Vec<(f64, f64, f64)>,
+        closed: bool,
+    },
+    /// An arc running counterclockwise between two angles, in degrees
+    Arc {
+        centre: (f64, f64),
+        radius: f64,
+        start: f64,
+        end: f64,
+    },
+}
+
+/// A LINE, LWPOLYLINE or ARC entity with the attributes used for plate thicknesses
+#[derive(Clone, Debug)]
+pub struct Entity {
+    pub layer: String,
+    pub geometry: Geometry,
+    /// Constant width of a polyline, group 43
+    pub width: Option<f64>,
+    /// Extrusion thickness, group 39
+    pub thickness: Option<f64>,
+    /// Lineweight in hundredths of a millimetre, group 370
+    pub lineweight: Option<f64>,
+}
+
+/// The supported entities of a DXF file, other entities being skipped
+#[derive(Clone, Debug, Default)]
+pub struct Drawing {
+    pub entities: Vec<Entity>,
+}
+
+/// The attribute giving the thickness of the plates drawn as centrelines
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Thickness {
+    /// Constant width of polylines
+    Width,
+    /// Extrusion thickness, as written by `write_dxf`
+    Extrusion,
+    /// Lineweight, multiplied by a scale from hundredths of a millimetre to drawing units
+    Lineweight(f64),
+    /// The same thickness for every plate
+    Constant(f64),
+}
+
+/// Options for reading plate centrelines into a section
+#[derive(Clone, Debug)]
+pub struct Import {
+    /// Layer holding the centrelines, or None for the CENTRELINE layer if the drawing has
+    /// one and otherwise every layer but those `write_dxf` adds for the outline and axes
+    pub layer: Option<String>,
+    pub thickness: Thickness,
+    /// Thickness of plates whose entity lacks the attribute
+    pub fallback: f64,
+    pub elastic: f64,
+    pub poisson: f64,
+    /// Number of straight plates replacing a quarter turn of an arc
+    pub arc_segments: usize,
+    /// Distance within which points are taken as the same node
+    pub tolerance: f64,
+}
+
+impl Default for Import {
+    fn default() -> Import {
+        Import {
+            layer: None,
+            thickness: Thickness::Extrusion,
+            fallback: 2.,
+            elastic: 29000.,
+            poisson: 0.3,
+            arc_segments: 4,
+            tolerance: 1e-6,
+        }
+    }
+}
+
+impl Drawing {
+    /// This function reads the entities of a DXF file
+    pub fn read(path: &Path) -> Result<Drawing, DxfError> {
+        Drawing::parse(&fs::read_to_string(path).map_err(DxfError::Io)?)
+    }
+
+    /// This function parses the ENTITIES section of DXF text
+    pub fn parse(text: &str) -> Result<Drawing, DxfError> {
+        let lines: Vec<&str> = text.lines().map(|l| l.trim()).collect();
+        let mut pairs = vec![];
+        for (i, chunk) in lines.chunks(2).enumerate() {
+            if chunk.len() < 2 {
+                if chunk[0].is_empty() {
+                    break;
+                }
+                return Err(DxfError::Parse(
+                    2 * i + 1,
+                    "group without a value".to_string(),
+                ));
+            }
+            let code: i32 = chunk[0].parse().map_err(|_| {
+                DxfError::Parse(2 * i + 1, format!("invalid group code {:?}", chunk[0]))
+            })?;
+            pairs.push((2 * i + 1, code, chunk[1]));
+        }
+
+        let mut drawing = Drawing::default();
+        let mut in_entities = false;
+        let mut i = 0;
+        while i < pairs.len() {
+            let (line, code, value) = pairs[i];
+            i += 1;
+            if code != 0 {
+                continue;
+            }
+            match value {
+                "SECTION" => in_entities = pairs.get(i).map(|p| p.2) == Some("ENTITIES"),
+                "ENDSEC" => in_entities = false,
+                "LINE" | "LWPOLYLINE" | "ARC" if in_entities => {
+                    let start = i;
+                    while i < pairs.len() && pairs[i].1 != 0 {
+                        i += 1;
+                    }
+                    drawing
+                        .entities
+                        .push(entity(value, &pairs[start..i]).map_err(|why| {
+                            DxfError::Parse(line, format!("{} entity {}", value, why))
+                        })?);
+                }
+                _ => {}
+            }
+        }
+        Ok(drawing)
+    }
+
+    /// The layers used by the entities, in order of appearance
+    pub fn layers(&self) -> Vec<String> {
+        let mut layers: Vec<String> = vec![];
+        for entity in &self.entities {
+            if !layers.contains(&entity.layer) {
+                layers.push(entity.layer.clone());
+            }
+        }
+        layers
+    }
+
+    /// The entities on a layer. Without one, these are the entities on the layer `write_dxf`
+    /// puts them on if the drawing has it, and otherwise those on any layer it does not write.
+    fn on_layer<'a>(
+        &'a self,
+        layer: Option<&'a str>,
+        written: &'static str,
+    ) -> impl Iterator<Item = &'a Entity> {
+        let layer = layer.or_else(|| {
+            self.entities
+                .iter()
+                .any(|e| e.layer == written)
+                .then_some(written)
+        });
+        self.entities.iter().filter(move |e| match layer {
+            Some(layer) => e.layer == layer,
+            None => !LAYERS.iter().any(|&(name, _)| e.layer == name),
+        })
+    }
+
+    /// This function builds a thin-walled section from the entities drawn as plate
+    /// centrelines. Points within the tolerance are merged and lines are split where
+    /// another plate ends on them; plates crossing without a shared point are not joined.
+    pub fn section(&self, import: &Import) -> Section {
+        let mut pieces = vec![];
+        for entity in self.on_layer(import.layer.as_deref(), "CENTRELINE") {
+            let thickness = match import.thickness {
+                Thickness::Width => entity.width,
+                Thickness::Extrusion => entity.thickness,
+                Thickness::Lineweight(scale) => entity.lineweight.map(|w| w * scale),
+                Thickness::Constant(t) => Some(t),
+            }
+            .filter(|t| *t > 0.)
+            .unwrap_or(import.fallback);
+            let points = entity.points(import.arc_segments);
+            for pair in points.windows(2) {
+                pieces.push((pair[0], pair[1], thickness));
+            }
+        }
+
+        let mut section = Section::new(import.poisson);
+        let tol = import.tolerance;
+        let node = |section: &mut Section, p: (f64, f64)| -> NodeIndex {
+            let existing = section.graph.node_indices().find(|&n| {
+                let node = &section.graph[n];
+                (node.x - p.0).hypot(node.y - p.1) <= tol
+            });
+            existing.unwrap_or_else(|| {
+                section.graph.add_node(Node {
+                    x: p.0,
+                    y: p.1,
+                    label: None,
+                })
+            })
+        };
+        let ends: Vec<(NodeIndex, NodeIndex, f64)> = pieces
+            .iter()
+            .map(|&(a, b, t)| (node(&mut section, a), node(&mut section, b), t))
+            .collect();
+
+        for (a, b, thickness) in ends {
+            let (pa, pb) = (
+                (section.graph[a].x, section.graph[a].y),
+                (section.graph[b].x, section.graph[b].y),
+            );
+            let length = (pb.0 - pa.0).hypot(pb.1 - pa.1);
+            if length <= tol {
+                continue;
+            }
+            let (ux, uy) = ((pb.0 - pa.0) / length, (pb.1 - pa.1) / length);
+            // nodes lying along the plate, by their distance from its start
+            let mut along: Vec<(f64, NodeIndex)> = section
+                .graph
+                .node_indices()
+                .filter_map(|n| {
+                    let (dx, dy) = (section.graph[n].x - pa.0, section.graph[n].y - pa.1);
+                    let s = dx * ux + dy * uy;
+                    let off = (dx * uy - dy * ux).abs();
+                    if s > tol && s < length - tol && off <= tol {
+                        Some((s, n))
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+            along.sort_by(|x, y| x.0.partial_cmp(&y.0).unwrap());
+            let chain: Vec<NodeIndex> = std::iter::once(a)
+                .chain(along.into_iter().map(|n| n.1))
+                .chain(std::iter::once(b))
+                .collect();
+            for pair in chain.windows(2) {
+                if section.graph.find_edge(pair[0], pair[1]).is_none() {
+                    section.graph.add_edge(
+                        pair[0],
+                        pair[1],
+                        Element {
+                            thickness,
+                            elastic: import.elastic,
+                            label: None,
+                        },
+                    );
+                }
+            }
+        }
+        section.reindex();
+        section
+    }
+
+    /// This function joins the entities on a layer into closed loops and returns them as
+    /// polygons, with loops lying inside a larger loop taken as its holes. Open chains that
+    /// cannot be closed are left out. Without a layer, the OUTLINE layer is read if the
+    /// drawing has one.
+    pub fn outline(&self, layer: Option<&str>, arc_segments: usize, tol: f64) -> MultiPolygon<f64> {
+        let close = |a: (f64, f64), b: (f64, f64)| (a.0 - b.0).hypot(a.1 - b.1) <= tol;
+        let mut open: Vec<Vec<(f64, f64)>> = vec![];
+        let mut loops: Vec<Vec<(f64, f64)>> = vec![];
+        for entity in self.on_layer(layer, "OUTLINE") {
+            let points = entity.points(arc_segments);
+            if points.len() > 2 && close(points[0], points[points.len() - 1]) {
+                loops.push(points);
+            } else if points.len() > 1 {
+                open.push(points);
+            }
+        }
+
+        while let Some(mut chain) = open.pop() {
+            loop {
+                let end = chain[chain.len() - 1];
+                if chain.len() > 2 && close(chain[0], end) {
+                    loops.push(chain);
+                    break;
+                }
+                let next = open
+                    .iter()
+                    .position(|p| close(p[0], end) || close(p[p.len() - 1], end));
+                match next {
+                    Some(k) => {
+                        let mut path = open.swap_remove(k);
+                        if !close(path[0], end) {
+                            path.reverse();
+                        }
+                        chain.extend_from_slice(&path[1..]);
+                    }
+                    None => break,
+                }
+            }
+        }
+
+        let area = |ring: &[(f64, f64)]| {
+            (0..ring.len())
+                .map(|i| {
+                    let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+                    a.0 * b.1 - b.0 * a.1
+                })
+                .sum::<f64>()
+                .abs()
+                / 2.
+        };
+        loops.sort_by(|a, b| area(b).partial_cmp(&area(a)).unwrap());
+        let mut polygons: Vec<Polygon<f64>> = vec![];
+        for ring in loops {
+            let inside = Point::from(ring[0]);
+            let parent = polygons.iter().position(|p| {
+                p.contains(&inside)
+                    && !p
+                        .interiors()
+                        .iter()
+                        .any(|h| Polygon::new(h.clone(), vec![]).contains(&inside))
+            });
+            let ring = LineString::from(ring);
+            match parent {
+                Some(k) => polygons[k].interiors_push(ring),
+                None => polygons.push(Polygon::new(ring, vec![])),
+            }
+        }
+        MultiPolygon(polygons)
+    }
+}
+
+impl Entity {
+    /// This function approximates the entity by a polyline, with `arc_segments` straight
+    /// pieces per quarter turn of an arc. Closed polylines repeat their first point.
+    pub fn points(&self, arc_segments: usize) -> Vec<(f64, f64)> {
+        let pieces = |sweep: f64| {
+            ((arc_segments.max(1) as f64 * sweep.abs() / FRAC_PI_2).ceil() as usize).max(1)
+        };
+        match &self.geometry {
+            Geometry::Line(a, b) => vec![*a, *b],
+            Geometry::Arc {
+                centre,
+                radius,
+                start,
+                end,
+            } => {
+                let start = start.to_radians();
+                let mut sweep = (end.to_radians() - start).rem_euclid(2. * PI);
+                if sweep == 0. {
+                    sweep = 2. * PI;
+                }
+                let n = pieces(sweep);
+                (0..=n)
+                    .map(|i| {
+                        let angle = start + sweep * i as f64 / n as f64;
+                        (
+                            centre.0 + radius * angle.cos(),
+                            centre.1 + radius * angle.sin(),
+                        )
+                    })
+                    .collect()
+            }
+            Geometry::Polyline { vertices, closed } => {
+                let mut points = vec![];
+                let count = if *closed {
+                    vertices.len()
+                } else {
+                    vertices.len().saturating_sub(1)
+                };
+                for i in 0..count {
+                    let (x1, y1, bulge) = vertices[i];
+                    let (x2, y2, _) = vertices[(i + 1) % vertices.len()];
+                    points.push((x1, y1));
+                    if bulge == 0. {
+                        continue;
+                    }
+                    // the arc of included angle θ leaves its centre to the left of the
+                    // chord for positive bulges
+                    let theta = 4. * bulge.atan();
+                    let chord = (x2 - x1).hypot(y2 - y1);
+                    let (ux, uy) = ((x2 - x1) / chord, (y2 - y1) / chord);
+                    let h = chord / 2. / (theta / 2.).tan();
+                    let centre = ((x1 + x2) / 2. - uy * h, (y1 + y2) / 2. + ux * h);
+                    let radius = (x1 - centre.0).hypot(y1 - centre.1);
+                    let start = (y1 - centre.1).atan2(x1 - centre.0);
+                    let n = pieces(theta);
+                    for k in 1..n {
+                        let angle = start + theta * k as f64 / n as f64;
+                        points.push((
+                            centre.0 + radius * angle.cos(),
+                            centre.1 + radius * angle.sin(),
+                        ));
+                    }
+                }
+                match vertices.last() {
+                    Some(last) if !closed => points.push((last.0, last.1)),
+                    _ => {
+                        if let Some(&first) = points.first() {
+                            points.push(first);
+                        }
+                    }
+                }
+                points
+            }
+        }
+    }
+}
+
+/// This function reads one entity from its groups. Arcs and polylines are drawn in the
+/// object coordinate system of their extrusion direction, groups 210, 220 and 230, which
+/// for a drawing seen from below (0, 0, -1) mirrors x; lines are in world coordinates.
+fn entity(kind: &str, pairs: &[(usize, i32, &str)]) -> Result<Entity, String> {
+    let mut layer = "0".to_string();
+    let (mut width, mut thickness, mut lineweight) = (None, None, None);
+    let mut values: Vec<(i32, f64)> = vec![];
+    let mut vertices: Vec<(f64, f64, f64)> = vec![];
+    let mut flags = 0;
+    for &(_, code, value) in pairs {
+        let number = || {
+            value
+                .parse::<f64>()
+                .map_err(|_| format!("has an invalid value {:?} for group {}", value, code))
+        };
+        match code {
+            8 => layer = value.to_string(),
+            39 => thickness = Some(number()?),
+            43 => width = Some(number()?),
+            370 => lineweight = Some(number()?),
+            70 => flags = number()? as i32,
+            10 if kind == "LWPOLYLINE" => vertices.push((number()?, 0., 0.)),
+            20 if kind == "LWPOLYLINE" => match vertices.last_mut() {
+                Some(v) => v.1 = number()?,
+                None => return Err("has a y coordinate before its x".to_string()),
+            },
+            42 if kind == "LWPOLYLINE" => match vertices.last_mut() {
+                Some(v) => v.2 = number()?,
+                None => return Err("has a bulge before its first vertex".to_string()),
+            },
+            10 | 20 | 11 | 21 | 40 | 50 | 51 | 210 | 220 | 230 => values.push((code, number()?)),
+            _ => {}
+        }
+    }
+    let find = |code: i32| values.iter().find(|v| v.0 == code).map(|v| v.1);
+    let get = |code: i32| find(code).ok_or(format!("is missing group {}", code));
+
+    // the arbitrary axis algorithm keeps the world x axis for directions within 1/64 of z
+    let normal = (
+        find(210).unwrap_or(0.),
+        find(220).unwrap_or(0.),
+        find(230).unwrap_or(1.),
+    );
+    if normal.0.abs() >= 1. / 64. || normal.1.abs() >= 1. / 64. || normal.2 == 0. {
+        return Err(format!(
+            "has an extrusion direction {:?} out of the plane of the drawing",
+            normal
+        ));
+    }
+    let mirror = normal.2 < 0.;
+
+    let geometry = match kind {
+        "LINE" => Geometry::Line((get(10)?, get(20)?), (get(11)?, get(21)?)),
+        "ARC" if mirror => Geometry::Arc {
+            centre: (-get(10)?, get(20)?),
+            radius: get(40)?,
+            start: 180. - get(51)?,
+            end: 180. - get(50)?,
+        },
+        "ARC" => Geometry::Arc {
+            centre: (get(10)?, get(20)?),
+            radius: get(40)?,
+            start: get(50)?,
+            end: get(51)?,
+        },
+        _ => Geometry::Polyline {
+            vertices: if mirror {
+                vertices.iter().map(|&(x, y, b)| (-x, y, -b)).collect()
+            } else {
+                vertices
+            },
+            closed: flags & 1 == 1,
+        },
+    };
+    Ok(Entity {
+        layer,
+        geometry,
+        width,
+        thickness,
+        lineweight,
+    })
+}
+
+/// Layers written by `write_dxf`, with their colour numbers
+const LAYERS: [(&str, u8); 5] = [
+    ("OUTLINE", 7),
+    ("CENTRELINE", 5),
+    ("CENTROID", 3),
+    ("AXES", 3),
+    ("SHEAR_CENTRE", 1),
+];
+
+fn group(out: &mut String, code: i32, value: impl Display) {
+    out.push_str(&format!("{}\n{}\n", code, value));
+}
+
+fn line(out: &mut String, layer: &str, a: (f64, f64), b: (f64, f64), thickness: Option<f64>) {
+    group(out, 0, "LINE");
+    group(out, 8, layer);
+    if let Some(t) = thickness {
+        group(out, 39, t);
+    }
+    for (code, value) in &[
+        (10, a.0),
+        (20, a.1),
+        (30, 0.),
+        (11, b.0),
+        (21, b.1),
+        (31, 0.),
+    ] {
+        group(out, *code, value);
+    }
+}
+
+fn point(out: &mut String, layer: &str, p: (f64, f64), radius: f64) {
+    group(out, 0, "POINT");
+    group(out, 8, layer);
+    for (code, value) in &[(10, p.0), (20, p.1), (30, 0.)] {
+        group(out, *code, value);
+    }
+    group(out, 0, "CIRCLE");
+    group(out, 8, layer);
+    for (code, value) in &[(10, p.0), (20, p.1), (30, 0.), (40, radius)] {
+        group(out, *code, value);
+    }
+}
+
+impl Section {
+    /// This function writes the section as an R12 ASCII DXF drawing: the built outline,
+    /// the plate centrelines with their thickness as the extrusion thickness, the centroid
+    /// with the principal axes and the shear centre, each on its own layer
+    pub fn to_dxf(&self) -> String {
+        let mut out = String::new();
+        group(&mut out, 0, "SECTION");
+        group(&mut out, 2, "HEADER");
+        group(&mut out, 9, "$ACADVER");
+        group(&mut out, 1, "AC1009");
+        group(&mut out, 0, "ENDSEC");
+
+        group(&mut out, 0, "SECTION");
+        group(&mut out, 2, "TABLES");
+        group(&mut out, 0, "TABLE");
+        group(&mut out, 2, "LAYER");
+        group(&mut out, 70, LAYERS.len());
+        for (name, colour) in LAYERS.iter() {
+            group(&mut out, 0, "LAYER");
+            group(&mut out, 2, name);
+            group(&mut out, 70, 0);
+            group(&mut out, 62, colour);
+            group(&mut out, 6, "CONTINUOUS");
+        }
+        group(&mut out, 0, "ENDTAB");
+        group(&mut out, 0, "ENDSEC");
+
+        group(&mut out, 0, "SECTION");
+        group(&mut out, 2, "ENTITIES");
+        if self.graph.edge_count() > 0 {
+            let outline = self.build();
+            for polygon in &outline.0 {
+                for ring in std::iter::once(polygon.exterior()).chain(polygon.interiors()) {
+                    for pair in ring.0.windows(2) {
+                        let (a, b) = ((pair[0].x, pair[0].y), (pair[1].x, pair[1].y));
+                        line(&mut out, "OUTLINE", a, b, None);
+                    }
+                }
+            }
+            for seg in self.segments() {
+                line(
+                    &mut out,
+                    "CENTRELINE",
+                    seg.start,
+                    seg.end,
+                    Some(seg.thickness),
+                );
+            }
+
+            let props = self.properties();
+            let (xmin, ymin, xmax, ymax) = crate::draw::bounds(self, &outline);
+            let reach = 0.6 * (xmax - xmin).max(ymax - ymin);
+            let c = props.centroid;
+            point(&mut out, "CENTROID", c, reach / 30.);
+            for angle in &[props.phi, props.phi + FRAC_PI_2] {
+                let d = (reach * angle.cos(), reach * angle.sin());
+                line(
+                    &mut out,
+                    "AXES",
+                    (c.0 - d.0, c.1 - d.1),
+                    (c.0 + d.0, c.1 + d.1),
+                    None,
+                );
+            }
+            point(&mut out, "SHEAR_CENTRE", props.shear_centre, reach / 40.);
+        }
+        group(&mut out, 0, "ENDSEC");
+        group(&mut out, 0, "EOF");
+        out
+    }
+}
+
+/// This function writes a section to a DXF file
+pub fn write_dxf(section: &Section, path: &Path) -> io::Result<()> {
+    File::create(path)?.write_all(section.to_dxf().as_bytes())?;
+    println!("successfully wrote to {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo::prelude::Area;
+
+    fn i_section() -> Section {
+        let mut section = Section::new(0.3);
+        let n: Vec<_> = [
+            (-9., 9.),
+            (0., 9.),
+            (9., 9.),
+            (-9., -9.),
+            (0., -9.),
+            (9., -9.),
+        ]
+        .iter()
+        .map(|&(x, y)| section.add_node(x, y))
+        .collect();
+        for &(a, b) in &[(0, 1), (1, 2), (1, 4), (3, 4), (4, 5)] {
+            section.add_edge(n[a], n[b], None, None);
+        }
+        section
+    }
+
+    #[test]
+    fn round_trip_reads_only_the_centrelines() {
+        let section = i_section();
+        let drawing = Drawing::parse(&section.to_dxf()).unwrap();
+        let read = drawing.section(&Import::default());
+        assert_eq!(read.graph.node_count(), 6);
+        assert_eq!(read.graph.edge_count(), 5);
+        for node in read.graph.node_indices() {
+            let n = &read.graph[node];
+            assert!(section
+                .graph
+                .node_indices()
+                .any(|m| (section.graph[m].x, section.graph[m].y) == (n.x, n.y)));
+        }
+    }
+
+    /// DXF text with an ENTITIES section holding the groups
+    fn entities(groups: &[(i32, &str)]) -> String {
+        let mut out = String::new();
+        group(&mut out, 0, "SECTION");
+        group(&mut out, 2, "ENTITIES");
+        for &(code, value) in groups {
+            group(&mut out, code, value);
+        }
+        group(&mut out, 0, "ENDSEC");
+        group(&mut out, 0, "EOF");
+        out
+    }
+
+    fn close(a: (f64, f64), b: (f64, f64)) -> bool {
+        (a.0 - b.0).hypot(a.1 - b.1) < 1e-9
+    }
+
+    const ARC: [(i32, &str); 7] = [
+        (0, "ARC"),
+        (8, "PLATES"),
+        (10, "5"),
+        (20, "0"),
+        (40, "2"),
+        (50, "0"),
+        (51, "90"),
+    ];
+
+    const SEMICIRCLE: [(i32, &str); 9] = [
+        (0, "LWPOLYLINE"),
+        (90, "2"),
+        (70, "0"),
+        (10, "0"),
+        (20, "0"),
+        (42, "1"),
+        (10, "2"),
+        (20, "0"),
+        (43, "0.25"),
+    ];
+
+    #[test]
+    fn arc() {
+        let drawing = Drawing::parse(&entities(&ARC)).unwrap();
+        assert_eq!(drawing.layers(), vec!["PLATES".to_string()]);
+        let points = drawing.entities[0].points(4);
+        assert_eq!(points.len(), 5);
+        assert!(close(points[0], (7., 0.)));
+        assert!(close(points[4], (5., 2.)));
+        let middle = std::f64::consts::FRAC_PI_8;
+        assert!(close(
+            points[1],
+            (5. + 2. * middle.cos(), 2. * middle.sin())
+        ));
+
+        // a quarter of a circle drawn as four plates
+        let section = drawing.section(&Import::default());
+        assert_eq!(section.graph.node_count(), 5);
+        assert_eq!(section.graph.edge_count(), 4);
+    }
+
+    #[test]
+    fn bulged_polyline() {
+        // a positive bulge of 1 turns a half circle counterclockwise from the first vertex
+        let drawing = Drawing::parse(&entities(&SEMICIRCLE)).unwrap();
+        let entity = &drawing.entities[0];
+        assert_eq!(entity.width, Some(0.25));
+        let points = entity.points(2);
+        assert_eq!(points.len(), 5);
+        assert!(close(points[0], (0., 0.)));
+        assert!(close(points[2], (1., -1.)));
+        assert!(close(points[4], (2., 0.)));
+        for p in &points {
+            assert!(((p.0 - 1.).hypot(p.1) - 1.).abs() < 1e-12);
+        }
+
+        let import = Import {
+            thickness: Thickness::Width,
+            arc_segments: 2,
+            ..Import::default()
+        };
+        let section = drawing.section(&import);
+        assert_eq!(section.graph.edge_count(), 4);
+        for edge in section.graph.edge_indices() {
+            assert_eq!(section.graph[edge].thickness, 0.25);
+        }
+    }
+
+    #[test]
+    fn extrusion_below_mirrors_x() {
+        let mut arc = ARC.to_vec();
+        arc.push((230, "-1"));
+        let mut polyline = SEMICIRCLE.to_vec();
+        polyline.extend_from_slice(&[(210, "0"), (220, "0"), (230, "-1")]);
+        arc.extend_from_slice(&polyline);
+        let drawing = Drawing::parse(&entities(&arc)).unwrap();
+
+        // the arc runs counterclockwise about -z, so clockwise as seen from above
+        let points = drawing.entities[0].points(4);
+        assert!(close(points[0], (-5., 2.)));
+        assert!(close(points[4], (-7., 0.)));
+        let points = drawing.entities[1].points(2);
+        assert!(close(points[0], (0., 0.)));
+        assert!(close(points[2], (-1., -1.)));
+        assert!(close(points[4], (-2., 0.)));
+
+        let mut tilted = ARC.to_vec();
+        tilted.extend_from_slice(&[(210, "1"), (230, "0")]);
+        let error = Drawing::parse(&entities(&tilted)).err().unwrap();
+        assert!(error
+            .to_string()
+            .contains("ARC entity has an extrusion direction (1.0, 0.0, 0.0)"));
+    }
+
+    #[test]
+    fn lineweight_thickness() {
+        let drawing = Drawing::parse(&entities(&[
+            (0, "LINE"),
+            (370, "50"),
+            (10, "0"),
+            (20, "0"),
+            (11, "10"),
+            (21, "0"),
+            (0, "LINE"),
+            (10, "10"),
+            (20, "0"),
+            (11, "10"),
+            (21, "5"),
+        ]))
+        .unwrap();
+        assert_eq!(drawing.entities[0].lineweight, Some(50.));
+        assert_eq!(drawing.entities[1].lineweight, None);
+        // lineweights are in hundredths of a millimetre, the second plate takes the fallback
+        let import = Import {
+            thickness: Thickness::Lineweight(0.01),
+            fallback: 0.3,
+            ..Import::default()
+        };
+        let section = drawing.section(&import);
+        let thicknesses: Vec<f64> = section
+            .graph
+            .edge_indices()
+            .map(|edge| section.graph[edge].thickness)
+            .collect();
+        assert_eq!(thicknesses, vec![0.5, 0.3]);
+    }
+
+    #[test]
+    fn malformed_groups() {
+        let error = |text: &str| Drawing::parse(text).err().unwrap().to_string();
+        assert_eq!(
+            error("0\nSECTION\nx\nENTITIES\n"),
+            "line 3: invalid group code \"x\""
+        );
+        assert_eq!(error("0\nSECTION\n2"), "line 3: group without a value");
+        let mut line = vec![(0, "LINE"), (10, "0"), (20, "zero")];
+        assert_eq!(
+            error(&entities(&line)),
+            "line 5: LINE entity has an invalid value \"zero\" for group 20"
+        );
+        line[2].1 = "0";
+        assert_eq!(
+            error(&entities(&line)),
+            "line 5: LINE entity is missing group 11"
+        );
+        assert_eq!(
+            error(&entities(&[(0, "LWPOLYLINE"), (20, "1")])),
+            "line 5: LWPOLYLINE entity has a y coordinate before its x"
+        );
+        assert_eq!(
+            error(&entities(&[(0, "LWPOLYLINE"), (42, "1")])),
+            "line 5: LWPOLYLINE entity has a bulge before its first vertex"
+        );
+        // entities outside the ENTITIES section and other entities are skipped
+        let drawing = Drawing::parse("0\nLINE\n10\nzero\n0\nEOF\n").unwrap();
+        assert!(drawing.entities.is_empty());
+        let drawing = Drawing::parse(&entities(&[(0, "CIRCLE"), (40, "x")])).unwrap();
+        assert!(drawing.entities.is_empty());
+    }
+
+    #[test]
+    fn round_trip_reads_the_outline() {
+        let section = i_section();
+        let drawing = Drawing::parse(&section.to_dxf()).unwrap();
+        let outline = drawing.outline(None, 4, 1e-6);
+        let expected = section.build().area().abs();
+        assert!((outline.area().abs() - expected).abs() < 1e-6 * expected);
+    }
+}
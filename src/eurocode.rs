@@ -0,0 +1,613 @@
+// Cross-section classification and member resistance to EN 1993-1-1
+use crate::buckling::{critical_moment, Restraint};
+use crate::build::Section;
+use crate::design::{Demands, Entry, Fabrication};
+use crate::material::Material;
+use crate::plate::{outstand_factor, Boundary};
+use crate::properties::area_above;
+use petgraph::graph::EdgeIndex;
+use serde::Serialize;
+use std::f64::consts::PI;
+use std::fmt::{Display, Formatter, Result};
+
+const GAMMA_M0: f64 = 1.0;
+const GAMMA_M1: f64 = 1.0;
+
+/// Buckling curves (EN 1993-1-1 Table 6.1)
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum Curve {
+    A0,
+    A,
+    B,
+    C,
+    D,
+}
+
+impl Curve {
+    /// Imperfection factor, α
+    pub fn imperfection(&self) -> f64 {
+        match self {
+            Curve::A0 => 0.13,
+            Curve::A => 0.21,
+            Curve::B => 0.34,
+            Curve::C => 0.49,
+            Curve::D => 0.76,
+        }
+    }
+
+    /// Reduction factor, χ, for a non-dimensional slenderness (Eq. 6.49 and 6.56)
+    pub fn reduction(&self, slenderness: f64) -> f64 {
+        if slenderness <= 0.2 {
+            return 1.;
+        }
+        let phi = 0.5 * (1. + self.imperfection() * (slenderness - 0.2) + slenderness.powi(2));
+        (1. / (phi + (phi * phi - slenderness * slenderness).sqrt())).min(1.)
+    }
+}
+
+/// Classification of one plate (EN 1993-1-1 Table 5.2)
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Element {
+    pub edge: EdgeIndex,
+    pub boundary: Boundary,
+    /// Width-to-thickness ratio, c/t
+    pub slenderness: f64,
+    /// Compressed fraction of the plate under plastic stresses, α
+    pub alpha: f64,
+    /// Elastic stress ratio, ψ
+    pub psi: f64,
+    /// Limits for Class 1, 2 and 3
+    pub limits: [f64; 3],
+    pub class: u8,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Report {
+    pub material: String,
+    /// ε = √(235 / fy)
+    pub epsilon: f64,
+    pub elements: Vec<Element>,
+    pub class: u8,
+    pub entries: Vec<Entry>,
+    /// The governing utilisation
+    pub ratio: f64,
+}
+
+/// This function classifies the section and checks its resistance to the combined actions
+/// (6.2) and the member buckling resistance (6.3) over the unbraced `length`. Forces and
+/// lengths are expected in N and mm. The plates are classified under both moments, with the
+/// plastic neutral axis taken parallel to the elastic one. Flexural buckling uses curve b
+/// about x and curve c about y, lateral-torsional buckling uses the general case with the
+/// curve of Table 6.4 for a rolled or welded I-section, and the interaction factors follow
+/// Annex B with equivalent uniform moment factors of 1.0.
+pub fn check(
+    section: &Section,
+    material: &Material,
+    fabrication: Fabrication,
+    length: f64,
+    c1: Option<f64>,
+    demands: &Demands,
+) -> Report {
+    let mut props = section.properties();
+    props.elastic = material.elastic;
+    let e = material.elastic;
+    let fy = material.yield_strength;
+    let epsilon = (235. / fy).sqrt();
+    let segments = section.segments();
+    let mut entries = vec![];
+
+    // stress distributions used to classify the plates, compression positive
+    let elastic = |(x, y): (f64, f64)| {
+        demands.axial / props.area
+            + demands.moment_x * (y - props.centroid.1) / props.ixx
+            + demands.moment_y * (x - props.centroid.0) / props.iyy
+    };
+    // the direction the elastic compression increases in
+    let gradient = (demands.moment_y / props.iyy, demands.moment_x / props.ixx);
+    let norm = gradient.0.hypot(gradient.1);
+    let direction = if norm > 0. {
+        (gradient.0 / norm, gradient.1 / norm)
+    } else {
+        (0., 1.)
+    };
+    let across = |p: (f64, f64)| direction.0 * p.0 + direction.1 * p.1;
+    let compressed = ((props.area + demands.axial / fy) / 2.)
+        .max(0.)
+        .min(props.area);
+    let plastic = if norm == 0. {
+        if demands.axial > 0. {
+            f64::MIN
+        } else {
+            f64::MAX
+        }
+    } else {
+        let (mut low, mut high) = (-1e12, 1e12);
+        for _ in 0..200 {
+            let mid = (low + high) / 2.;
+            if area_above(&segments, across, mid) > compressed {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        (low + high) / 2.
+    };
+
+    let elements: Vec<Element> = section
+        .graph
+        .edge_indices()
+        .zip(segments.iter())
+        .map(|(edge, seg)| {
+            let boundary = section.boundary(edge);
+            let (a, _) = section.graph.edge_endpoints(edge).unwrap();
+            // run outstands from their supported end to their tip
+            let (root, tip) = if section.graph.neighbors(a).count() == 1 {
+                (seg.end, seg.start)
+            } else {
+                (seg.start, seg.end)
+            };
+            let slenderness = seg.length() / seg.thickness;
+            let (s1, s2) = (elastic(root), elastic(tip));
+            let (high, low) = (s1.max(s2), s1.min(s2));
+            let psi = if high > 0. { low / high } else { 1. };
+            let (f1, f2) = (across(root) - plastic, across(tip) - plastic);
+            let alpha = if f1 >= 0. && f2 >= 0. {
+                1.
+            } else if f1 <= 0. && f2 <= 0. {
+                0.
+            } else {
+                f1.max(f2) / (f1 - f2).abs()
+            };
+
+            let limits = match boundary {
+                Boundary::Internal => {
+                    let (l1, l2) = if alpha > 0.5 {
+                        (396. / (13. * alpha - 1.), 456. / (13. * alpha - 1.))
+                    } else {
+                        (36. / alpha, 41.5 / alpha)
+                    };
+                    let l3 = if psi > -1. {
+                        42. / (0.67 + 0.33 * psi)
+                    } else {
+                        62. * (1. - psi) * (-psi).sqrt()
+                    };
+                    [l1 * epsilon, l2 * epsilon, l3 * epsilon]
+                }
+                Boundary::Outstand => {
+                    let tip_compressed = s2 >= s1;
+                    let reduce = if tip_compressed {
+                        alpha
+                    } else {
+                        alpha * alpha.sqrt()
+                    };
+                    let k = outstand_factor(psi, tip_compressed);
+                    [
+                        9. * epsilon / reduce,
+                        10. * epsilon / reduce,
+                        21. * epsilon * k.sqrt(),
+                    ]
+                }
+            };
+            let class = if high <= 0. || alpha == 0. || slenderness <= limits[0] {
+                1
+            } else if slenderness <= limits[1] {
+                2
+            } else if slenderness <= limits[2] {
+                3
+            } else {
+                4
+            };
+            Element {
+                edge,
+                boundary,
+                slenderness,
+                alpha,
+                psi,
+                limits,
+                class,
+            }
+        })
+        .collect();
+    let class = elements.iter().map(|el| el.class).max().unwrap_or(1);
+
+    // 6.2 cross-section resistance
+    let area = if class == 4 && demands.axial > 0. {
+        section.effective(fy).properties.area
+    } else {
+        props.area
+    };
+    let (wy, wz) = match class {
+        1 | 2 => (props.zxx, props.zyy),
+        3 => (props.sxx, props.syy),
+        _ => (section.effective_bending(fy).properties.sxx, props.syy),
+    };
+    let n_rd = area * fy / GAMMA_M0;
+    let my_rd = wy * fy / GAMMA_M0;
+    let mz_rd = wz * fy / GAMMA_M0;
+    entries.push(Entry::new(
+        if demands.axial > 0. { "6.2.4" } else { "6.2.3" },
+        "Axial force",
+        &[("A", area), ("N,Rd", n_rd), ("N,Ed", demands.axial)],
+        Some(demands.axial.abs() / n_rd),
+    ));
+    entries.push(Entry::new(
+        "6.2.5",
+        "Bending about x",
+        &[("W", wy), ("M,Rd", my_rd), ("M,Ed", demands.moment_x)],
+        Some(demands.moment_x.abs() / my_rd),
+    ));
+    entries.push(Entry::new(
+        "6.2.5",
+        "Bending about y",
+        &[("W", wz), ("M,Rd", mz_rd), ("M,Ed", demands.moment_y)],
+        Some(demands.moment_y.abs() / mz_rd),
+    ));
+
+    let webs: Vec<(f64, f64)> = segments
+        .iter()
+        .filter(|seg| (seg.start.1 - props.centroid.1) * (seg.end.1 - props.centroid.1) < 0.)
+        .map(|seg| (seg.length(), seg.thickness))
+        .collect();
+    let shear_area: f64 = webs.iter().map(|w| w.0 * w.1).sum();
+    let web = webs.iter().fold((0., 0.), |acc, w| {
+        if w.0 / w.1 > acc.0 {
+            (w.0 / w.1, w.1)
+        } else {
+            acc
+        }
+    });
+    let v_rd = shear_area * fy / 3f64.sqrt() / GAMMA_M0;
+    entries.push(Entry::new(
+        "6.2.6",
+        "Shear",
+        &[
+            ("Av", shear_area),
+            ("hw/tw", web.0),
+            ("72ε", 72. * epsilon),
+            ("Vpl,Rd", v_rd),
+            ("V,Ed", demands.shear),
+        ],
+        Some(demands.shear.abs() / v_rd),
+    ));
+    if web.0 > 72. * epsilon {
+        entries.push(Entry::new(
+            "6.2.6(6)",
+            "Shear buckling resistance to be verified to EN 1993-1-5",
+            &[("hw/tw", web.0)],
+            None,
+        ));
+    }
+
+    let my_v_rd = if demands.shear.abs() > 0.5 * v_rd {
+        let rho = (2. * demands.shear.abs() / v_rd - 1.).powi(2);
+        let reduced =
+            ((wy - rho * shear_area * shear_area / (4. * web.1)) * fy / GAMMA_M0).min(my_rd);
+        entries.push(Entry::new(
+            "6.2.8",
+            "Bending and shear",
+            &[("ρ", rho), ("My,V,Rd", reduced)],
+            Some(demands.moment_x.abs() / reduced),
+        ));
+        reduced
+    } else {
+        my_rd
+    };
+    entries.push(Entry::new(
+        "6.2.1(7)",
+        "Combined axial force and bending",
+        &[("N,Rd", n_rd), ("My,Rd", my_v_rd), ("Mz,Rd", mz_rd)],
+        Some(
+            demands.axial.abs() / n_rd
+                + demands.moment_x.abs() / my_v_rd
+                + demands.moment_y.abs() / mz_rd,
+        ),
+    ));
+
+    // 6.3 member buckling resistance
+    let slender = |inertia: f64| (area * fy / (PI * PI * e * inertia / length.powi(2))).sqrt();
+    let (lambda_y, lambda_z) = (slender(props.ixx), slender(props.iyy));
+    let (chi_y, chi_z) = (Curve::B.reduction(lambda_y), Curve::C.reduction(lambda_z));
+
+    let c1 = c1.unwrap_or(1.);
+    let mcr = critical_moment(
+        &props,
+        section.poisson(),
+        length,
+        Restraint::pinned(),
+        c1,
+        0.,
+        0.,
+    );
+    let (xs, ys): (Vec<f64>, Vec<f64>) = section
+        .graph
+        .node_indices()
+        .map(|n| (section.graph[n].x, section.graph[n].y))
+        .unzip();
+    let span = |v: &Vec<f64>| {
+        v.iter().cloned().fold(f64::MIN, f64::max) - v.iter().cloned().fold(f64::MAX, f64::min)
+    };
+    // Table 6.4
+    let curve_lt = match (fabrication, span(&ys) <= 2. * span(&xs)) {
+        (Fabrication::Rolled, true) => Curve::A,
+        (Fabrication::Rolled, false) => Curve::B,
+        (Fabrication::Welded, true) => Curve::C,
+        (Fabrication::Welded, false) => Curve::D,
+    };
+    let lambda_lt = (wy * fy / mcr).sqrt();
+    let chi_lt = curve_lt.reduction(lambda_lt);
+    let mb_rd = chi_lt * wy * fy / GAMMA_M1;
+    entries.push(Entry::new(
+        "6.3.2.2",
+        "Lateral-torsional buckling",
+        &[
+            ("C1", c1),
+            ("Mcr", mcr),
+            ("λLT", lambda_lt),
+            ("αLT", curve_lt.imperfection()),
+            ("χLT", chi_lt),
+            ("Mb,Rd", mb_rd),
+            ("M,Ed", demands.moment_x),
+        ],
+        Some(demands.moment_x.abs() / mb_rd),
+    ));
+
+    if demands.axial > 0. {
+        let nb_rd = chi_y.min(chi_z) * area * fy / GAMMA_M1;
+        entries.push(Entry::new(
+            "6.3.1",
+            "Flexural buckling",
+            &[
+                ("Lcr", length),
+                ("λx", lambda_y),
+                ("χx", chi_y),
+                ("λy", lambda_z),
+                ("χy", chi_z),
+                ("Nb,Rd", nb_rd),
+                ("N,Ed", demands.axial),
+            ],
+            Some(demands.axial / nb_rd),
+        ));
+
+        let n_rk = area * fy;
+        let ny = demands.axial / (chi_y * n_rk / GAMMA_M1);
+        let nz = demands.axial / (chi_z * n_rk / GAMMA_M1);
+        let (cmy, cmz, cmlt) = (1., 1., 1.);
+        let (kyy, kzz, kyz, kzy) = if class <= 2 {
+            let kyy = (cmy * (1. + (lambda_y - 0.2) * ny)).min(cmy * (1. + 0.8 * ny));
+            let kzz = (cmz * (1. + (2. * lambda_z - 0.6) * nz)).min(cmz * (1. + 1.4 * nz));
+            let kzy = if lambda_z < 0.4 {
+                (0.6 + lambda_z).min(1. - 0.1 * lambda_z * nz / (cmlt - 0.25))
+            } else {
+                (1. - 0.1 * lambda_z * nz / (cmlt - 0.25)).max(1. - 0.1 * nz / (cmlt - 0.25))
+            };
+            (kyy, kzz, 0.6 * kzz, kzy)
+        } else {
+            let kyy = (cmy * (1. + 0.6 * lambda_y * ny)).min(cmy * (1. + 0.6 * ny));
+            let kzz = (cmz * (1. + 0.6 * lambda_z * nz)).min(cmz * (1. + 0.6 * nz));
+            let kzy =
+                (1. - 0.05 * lambda_z * nz / (cmlt - 0.25)).max(1. - 0.05 * nz / (cmlt - 0.25));
+            (kyy, kzz, kzz, kzy)
+        };
+        let (my, mz) = (demands.moment_x.abs(), demands.moment_y.abs());
+        let my_rk = wy * fy / GAMMA_M1;
+        let mz_rk = wz * fy / GAMMA_M1;
+        entries.push(Entry::new(
+            "6.3.3(4)",
+            "Member in bending and axial compression, Eq. 6.61",
+            &[("kxx", kyy), ("kxy", kyz)],
+            Some(ny + kyy * my / (chi_lt * my_rk) + kyz * mz / mz_rk),
+        ));
+        entries.push(Entry::new(
+            "6.3.3(4)",
+            "Member in bending and axial compression, Eq. 6.62",
+            &[("kyx", kzy), ("kyy", kzz)],
+            Some(nz + kzy * my / (chi_lt * my_rk) + kzz * mz / mz_rk),
+        ));
+    }
+
+    let ratio = entries
+        .iter()
+        .filter_map(|entry| entry.ratio)
+        .fold(0., f64::max);
+    Report {
+        material: material.name.clone(),
+        epsilon,
+        elements,
+        class,
+        entries,
+        ratio,
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        writeln!(
+            f,
+            "EN 1993-1-1, {} (ε = {:.3})",
+            self.material, self.epsilon
+        )?;
+        writeln!(f, "Section: Class {}", self.class)?;
+        for el in &self.elements {
+            writeln!(
+                f,
+                "  edge {}: {:?}, c/t = {:.3} (α = {:.3}, ψ = {:.3}, limits {:.3} / {:.3} / {:.3}) Class {}",
+                el.edge.index(),
+                el.boundary,
+                el.slenderness,
+                el.alpha,
+                el.psi,
+                el.limits[0],
+                el.limits[1],
+                el.limits[2],
+                el.class
+            )?;
+        }
+        for entry in &self.entries {
+            write!(f, "{} {}", entry.clause, entry.title)?;
+            match entry.ratio {
+                Some(ratio) => writeln!(f, ": utilisation = {:.3}", ratio)?,
+                None => writeln!(f)?,
+            }
+            for (name, value) in &entry.values {
+                writeln!(f, "    {} = {:.4}", name, value)?;
+            }
+        }
+        writeln!(f, "Governing utilisation = {:.3}", self.ratio)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::{lookup, Shape};
+
+    /// A value of the first entry for the clause
+    fn value(report: &Report, clause: &str, name: &str) -> f64 {
+        let entry = report.entries.iter().find(|e| e.clause == clause).unwrap();
+        entry.values.iter().find(|v| v.0 == name).unwrap().1
+    }
+
+    #[test]
+    fn buckling_curves() {
+        // Table 6.1 and the tabulated reduction factors at a slenderness of 1.0
+        for &(curve, chi) in &[
+            (Curve::A, 0.6656),
+            (Curve::B, 0.5970),
+            (Curve::C, 0.5399),
+            (Curve::D, 0.4671),
+        ] {
+            assert!((curve.reduction(1.) - chi).abs() < 1e-4);
+        }
+        assert_eq!(Curve::B.reduction(0.2), 1.);
+    }
+
+    #[test]
+    fn ipe300_in_bending() {
+        // S355, Wpl,y = 628 × 10³ mm³ tabulated with the root radii
+        let section = lookup("IPE300").unwrap().section(0.3);
+        let demands = Demands {
+            moment_x: 100e6,
+            ..Demands::default()
+        };
+        let report = check(
+            &section,
+            &Material::s355(),
+            Fabrication::Rolled,
+            4000.,
+            None,
+            &demands,
+        );
+        assert!((report.epsilon - 0.8136).abs() < 1e-4);
+        assert_eq!(report.class, 1);
+        let w = value(&report, "6.2.5", "W");
+        assert!((w - 628e3).abs() < 0.03 * 628e3);
+        assert!((value(&report, "6.2.5", "M,Rd") - w * 355.).abs() < 1e-6);
+
+        // rolled with h / b ≤ 2 takes curve a, welded curve c
+        let lambda = (w * 355. / value(&report, "6.3.2.2", "Mcr")).sqrt();
+        assert!((value(&report, "6.3.2.2", "λLT") - lambda).abs() < 1e-12);
+        assert_eq!(value(&report, "6.3.2.2", "αLT"), 0.21);
+        let chi = Curve::A.reduction(lambda);
+        assert!((value(&report, "6.3.2.2", "Mb,Rd") - chi * w * 355.).abs() < 1e-6);
+        let welded = check(
+            &section,
+            &Material::s355(),
+            Fabrication::Welded,
+            4000.,
+            None,
+            &demands,
+        );
+        assert_eq!(value(&welded, "6.3.2.2", "αLT"), 0.49);
+    }
+
+    #[test]
+    fn deep_sections_take_the_next_curve() {
+        // h / b = 400 / 180 > 2
+        let section = lookup("IPE400").unwrap().section(0.3);
+        let demands = Demands {
+            moment_x: 100e6,
+            ..Demands::default()
+        };
+        let alpha = |fabrication| {
+            let report = check(
+                &section,
+                &Material::s355(),
+                fabrication,
+                4000.,
+                None,
+                &demands,
+            );
+            value(&report, "6.3.2.2", "αLT")
+        };
+        assert_eq!(alpha(Fabrication::Rolled), 0.34);
+        assert_eq!(alpha(Fabrication::Welded), 0.76);
+    }
+
+    #[test]
+    fn ipe300_in_compression() {
+        // the web, c/t = 40.7 beyond 42ε = 34.2, is Class 4 in compression
+        let section = lookup("IPE300").unwrap().section(0.3);
+        let demands = Demands {
+            axial: 1e6,
+            ..Demands::default()
+        };
+        let report = check(
+            &section,
+            &Material::s355(),
+            Fabrication::Rolled,
+            3000.,
+            None,
+            &demands,
+        );
+        assert_eq!(report.class, 4);
+        let web = report
+            .elements
+            .iter()
+            .find(|el| el.boundary == Boundary::Internal)
+            .unwrap();
+        assert_eq!((web.alpha, web.psi), (1., 1.));
+        assert!((web.limits[2] - 42. * report.epsilon).abs() < 1e-12);
+        assert!(value(&report, "6.2.4", "A") < section.properties().area);
+    }
+
+    #[test]
+    fn minor_axis_bending_classifies_the_flanges() {
+        // welded, with flange outstands of c/t = 15
+        let section = Shape {
+            name: "welded",
+            depth: 410.,
+            width: 300.,
+            flange: 10.,
+            web: 8.,
+            elastic: 210000.,
+        }
+        .section(0.3);
+        let classes = |demands: Demands| {
+            let report = check(
+                &section,
+                &Material::s355(),
+                Fabrication::Welded,
+                4000.,
+                None,
+                &demands,
+            );
+            (report.class, report.elements)
+        };
+        let (class, elements) = classes(Demands {
+            moment_y: 1e6,
+            ..Demands::default()
+        });
+        assert_eq!(class, 4);
+        let props = section.properties();
+        for (el, seg) in elements.iter().zip(section.segments()) {
+            let x = seg.start.0.max(seg.end.0) - props.centroid.0;
+            if el.boundary == Boundary::Outstand && x > 0. {
+                // compressed towards the tip, from nothing at the web
+                assert_eq!((el.alpha, el.psi, el.class), (1., 0., 4));
+            } else {
+                assert_eq!(el.class, 1);
+            }
+        }
+        assert_eq!(classes(Demands::default()).0, 1);
+    }
+}
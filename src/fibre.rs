@@ -0,0 +1,446 @@
+// Fibre discretisation of the built outline and moment-curvature analysis
+use crate::build::Section;
+use crate::law::StressStrain;
+use geo::{LineString, MultiPolygon};
+use serde::Serialize;
+
+/// A small area of the section carrying uniform strain
+#[derive(Clone, Copy, Debug)]
+pub struct Fibre {
+    pub x: f64,
+    pub y: f64,
+    pub area: f64,
+    /// Index of the stress-strain law of the fibre
+    pub law: usize,
+}
+
+/// A point on the moment-curvature curve
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Curvature {
+    pub curvature: f64,
+    pub moment: f64,
+    /// Strain at the reference point, tension positive
+    pub strain: f64,
+    /// Depth of the neutral axis below the extreme compression fibre
+    pub depth: f64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MomentCurvature {
+    pub points: Vec<Curvature>,
+    /// The point where a fibre first reaches yield, if any does before failure
+    pub first_yield: Option<Curvature>,
+    /// The point where a fibre reaches its failure strain
+    pub ultimate: Curvature,
+}
+
+pub struct FibreSection {
+    pub fibres: Vec<Fibre>,
+    pub laws: Vec<Box<dyn StressStrain>>,
+}
+
+impl Default for FibreSection {
+    fn default() -> Self {
+        FibreSection::new()
+    }
+}
+
+impl FibreSection {
+    pub fn new() -> FibreSection {
+        FibreSection {
+            fibres: vec![],
+            laws: vec![],
+        }
+    }
+
+    /// This function adds a stress-strain law and returns its index
+    pub fn add_law(&mut self, law: Box<dyn StressStrain>) -> usize {
+        self.laws.push(law);
+        self.laws.len() - 1
+    }
+
+    /// This function adds a single fibre, such as a reinforcing bar
+    pub fn add_fibre(&mut self, x: f64, y: f64, area: f64, law: usize) {
+        self.fibres.push(Fibre { x, y, area, law });
+    }
+
+    /// This function discretises an outline into square fibres of side `size`
+    pub fn add_outline(&mut self, outline: &MultiPolygon<f64>, size: f64, law: usize) {
+        self.fibres.extend(discretise(outline, size, law));
+    }
+
+    /// The modulus-weighted centroid, used as the reference point for strains and moments
+    pub fn centroid(&self) -> (f64, f64) {
+        let (mut ea, mut x, mut y) = (0., 0., 0.);
+        for fibre in &self.fibres {
+            let weight = fibre.area * self.laws[fibre.law].elastic();
+            ea += weight;
+            x += weight * fibre.x;
+            y += weight * fibre.y;
+        }
+        (x / ea, y / ea)
+    }
+
+    /// Distance of a fibre from the bending axis through the reference point, where `angle`
+    /// is the direction of the axis from x and positive distances lie to its left
+    pub(crate) fn distance(&self, fibre: &Fibre, centroid: (f64, f64), angle: f64) -> f64 {
+        -(fibre.x - centroid.0) * angle.sin() + (fibre.y - centroid.1) * angle.cos()
+    }
+
+    /// This function integrates the fibre stresses for a plane of strain, returning the axial
+    /// force (tension positive) and the moments about x and y
+    pub fn resultants(&self, strain: f64, curvature: f64, angle: f64) -> (f64, f64, f64) {
+        let centroid = self.centroid();
+        self.fibres.iter().fold((0., 0., 0.), |acc, fibre| {
+            let d = self.distance(fibre, centroid, angle);
+            let force = self.laws[fibre.law].stress(strain - curvature * d) * fibre.area;
+            (
+                acc.0 + force,
+                acc.1 - force * (fibre.y - centroid.1),
+                acc.2 - force * (fibre.x - centroid.0),
+            )
+        })
+    }
+
+    /// This function finds the reference strain giving an axial force of `axial`
+    /// (compression positive) at a curvature. Returns None if the section cannot carry it.
+    pub fn equilibrium(&self, axial: f64, curvature: f64, angle: f64) -> Option<f64> {
+        let target = -axial;
+        let force = |strain: f64| self.resultants(strain, curvature, angle).0;
+        let (mut low, mut high) = (-1., 1.);
+        if force(low) > target || force(high) < target {
+            return None;
+        }
+        for _ in 0..100 {
+            let mid = (low + high) / 2.;
+            if force(mid) < target {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        Some((low + high) / 2.)
+    }
+
+    /// This function checks whether any fibre meets a condition under a plane of strain
+    fn any(
+        &self,
+        strain: f64,
+        curvature: f64,
+        angle: f64,
+        test: impl Fn(&dyn StressStrain, f64) -> bool,
+    ) -> bool {
+        let centroid = self.centroid();
+        self.fibres.iter().any(|fibre| {
+            let e = strain - curvature * self.distance(fibre, centroid, angle);
+            test(self.laws[fibre.law].as_ref(), e)
+        })
+    }
+
+    fn point(&self, axial: f64, curvature: f64, angle: f64) -> Option<Curvature> {
+        let strain = self.equilibrium(axial, curvature, angle)?;
+        let (_, mx, my) = self.resultants(strain, curvature, angle);
+        let centroid = self.centroid();
+        let top = self
+            .fibres
+            .iter()
+            .map(|f| self.distance(f, centroid, angle))
+            .fold(f64::MIN, f64::max);
+        Some(Curvature {
+            curvature,
+            moment: mx * angle.cos() - my * angle.sin(),
+            strain,
+            depth: if curvature == 0. {
+                f64::INFINITY
+            } else {
+                top - strain / curvature
+            },
+        })
+    }
+
+    /// This function traces the moment-curvature response under a constant axial force
+    /// (compression positive), bending about an axis at `angle` from x so that the fibres to
+    /// its left are compressed, up to the first fibre reaching a failure strain. Returns None
+    /// if the section cannot carry the axial force, or if no fibre fails by 2⁶⁰ times the
+    /// curvature at which the most extreme fibre would first yield, as with laws without
+    /// failure strains.
+    pub fn moment_curvature(
+        &self,
+        axial: f64,
+        angle: f64,
+        steps: usize,
+    ) -> Option<MomentCurvature> {
+        let failed = |curvature: f64| match self.equilibrium(axial, curvature, angle) {
+            Some(strain) => self.any(strain, curvature, angle, |law, e| {
+                let (low, high) = law.limits();
+                e < low || e > high
+            }),
+            None => true,
+        };
+        if failed(0.) {
+            return None;
+        }
+
+        let centroid = self.centroid();
+        let (low, high) = self.fibres.iter().fold((f64::MAX, f64::MIN), |acc, f| {
+            let d = self.distance(f, centroid, angle);
+            (acc.0.min(d), acc.1.max(d))
+        });
+        let yield_strain = self
+            .laws
+            .iter()
+            .map(|law| law.yield_strain())
+            .fold(f64::MAX, f64::min);
+        let mut high_curvature = 2. * yield_strain / (high - low);
+        let mut doublings = 0;
+        while !failed(high_curvature) {
+            if doublings == 60 {
+                return None;
+            }
+            high_curvature *= 2.;
+            doublings += 1;
+        }
+        let mut low_curvature = 0.;
+        for _ in 0..60 {
+            let mid = (low_curvature + high_curvature) / 2.;
+            if failed(mid) {
+                high_curvature = mid;
+            } else {
+                low_curvature = mid;
+            }
+        }
+        let ultimate = low_curvature;
+
+        let yielded = |curvature: f64| match self.equilibrium(axial, curvature, angle) {
+            Some(strain) => self.any(strain, curvature, angle, |law, e| law.yielded(e)),
+            None => true,
+        };
+        let first_yield = if yielded(ultimate) {
+            let (mut low, mut high) = (0., ultimate);
+            for _ in 0..60 {
+                let mid = (low + high) / 2.;
+                if yielded(mid) {
+                    high = mid;
+                } else {
+                    low = mid;
+                }
+            }
+            self.point(axial, high, angle)
+        } else {
+            None
+        };
+
+        let steps = steps.max(1);
+        let points = (0..=steps)
+            .filter_map(|i| self.point(axial, ultimate * i as f64 / steps as f64, angle))
+            .collect();
+        Some(MomentCurvature {
+            points,
+            first_yield,
+            ultimate: self.point(axial, ultimate, angle)?,
+        })
+    }
+}
+
+impl Section {
+    /// This function discretises the built outline into fibres of a single material
+    pub fn fibres(&self, size: f64, law: Box<dyn StressStrain>) -> FibreSection {
+        let mut fibres = FibreSection::new();
+        let law = fibres.add_law(law);
+        fibres.add_outline(&self.build(), size, law);
+        fibres
+    }
+}
+
+/// This function lists the points of a ring, without the closing point
+pub fn ring(line: &LineString<f64>) -> Vec<(f64, f64)> {
+    let mut points: Vec<(f64, f64)> = line.0.iter().map(|c| (c.x, c.y)).collect();
+    if points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    points
+}
+
+/// This function clips a ring to the half-plane where `normal · p <= offset`
+/// (Sutherland-Hodgman)
+pub fn clip_half_plane(points: &[(f64, f64)], normal: (f64, f64), offset: f64) -> Vec<(f64, f64)> {
+    let side = |p: (f64, f64)| normal.0 * p.0 + normal.1 * p.1 - offset;
+    let mut clipped = vec![];
+    for i in 0..points.len() {
+        let a = points[i];
+        let b = points[(i + 1) % points.len()];
+        let (sa, sb) = (side(a), side(b));
+        if sa <= 0. {
+            clipped.push(a);
+        }
+        if (sa < 0. && sb > 0.) || (sa > 0. && sb < 0.) {
+            let t = sa / (sa - sb);
+            clipped.push((a.0 + t * (b.0 - a.0), a.1 + t * (b.1 - a.1)));
+        }
+    }
+    clipped
+}
+
+/// This function computes the unsigned area and the centroid of a ring
+pub fn ring_properties(points: &[(f64, f64)]) -> (f64, (f64, f64)) {
+    let (mut area, mut cx, mut cy) = (0., 0., 0.);
+    for i in 0..points.len() {
+        let a = points[i];
+        let b = points[(i + 1) % points.len()];
+        let cross = a.0 * b.1 - b.0 * a.1;
+        area += cross / 2.;
+        cx += (a.0 + b.0) * cross / 6.;
+        cy += (a.1 + b.1) * cross / 6.;
+    }
+    if area == 0. {
+        return (0., (0., 0.));
+    }
+    (area.abs(), (cx / area, cy / area))
+}
+
+/// This function discretises an outline into fibres by intersecting it with a square grid
+pub fn discretise(outline: &MultiPolygon<f64>, size: f64, law: usize) -> Vec<Fibre> {
+    let mut fibres = vec![];
+    for polygon in &outline.0 {
+        let exterior = ring(polygon.exterior());
+        let holes: Vec<Vec<(f64, f64)>> = polygon.interiors().iter().map(ring).collect();
+        let (x0, y0, x1, y1) =
+            exterior
+                .iter()
+                .fold((f64::MAX, f64::MAX, f64::MIN, f64::MIN), |acc, p| {
+                    (
+                        acc.0.min(p.0),
+                        acc.1.min(p.1),
+                        acc.2.max(p.0),
+                        acc.3.max(p.1),
+                    )
+                });
+        let nx = ((x1 - x0) / size).ceil().max(1.) as usize;
+        let ny = ((y1 - y0) / size).ceil().max(1.) as usize;
+        for i in 0..nx {
+            for j in 0..ny {
+                let (cx0, cy0) = (x0 + i as f64 * size, y0 + j as f64 * size);
+                let cell = |points: &[(f64, f64)]| {
+                    let clipped = clip_half_plane(points, (-1., 0.), -cx0);
+                    let clipped = clip_half_plane(&clipped, (1., 0.), cx0 + size);
+                    let clipped = clip_half_plane(&clipped, (0., -1.), -cy0);
+                    let clipped = clip_half_plane(&clipped, (0., 1.), cy0 + size);
+                    ring_properties(&clipped)
+                };
+                let (mut area, centre) = cell(&exterior);
+                let (mut mx, mut my) = (area * centre.0, area * centre.1);
+                for hole in &holes {
+                    let (a, c) = cell(hole);
+                    area -= a;
+                    mx -= a * c.0;
+                    my -= a * c.1;
+                }
+                if area > 1e-12 * size * size {
+                    fibres.push(Fibre {
+                        x: mx / area,
+                        y: my / area,
+                        area,
+                        law,
+                    });
+                }
+            }
+        }
+    }
+    fibres
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::law::ElasticPlastic;
+    use geo::Polygon;
+
+    const STEEL: ElasticPlastic = ElasticPlastic {
+        elastic: 200000.,
+        yield_strength: 400.,
+        ultimate_strain: 0.2,
+    };
+
+    /// A rectangle 10 wide and 20 deep cut into strips `size` deep
+    fn strips(law: ElasticPlastic, size: f64) -> FibreSection {
+        let mut fibres = FibreSection::new();
+        let law = fibres.add_law(Box::new(law));
+        let count = (20. / size).round() as usize;
+        for i in 0..count {
+            let y = -10. + (i as f64 + 0.5) * size;
+            fibres.add_fibre(0., y, 10. * size, law);
+        }
+        fibres
+    }
+
+    #[test]
+    fn elastic_plastic_rectangle() {
+        let (b, h, fy) = (10., 20., 400.);
+        let size = 0.05;
+        let response = strips(STEEL, size).moment_curvature(0., 0., 20).unwrap();
+        let first = response.first_yield.unwrap();
+        let yield_strain = fy / 200000.;
+        // the middle of the outer strip yields at 2εy / (h - size)
+        assert!((first.curvature - 2. * yield_strain / (h - size)).abs() < 1e-9 * first.curvature);
+        assert!((first.curvature - 2. * yield_strain / h).abs() < 2. * size / h * first.curvature);
+        let my = fy * b * h * h / 6.;
+        assert!((first.moment - my).abs() < 2. * size / h * my);
+        // the core within εy / κ of the axis is still elastic at failure
+        let mp = fy * b * h * h / 4.;
+        assert!((response.ultimate.moment - mp).abs() < 1e-4 * mp);
+        assert!((response.ultimate.moment / first.moment - 1.5).abs() < 2. * size / h * 1.5);
+        // the top fibre reaches the failure strain
+        let top = h / 2. - size / 2.;
+        let strain = response.ultimate.strain - response.ultimate.curvature * top;
+        assert!((strain + 0.2).abs() < 1e-6);
+        // the moment rises with the curvature
+        for pair in response.points.windows(2) {
+            assert!(pair[1].moment >= pair[0].moment);
+        }
+    }
+
+    #[test]
+    fn axial_force_moves_the_neutral_axis() {
+        let fibres = strips(STEEL, 0.5);
+        // the squash load is the whole area at yield
+        assert!(fibres.equilibrium(200. * 400. * 1.001, 0., 0.).is_none());
+        let strain = fibres.equilibrium(200. * 400. / 2., 0., 0.).unwrap();
+        assert!((strain + 0.001).abs() < 1e-12);
+        let response = fibres.moment_curvature(200. * 400. / 2., 0., 10).unwrap();
+        let bare = fibres.moment_curvature(0., 0., 10).unwrap();
+        assert!(response.ultimate.moment < bare.ultimate.moment);
+        // the plastic moment under half the squash load is three quarters of Mp
+        let mp = 400. * 10. * 400. / 4.;
+        assert!((response.ultimate.moment - 0.75 * mp).abs() < 1e-3 * mp);
+    }
+
+    #[test]
+    fn laws_without_failure_strains() {
+        let law = ElasticPlastic {
+            ultimate_strain: f64::INFINITY,
+            ..STEEL
+        };
+        assert!(strips(law, 0.5).moment_curvature(0., 0., 10).is_none());
+    }
+
+    #[test]
+    fn outline_fibres() {
+        let outline = MultiPolygon(vec![Polygon::new(
+            LineString::from(vec![(0., 0.), (10., 0.), (10., 10.), (0., 10.)]),
+            vec![LineString::from(vec![
+                (2.5, 2.5),
+                (7.5, 2.5),
+                (7.5, 7.5),
+                (2.5, 7.5),
+            ])],
+        )]);
+        let mut fibres = FibreSection::new();
+        let law = fibres.add_law(Box::new(STEEL));
+        fibres.add_outline(&outline, 1., law);
+        let area: f64 = fibres.fibres.iter().map(|f| f.area).sum();
+        assert!((area - 75.).abs() < 1e-9);
+        let (x, y) = fibres.centroid();
+        assert!((x - 5.).abs() < 1e-9 && (y - 5.).abs() < 1e-9);
+        assert!(fibres.fibres.iter().all(|f| f.area <= 1. + 1e-12));
+    }
+}
@@ -0,0 +1,199 @@
+// A uniform grid over the elements of a section, so that the checks made when drawing
+// look only at the elements close by
+use petgraph::graph::EdgeIndex;
+use std::collections::HashMap;
+
+/// The most cells an element may span along either axis before the grid is coarsened
+const SPAN: i64 = 16;
+
+type Cell = (i64, i64);
+
+/// The padded bounding box of an element: its lower left and upper right corners
+type Bounds = ((f64, f64), (f64, f64));
+
+/// A grid of square cells, each listing the elements whose padded bounding boxes overlap it
+#[derive(Clone, Debug, Default)]
+pub struct Grid {
+    /// The side of a cell, or zero until the first element is inserted
+    cell: f64,
+    cells: HashMap<Cell, Vec<EdgeIndex>>,
+    bounds: HashMap<EdgeIndex, Bounds>,
+}
+
+impl Grid {
+    /// This function instantiates an empty grid
+    pub fn new() -> Grid {
+        Grid::default()
+    }
+
+    /// This function instantiates an empty grid of cells of the given side
+    pub fn with_cell(cell: f64) -> Grid {
+        Grid {
+            cell,
+            ..Grid::default()
+        }
+    }
+
+    /// The number of elements in the grid
+    pub fn len(&self) -> usize {
+        self.bounds.len()
+    }
+
+    /// Whether or not the grid is empty
+    pub fn is_empty(&self) -> bool {
+        self.bounds.is_empty()
+    }
+
+    /// This function inserts an element between two points, padded all round by the given
+    /// distance. An element already in the grid is replaced.
+    pub fn insert(&mut self, edge: EdgeIndex, a: (f64, f64), b: (f64, f64), pad: f64) {
+        self.remove(edge);
+        let bounds = (
+            (a.0.min(b.0) - pad, a.1.min(b.1) - pad),
+            (a.0.max(b.0) + pad, a.1.max(b.1) + pad),
+        );
+        let extent = (bounds.1 .0 - bounds.0 .0).max(bounds.1 .1 - bounds.0 .1);
+        if self.cell == 0. {
+            self.cell = if extent > 0. { extent } else { 1. };
+        } else if extent > SPAN as f64 * self.cell {
+            self.cell = extent / SPAN as f64 * 2.;
+            self.bounds.insert(edge, bounds);
+            self.regrid();
+            return;
+        }
+        self.bounds.insert(edge, bounds);
+        self.fill(edge, bounds);
+    }
+
+    /// This function removes an element from the grid
+    pub fn remove(&mut self, edge: EdgeIndex) {
+        if let Some(bounds) = self.bounds.remove(&edge) {
+            let cells: Vec<Cell> = self.covered(bounds).collect();
+            for cell in cells {
+                if let Some(list) = self.cells.get_mut(&cell) {
+                    list.retain(|&e| e != edge);
+                    if list.is_empty() {
+                        self.cells.remove(&cell);
+                    }
+                }
+            }
+        }
+    }
+
+    /// This function files an element under a new index, as when the graph moves its last
+    /// element into the place of a removed one
+    pub fn rename(&mut self, from: EdgeIndex, to: EdgeIndex) {
+        if let Some(bounds) = self.bounds.remove(&from) {
+            let cells: Vec<Cell> = self.covered(bounds).collect();
+            for cell in cells {
+                if let Some(list) = self.cells.get_mut(&cell) {
+                    for e in list.iter_mut().filter(|e| **e == from) {
+                        *e = to;
+                    }
+                }
+            }
+            self.bounds.insert(to, bounds);
+        }
+    }
+
+    /// The elements whose padded bounding boxes overlap the box between two corners
+    pub fn query(&self, min: (f64, f64), max: (f64, f64)) -> Vec<EdgeIndex> {
+        let mut found: Vec<EdgeIndex> = self
+            .covered((min, max))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(|edge| {
+                let (lower, upper) = self.bounds[edge];
+                lower.0 <= max.0 && upper.0 >= min.0 && lower.1 <= max.1 && upper.1 >= min.1
+            })
+            .collect();
+        found.sort();
+        found.dedup();
+        found
+    }
+
+    /// The cells overlapping a box. A box covering more cells than there are filled is
+    /// answered with the filled cells instead.
+    fn covered(&self, (min, max): Bounds) -> Box<dyn Iterator<Item = Cell> + '_> {
+        if self.cell == 0. {
+            return Box::new(std::iter::empty());
+        }
+        let lower = self.locate(min);
+        let upper = self.locate(max);
+        let count = (upper.0 - lower.0 + 1) as f64 * (upper.1 - lower.1 + 1) as f64;
+        if count > self.cells.len() as f64 {
+            return Box::new(self.cells.keys().copied().filter(move |cell| {
+                cell.0 >= lower.0 && cell.0 <= upper.0 && cell.1 >= lower.1 && cell.1 <= upper.1
+            }));
+        }
+        Box::new((lower.0..=upper.0).flat_map(move |i| (lower.1..=upper.1).map(move |j| (i, j))))
+    }
+
+    fn locate(&self, point: (f64, f64)) -> Cell {
+        (
+            (point.0 / self.cell).floor() as i64,
+            (point.1 / self.cell).floor() as i64,
+        )
+    }
+
+    fn fill(&mut self, edge: EdgeIndex, bounds: Bounds) {
+        let (lower, upper) = (self.locate(bounds.0), self.locate(bounds.1));
+        for i in lower.0..=upper.0 {
+            for j in lower.1..=upper.1 {
+                self.cells.entry((i, j)).or_default().push(edge);
+            }
+        }
+    }
+
+    /// This function files every element again after the cell size changes
+    fn regrid(&mut self) {
+        self.cells.clear();
+        let bounds: Vec<(EdgeIndex, Bounds)> = self.bounds.iter().map(|(&e, &b)| (e, b)).collect();
+        for (edge, bounds) in bounds {
+            self.fill(edge, bounds);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_finds_overlapping_boxes() {
+        let mut grid = Grid::new();
+        grid.insert(EdgeIndex::new(0), (0., 0.), (10., 0.), 1.);
+        grid.insert(EdgeIndex::new(1), (20., 20.), (30., 20.), 1.);
+        assert_eq!(grid.query((5., 0.5), (5., 0.5)), vec![EdgeIndex::new(0)]);
+        assert_eq!(grid.query((25., 20.), (25., 20.)), vec![EdgeIndex::new(1)]);
+        assert!(grid.query((15., 10.), (15., 10.)).is_empty());
+        assert_eq!(grid.query((-5., -5.), (35., 35.)).len(), 2);
+    }
+
+    #[test]
+    fn coarsens_for_long_elements() {
+        let mut grid = Grid::new();
+        grid.insert(EdgeIndex::new(0), (0., 0.), (1., 0.), 0.);
+        grid.insert(EdgeIndex::new(1), (0., 5.), (1000., 5.), 0.);
+        assert_eq!(grid.query((0.5, 0.), (0.5, 0.)), vec![EdgeIndex::new(0)]);
+        assert_eq!(grid.query((999., 5.), (999., 5.)), vec![EdgeIndex::new(1)]);
+        assert!(grid.cells.len() as i64 <= 2 * SPAN);
+    }
+
+    #[test]
+    fn rename_after_remove() {
+        // the graph moves its last element into the place of a removed one
+        let mut grid = Grid::new();
+        grid.insert(EdgeIndex::new(0), (0., 0.), (10., 0.), 1.);
+        grid.insert(EdgeIndex::new(1), (0., 5.), (10., 5.), 1.);
+        grid.insert(EdgeIndex::new(2), (0., 10.), (10., 10.), 1.);
+        grid.remove(EdgeIndex::new(0));
+        grid.rename(EdgeIndex::new(2), EdgeIndex::new(0));
+        assert_eq!(grid.len(), 2);
+        assert_eq!(grid.query((5., 10.), (5., 10.)), vec![EdgeIndex::new(0)]);
+        assert_eq!(grid.query((5., 5.), (5., 5.)), vec![EdgeIndex::new(1)]);
+        assert!(grid.query((5., 0.), (5., 0.)).is_empty());
+        assert!(grid.cells.values().flatten().all(|&e| e.index() < 2));
+    }
+}
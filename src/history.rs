@@ -0,0 +1,347 @@
+// An edit history of a section, kept as the changes each command made to the graph
+use crate::build::{Element, Node, Section};
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+
+/// A change made to the graph, holding what is needed to make it again or take it back
+#[derive(Clone, Debug)]
+pub(crate) enum Change {
+    AddNode(NodeIndex, Node),
+    /// A node without elements was removed
+    RemoveNode(NodeIndex, Node),
+    /// A node moved from one position to another
+    MoveNode(NodeIndex, (f64, f64), (f64, f64)),
+    AddEdge(EdgeIndex, NodeIndex, NodeIndex, Element),
+    RemoveEdge(EdgeIndex, NodeIndex, NodeIndex, Element),
+    /// A node was renamed from one label to another
+    LabelNode(NodeIndex, Option<String>, Option<String>),
+    LabelEdge(EdgeIndex, Option<String>, Option<String>),
+}
+
+/// The changes made by one command
+#[derive(Clone, Debug)]
+struct Command {
+    name: &'static str,
+    changes: Vec<Change>,
+}
+
+/// The commands made on a section, those taken back, and named points in between
+#[derive(Clone, Debug, Default)]
+pub struct History {
+    done: Vec<Command>,
+    undone: Vec<Command>,
+    /// The names of checkpoints and the number of commands done at each
+    checkpoints: Vec<(String, usize)>,
+    /// The changes of the command being made, which may call other commands
+    open: Vec<Change>,
+    depth: usize,
+}
+
+impl History {
+    pub(crate) fn begin(&mut self) {
+        self.depth += 1;
+    }
+
+    pub(crate) fn record(&mut self, change: Change) {
+        self.open.push(change);
+    }
+
+    /// This function closes a command. Commands called by another are part of it.
+    pub(crate) fn end(&mut self, name: &'static str) {
+        self.depth -= 1;
+        if self.depth > 0 || self.open.is_empty() {
+            return;
+        }
+        let done = self.done.len();
+        self.checkpoints.retain(|&(_, at)| at <= done);
+        self.undone.clear();
+        self.done.push(Command {
+            name,
+            changes: std::mem::take(&mut self.open),
+        });
+    }
+}
+
+impl Section {
+    /// This function takes back the last command and returns its name, if there is one
+    pub fn undo(&mut self) -> Option<&'static str> {
+        let command = self.history.done.pop()?;
+        self.invalidate();
+        for change in command.changes.iter().rev() {
+            self.revert(change);
+        }
+        let name = command.name;
+        self.history.undone.push(command);
+        Some(name)
+    }
+
+    /// This function makes the last command taken back again and returns its name, if there
+    /// is one
+    pub fn redo(&mut self) -> Option<&'static str> {
+        let command = self.history.undone.pop()?;
+        self.invalidate();
+        for change in &command.changes {
+            self.apply(change);
+        }
+        let name = command.name;
+        self.history.done.push(command);
+        Some(name)
+    }
+
+    /// Whether or not there is a command to undo
+    pub fn can_undo(&self) -> bool {
+        !self.history.done.is_empty()
+    }
+
+    /// Whether or not there is a command to redo
+    pub fn can_redo(&self) -> bool {
+        !self.history.undone.is_empty()
+    }
+
+    /// This function names the current state, replacing any checkpoint of the same name.
+    /// Checkpoints are forgotten when the commands after them are taken back and replaced.
+    pub fn checkpoint(&mut self, name: &str) {
+        let done = self.history.done.len();
+        self.history.checkpoints.retain(|(other, _)| other != name);
+        self.history.checkpoints.push((name.to_string(), done));
+    }
+
+    /// This function undoes or redoes commands back to a checkpoint, returning whether or
+    /// not the checkpoint exists
+    pub fn restore(&mut self, name: &str) -> bool {
+        let at = match self
+            .history
+            .checkpoints
+            .iter()
+            .find(|(other, _)| other == name)
+        {
+            Some(&(_, at)) => at,
+            None => return false,
+        };
+        while self.history.done.len() > at {
+            self.undo();
+        }
+        while self.history.done.len() < at {
+            self.redo();
+        }
+        true
+    }
+
+    /// This function takes back a change, leaving every other node and element at the
+    /// index it had before the change
+    fn revert(&mut self, change: &Change) {
+        match change {
+            Change::AddNode(node, _) => {
+                self.graph.remove_node(*node);
+            }
+            Change::RemoveNode(node, weight) => {
+                let node = *node;
+                // the graph moved its last node into the place of the removed one, so that
+                // node goes back to the end, taking its elements with it
+                if node.index() < self.graph.node_count() {
+                    let moved = self.graph[node].clone();
+                    let last = self.graph.add_node(moved);
+                    let edges: Vec<(EdgeIndex, NodeIndex)> = self
+                        .graph
+                        .edges(node)
+                        .map(|edge| {
+                            let other = if edge.source() == node {
+                                edge.target()
+                            } else {
+                                edge.source()
+                            };
+                            (edge.id(), other)
+                        })
+                        .collect();
+                    for (edge, other) in edges {
+                        self.repoint(edge, other, last);
+                    }
+                    self.graph[node] = weight.clone();
+                } else {
+                    self.graph.add_node(weight.clone());
+                }
+            }
+            Change::MoveNode(node, (x, y), _) => {
+                self.graph[*node].x = *x;
+                self.graph[*node].y = *y;
+                self.refile(*node);
+            }
+            Change::AddEdge(edge, ..) => {
+                self.detach(*edge);
+            }
+            Change::RemoveEdge(edge, a, b, element) => {
+                // likewise the last element goes back to the end
+                if edge.index() < self.graph.edge_count() {
+                    let (c, d) = self.graph.edge_endpoints(*edge).unwrap();
+                    let moved = self.graph[*edge].clone();
+                    self.attach(c, d, moved);
+                    self.attach(*a, *b, element.clone());
+                    self.detach(*edge);
+                } else {
+                    self.attach(*a, *b, element.clone());
+                }
+            }
+            Change::LabelNode(node, old, _) => self.graph[*node].label = old.clone(),
+            Change::LabelEdge(edge, old, _) => self.graph[*edge].label = old.clone(),
+        }
+    }
+
+    /// This function makes a change again
+    fn apply(&mut self, change: &Change) {
+        match change {
+            Change::AddNode(_, weight) => {
+                self.graph.add_node(weight.clone());
+            }
+            Change::RemoveNode(node, _) => {
+                self.graph.remove_node(*node);
+            }
+            Change::MoveNode(node, _, (x, y)) => {
+                self.graph[*node].x = *x;
+                self.graph[*node].y = *y;
+                self.refile(*node);
+            }
+            Change::AddEdge(_, a, b, element) => {
+                self.attach(*a, *b, element.clone());
+            }
+            Change::RemoveEdge(edge, ..) => {
+                self.detach(*edge);
+            }
+            Change::LabelNode(node, _, label) => self.graph[*node].label = label.clone(),
+            Change::LabelEdge(edge, _, label) => self.graph[*edge].label = label.clone(),
+        }
+    }
+
+    /// This function gives an element new ends, keeping its index: the new element is added
+    /// at the end, and removing the old one moves it into its place
+    fn repoint(&mut self, edge: EdgeIndex, a: NodeIndex, b: NodeIndex) {
+        let element = self.graph[edge].clone();
+        self.attach(a, b, element);
+        self.detach(edge);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Snapshot = (Vec<(f64, f64, Option<String>)>, Vec<(usize, usize, f64)>);
+
+    /// The nodes and elements of a section, by index
+    fn snapshot(section: &Section) -> Snapshot {
+        let nodes = section
+            .graph
+            .node_indices()
+            .map(|n| {
+                let node = &section.graph[n];
+                (node.x, node.y, node.label.clone())
+            })
+            .collect();
+        let edges = section
+            .graph
+            .edge_indices()
+            .map(|e| {
+                let (a, b) = section.graph.edge_endpoints(e).unwrap();
+                (a.index(), b.index(), section.graph[e].thickness)
+            })
+            .collect();
+        (nodes, edges)
+    }
+
+    /// Whether the spatial index finds every element at its middle
+    fn indexed(section: &Section) -> bool {
+        section.graph.edge_indices().all(|edge| {
+            let (a, b) = section.graph.edge_endpoints(edge).unwrap();
+            let middle = (
+                (section.graph[a].x + section.graph[b].x) / 2.,
+                (section.graph[a].y + section.graph[b].y) / 2.,
+            );
+            section.edges_within(middle, middle).contains(&edge)
+        })
+    }
+
+    fn flanges() -> Section {
+        let mut section = Section::new(0.3);
+        let n: Vec<_> = [(-9., 9.), (9., 9.), (-9., -9.), (9., -9.)]
+            .iter()
+            .map(|&(x, y)| section.add_node(x, y))
+            .collect();
+        section.add_edge(n[0], n[1], Some(1.), None);
+        section.add_edge(n[2], n[3], Some(1.), None);
+        section
+    }
+
+    #[test]
+    fn undo_redo_add_edge_with_splits() {
+        let mut section = flanges();
+        let (top, bottom) = (section.add_node(0., 14.), section.add_node(0., -14.));
+        let before = snapshot(&section);
+        section.add_edge(top, bottom, Some(0.5), None);
+        let after = snapshot(&section);
+        assert_eq!(after.1.len(), 7);
+
+        assert_eq!(section.undo(), Some("add edge"));
+        assert_eq!(snapshot(&section), before);
+        assert!(indexed(&section));
+        assert_eq!(section.redo(), Some("add edge"));
+        assert_eq!(snapshot(&section), after);
+        assert!(indexed(&section));
+    }
+
+    #[test]
+    fn undo_redo_delete_node_with_index_swap() {
+        let mut section = flanges();
+        let labelled = NodeIndex::new(3);
+        section.label_node(labelled, Some("last"));
+        let before = snapshot(&section);
+        // the last node moves into the place of the first
+        section.delete_node(NodeIndex::new(0));
+        let after = snapshot(&section);
+        assert_eq!(section.node_by_label("last"), Some(NodeIndex::new(0)));
+
+        assert_eq!(section.undo(), Some("delete node"));
+        assert_eq!(snapshot(&section), before);
+        assert_eq!(section.node_by_label("last"), Some(labelled));
+        assert!(indexed(&section));
+        assert_eq!(section.redo(), Some("delete node"));
+        assert_eq!(snapshot(&section), after);
+        assert!(indexed(&section));
+    }
+
+    #[test]
+    fn undo_move_across_a_plate() {
+        let mut section = flanges();
+        let (a, b) = (section.add_node(0., 12.), section.add_node(0., 20.));
+        section.add_edge(a, b, Some(1.), None);
+        let before = snapshot(&section);
+        section.move_node(a, 0., 0.);
+        assert_ne!(snapshot(&section), before);
+        section.undo();
+        assert_eq!(snapshot(&section), before);
+        assert!(indexed(&section));
+    }
+
+    #[test]
+    fn checkpoints() {
+        let mut section = flanges();
+        section.checkpoint("flanges");
+        let flanges = snapshot(&section);
+        let (a, b) = (section.add_node(0., 14.), section.add_node(0., -14.));
+        section.add_edge(a, b, None, None);
+        section.checkpoint("web");
+        let web = snapshot(&section);
+
+        assert!(section.restore("flanges"));
+        assert_eq!(snapshot(&section), flanges);
+        assert!(section.can_redo());
+        assert!(section.restore("web"));
+        assert_eq!(snapshot(&section), web);
+        assert!(!section.restore("missing"));
+
+        // taking back and replacing commands forgets the checkpoints after them
+        section.restore("flanges");
+        section.add_node(0., 30.);
+        assert!(!section.can_redo());
+        assert!(!section.restore("web"));
+        assert!(section.restore("flanges"));
+    }
+}
@@ -0,0 +1,259 @@
+// Axial force and biaxial moment interaction surfaces from fibre sections
+use crate::fibre::FibreSection;
+use serde::Serialize;
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io::{self, prelude::*};
+use std::path::Path;
+
+/// A point on the interaction surface. Axial force is compression positive, Mx is positive
+/// when it compresses fibres at positive y and My when it compresses fibres at positive x,
+/// with moments taken about the modulus-weighted centroid of the fibres.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Point {
+    pub axial: f64,
+    pub mx: f64,
+    pub my: f64,
+}
+
+/// An interaction surface, stored as one meridian of points per neutral axis angle running
+/// from pure tension to pure compression
+#[derive(Clone, Debug, Serialize)]
+pub struct Surface {
+    pub meridians: Vec<Vec<Point>>,
+    /// Axial strength in tension, as a negative force
+    pub tension: f64,
+    /// Axial strength in compression
+    pub compression: f64,
+}
+
+impl FibreSection {
+    /// This function generates the interaction surface by sweeping `angles` neutral axis
+    /// angles and `depths` neutral axis depths per angle. The extreme compression fibre is
+    /// held at the smallest failure strain in compression of the laws.
+    pub fn interaction(&self, angles: usize, depths: usize) -> Surface {
+        let crushing = self
+            .laws
+            .iter()
+            .map(|law| -law.limits().0)
+            .fold(f64::INFINITY, f64::min);
+        let rupture = self
+            .laws
+            .iter()
+            .map(|law| law.limits().1)
+            .fold(f64::INFINITY, f64::min)
+            .min(1.);
+        let point = |strain: f64, curvature: f64, angle: f64| {
+            let (axial, mx, my) = self.resultants(strain, curvature, angle);
+            Point {
+                axial: -axial,
+                mx,
+                my,
+            }
+        };
+        let tension = point(rupture, 0., 0.).axial;
+        let compression = point(-crushing, 0., 0.).axial;
+
+        let centroid = self.centroid();
+        let angles = angles.max(3);
+        let depths = depths.max(2);
+        let meridians = (0..angles)
+            .map(|i| {
+                let angle = 2. * PI * i as f64 / angles as f64;
+                let (low, high) = self.fibres.iter().fold((f64::MAX, f64::MIN), |acc, f| {
+                    let d = self.distance(f, centroid, angle);
+                    (acc.0.min(d), acc.1.max(d))
+                });
+                let height = high - low;
+                let mut meridian = vec![Point {
+                    axial: tension,
+                    mx: 0.,
+                    my: 0.,
+                }];
+                // neutral axis depths spaced geometrically from 0.1% to 100 times the height
+                for j in 0..depths {
+                    let depth = height * 1e-3 * 1e5f64.powf(j as f64 / (depths - 1) as f64);
+                    let curvature = crushing / depth;
+                    meridian.push(point(curvature * (high - depth), curvature, angle));
+                }
+                meridian.push(Point {
+                    axial: compression,
+                    mx: 0.,
+                    my: 0.,
+                });
+                meridian
+            })
+            .collect();
+
+        Surface {
+            meridians,
+            tension,
+            compression,
+        }
+    }
+}
+
+impl Surface {
+    /// All the points of the surface
+    pub fn points(&self) -> Vec<Point> {
+        self.meridians.iter().flatten().copied().collect()
+    }
+
+    /// This function computes the contour of the surface at an axial force, as a moment
+    /// point on each meridian. Returns None if the force is outside the axial strengths.
+    pub fn contour(&self, axial: f64) -> Option<Vec<(f64, f64)>> {
+        if axial < self.tension || axial > self.compression {
+            return None;
+        }
+        self.meridians
+            .iter()
+            .map(|meridian| {
+                meridian.windows(2).find_map(|pair| {
+                    let (a, b) = (pair[0], pair[1]);
+                    if (a.axial <= axial && axial <= b.axial)
+                        || (b.axial <= axial && axial <= a.axial)
+                    {
+                        let t = if b.axial == a.axial {
+                            0.
+                        } else {
+                            (axial - a.axial) / (b.axial - a.axial)
+                        };
+                        Some((a.mx + t * (b.mx - a.mx), a.my + t * (b.my - a.my)))
+                    } else {
+                        None
+                    }
+                })
+            })
+            .collect()
+    }
+
+    /// This function computes the utilisation of a demand by the ratio of the demand moment
+    /// to the capacity in the same direction on the contour at the demand axial force, or by
+    /// the axial ratio when there is no moment. Infinite if the axial force is outside the
+    /// axial strengths.
+    pub fn utilisation(&self, axial: f64, mx: f64, my: f64) -> f64 {
+        let moment = mx.hypot(my);
+        if moment == 0. {
+            return if axial >= 0. {
+                axial / self.compression
+            } else {
+                axial / self.tension
+            };
+        }
+        let contour = match self.contour(axial) {
+            Some(contour) => contour,
+            None => return f64::INFINITY,
+        };
+        // intersect the ray from the origin through the demand with the contour edges
+        let direction = (mx / moment, my / moment);
+        let reach = (0..contour.len())
+            .filter_map(|i| {
+                let a = contour[i];
+                let b = contour[(i + 1) % contour.len()];
+                let edge = (b.0 - a.0, b.1 - a.1);
+                let denom = direction.0 * edge.1 - direction.1 * edge.0;
+                if denom.abs() < 1e-12 * (edge.0.hypot(edge.1) + 1.) {
+                    return None;
+                }
+                let t = (a.0 * edge.1 - a.1 * edge.0) / denom;
+                let s = (a.0 * direction.1 - a.1 * direction.0) / denom;
+                if t > 0. && (-1e-9..=1. + 1e-9).contains(&s) {
+                    Some(t)
+                } else {
+                    None
+                }
+            })
+            .fold(0., f64::max);
+        if reach == 0. {
+            f64::INFINITY
+        } else {
+            moment / reach
+        }
+    }
+
+    /// Whether a demand lies inside the surface
+    pub fn contains(&self, axial: f64, mx: f64, my: f64) -> bool {
+        self.utilisation(axial, mx, my) <= 1.
+    }
+
+    /// This function writes the points of the surface to a CSV file
+    pub fn write_csv(&self, path: &Path) -> io::Result<()> {
+        let mut text = String::from("axial,mx,my\n");
+        for point in self.points() {
+            text.push_str(&format!("{},{},{}\n", point.axial, point.mx, point.my));
+        }
+        File::create(path)?.write_all(text.as_bytes())?;
+        println!("successfully wrote to {}", path.display());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::build::Section;
+    use crate::concrete::{Concrete, Rebar};
+
+    /// A column 12 wide and 24 deep in 4 ksi concrete, with 3 of grade 60 steel at a depth
+    /// of 21.5 and 1 at a depth of 2.5
+    fn column() -> Section {
+        let mut section = Section::new(0.2);
+        section.set_solid(
+            vec![(0., 0.), (12., 0.), (12., 24.), (0., 24.)],
+            Concrete::ksi(4.),
+        );
+        for &(y, area) in &[(2.5, 3.), (21.5, 1.)] {
+            section.add_bar(Rebar {
+                x: 6.,
+                y,
+                area,
+                elastic: 29000.,
+                yield_strength: 60.,
+            });
+        }
+        section
+    }
+
+    #[test]
+    fn axial_strengths() {
+        let surface = column().concrete_fibres(0.5).unwrap().interaction(8, 10);
+        // the bars displace concrete, 0.85 f'c (Ag - As) + fy As
+        let squash = 0.85 * 4. * (12. * 24. - 4.) + 60. * 4.;
+        assert!((surface.compression - squash).abs() < 1e-6 * squash);
+        assert!((surface.tension + 60. * 4.).abs() < 1e-9);
+        for meridian in &surface.meridians {
+            assert_eq!(meridian[0].axial, surface.tension);
+            assert_eq!(meridian[meridian.len() - 1].axial, surface.compression);
+        }
+        assert!(surface.contains(0.5 * squash, 0., 0.));
+        assert!(!surface.contains(1.01 * squash, 0., 0.));
+    }
+
+    #[test]
+    fn balanced_point() {
+        // the extreme concrete fibre crushes at 0.003 as the tension steel yields
+        let fibres = column().concrete_fibres(0.1).unwrap();
+        let (crushing, yielding) = (0.003, 60. / 29000.);
+        let c = crushing / (crushing + yielding) * 21.5;
+        let centroid = fibres.centroid();
+        let curvature = (crushing + yielding) / 21.5;
+        let strain = curvature * (24. - centroid.1) - crushing;
+        let (axial, mx, my) = fibres.resultants(strain, curvature, 0.);
+
+        // the parabola-rectangle block reaches its plateau at 2/3 of the depth c, carrying
+        // 1 - r/3 of the plateau stress with its resultant (1 - r²)/2 + 5r²/12 over that
+        // fraction of c above the neutral axis
+        let r = 2. / 3.;
+        let block = 0.85 * 4. * 12. * c * (1. - r / 3.);
+        let lever = c * ((1. - r * r) / 2. + 5. * r * r / 12.) / (1. - r / 3.);
+        // the compression steel is on the plateau and yields
+        let compression = (60. - 0.85 * 4.) * 1.;
+        let tension = 60. * 3.;
+        assert!((-axial - (block + compression - tension)).abs() < 2e-3 * block);
+        let neutral = 24. - c;
+        let moment = block * (neutral + lever - centroid.1)
+            + compression * (21.5 - centroid.1)
+            + tension * (centroid.1 - 2.5);
+        assert!((mx - moment).abs() < 2e-3 * moment);
+        assert!(my.abs() < 1e-6 * moment);
+    }
+}
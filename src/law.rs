@@ -0,0 +1,228 @@
+// Uniaxial stress-strain laws, tension positive
+
+/// A uniaxial material law for fibre analyses
+pub trait StressStrain {
+    /// Stress at a strain
+    fn stress(&self, strain: f64) -> f64;
+    /// Initial tangent modulus
+    fn elastic(&self) -> f64;
+    /// Magnitude of the strain at first yield
+    fn yield_strain(&self) -> f64;
+    /// Whether a strain has reached yield
+    fn yielded(&self, strain: f64) -> bool {
+        strain.abs() >= self.yield_strain()
+    }
+    /// Failure strains in compression and in tension
+    fn limits(&self) -> (f64, f64);
+}
+
+/// Linear up to yield then perfectly plastic, symmetric in tension and compression
+#[derive(Clone, Copy, Debug)]
+pub struct ElasticPlastic {
+    pub elastic: f64,
+    pub yield_strength: f64,
+    pub ultimate_strain: f64,
+}
+
+/// Linear up to yield then linear strain hardening
+#[derive(Clone, Copy, Debug)]
+pub struct Bilinear {
+    pub elastic: f64,
+    pub yield_strength: f64,
+    /// Tangent modulus after yield
+    pub hardening: f64,
+    pub ultimate_strain: f64,
+}
+
+/// ε = σ/E + 0.002 (σ/σ0.2)^n
+#[derive(Clone, Copy, Debug)]
+pub struct RambergOsgood {
+    pub elastic: f64,
+    /// 0.2% proof stress
+    pub yield_strength: f64,
+    pub exponent: f64,
+    pub ultimate_strain: f64,
+}
+
+/// Concrete in compression (EN 1992-1-1 3.1.7), carrying no tension
+#[derive(Clone, Copy, Debug)]
+pub struct ParabolaRectangle {
+    pub strength: f64,
+    /// Strain at reaching the maximum strength, εc2
+    pub peak_strain: f64,
+    /// Ultimate strain, εcu2
+    pub ultimate_strain: f64,
+    pub exponent: f64,
+}
+
+impl ParabolaRectangle {
+    /// The law for strength classes up to C50/60
+    pub fn new(strength: f64) -> ParabolaRectangle {
+        ParabolaRectangle {
+            strength,
+            peak_strain: 0.002,
+            ultimate_strain: 0.0035,
+            exponent: 2.,
+        }
+    }
+}
+
+impl StressStrain for ElasticPlastic {
+    fn stress(&self, strain: f64) -> f64 {
+        (self.elastic * strain).clamp(-self.yield_strength, self.yield_strength)
+    }
+
+    fn elastic(&self) -> f64 {
+        self.elastic
+    }
+
+    fn yield_strain(&self) -> f64 {
+        self.yield_strength / self.elastic
+    }
+
+    fn limits(&self) -> (f64, f64) {
+        (-self.ultimate_strain, self.ultimate_strain)
+    }
+}
+
+impl StressStrain for Bilinear {
+    fn stress(&self, strain: f64) -> f64 {
+        let yield_strain = self.yield_strain();
+        if strain.abs() <= yield_strain {
+            self.elastic * strain
+        } else {
+            strain.signum() * (self.yield_strength + self.hardening * (strain.abs() - yield_strain))
+        }
+    }
+
+    fn elastic(&self) -> f64 {
+        self.elastic
+    }
+
+    fn yield_strain(&self) -> f64 {
+        self.yield_strength / self.elastic
+    }
+
+    fn limits(&self) -> (f64, f64) {
+        (-self.ultimate_strain, self.ultimate_strain)
+    }
+}
+
+impl StressStrain for RambergOsgood {
+    fn stress(&self, strain: f64) -> f64 {
+        // invert the strain function, which increases monotonically with stress
+        let strain_at =
+            |s: f64| s / self.elastic + 0.002 * (s / self.yield_strength).powf(self.exponent);
+        let (mut low, mut high) = (0., self.elastic * strain.abs());
+        for _ in 0..100 {
+            let mid = (low + high) / 2.;
+            if strain_at(mid) < strain.abs() {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        strain.signum() * (low + high) / 2.
+    }
+
+    fn elastic(&self) -> f64 {
+        self.elastic
+    }
+
+    fn yield_strain(&self) -> f64 {
+        self.yield_strength / self.elastic + 0.002
+    }
+
+    fn limits(&self) -> (f64, f64) {
+        (-self.ultimate_strain, self.ultimate_strain)
+    }
+}
+
+impl StressStrain for ParabolaRectangle {
+    fn stress(&self, strain: f64) -> f64 {
+        let compression = -strain;
+        if compression <= 0. {
+            0.
+        } else if compression < self.peak_strain {
+            -self.strength * (1. - (1. - compression / self.peak_strain).powf(self.exponent))
+        } else {
+            -self.strength
+        }
+    }
+
+    fn elastic(&self) -> f64 {
+        self.exponent * self.strength / self.peak_strain
+    }
+
+    fn yield_strain(&self) -> f64 {
+        self.peak_strain
+    }
+
+    fn yielded(&self, strain: f64) -> bool {
+        -strain >= self.peak_strain
+    }
+
+    fn limits(&self) -> (f64, f64) {
+        (-self.ultimate_strain, f64::INFINITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn elastic_plastic() {
+        let law = ElasticPlastic {
+            elastic: 200000.,
+            yield_strength: 400.,
+            ultimate_strain: 0.05,
+        };
+        assert_eq!(law.yield_strain(), 0.002);
+        assert_eq!(law.stress(0.001), 200.);
+        assert_eq!(law.stress(0.01), 400.);
+        assert_eq!(law.stress(-0.01), -400.);
+        assert!(law.yielded(-0.002) && !law.yielded(0.0019));
+        assert_eq!(law.limits(), (-0.05, 0.05));
+    }
+
+    #[test]
+    fn bilinear() {
+        let law = Bilinear {
+            elastic: 200000.,
+            yield_strength: 400.,
+            hardening: 2000.,
+            ultimate_strain: 0.05,
+        };
+        assert_eq!(law.stress(0.001), 200.);
+        assert!((law.stress(0.012) - 420.).abs() < 1e-9);
+        assert!((law.stress(-0.012) + 420.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ramberg_osgood() {
+        let law = RambergOsgood {
+            elastic: 70000.,
+            yield_strength: 250.,
+            exponent: 20.,
+            ultimate_strain: 0.1,
+        };
+        // the proof stress at its strain, and the strain function inverted elsewhere
+        assert!((law.stress(250. / 70000. + 0.002) - 250.).abs() < 1e-9);
+        let stress = 200.;
+        let strain = stress / 70000. + 0.002 * (stress / 250f64).powf(20.);
+        assert!((law.stress(-strain) + stress).abs() < 1e-9);
+        assert!(!law.yielded(strain));
+    }
+
+    #[test]
+    fn parabola_rectangle() {
+        let law = ParabolaRectangle::new(30.);
+        assert_eq!(law.stress(0.001), 0.);
+        assert!((law.stress(-0.001) + 0.75 * 30.).abs() < 1e-12);
+        assert_eq!(law.stress(-0.003), -30.);
+        assert_eq!(law.elastic(), 30000.);
+        assert!(law.yielded(-0.002) && !law.yielded(0.002));
+        assert_eq!(law.limits(), (-0.0035, f64::INFINITY));
+    }
+}
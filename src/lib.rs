@@ -0,0 +1,30 @@
+extern crate nalgebra as na;
+pub mod aisc;
+pub mod beam;
+pub mod buckling;
+pub mod build;
+mod cache;
+pub mod catalog;
+pub mod clipping;
+pub mod composite;
+pub mod concrete;
+pub mod design;
+pub mod draw;
+pub mod dxf;
+pub mod eurocode;
+pub mod fibre;
+pub mod grid;
+pub mod history;
+pub mod interaction;
+pub mod law;
+pub mod load;
+pub mod material;
+pub mod mesh;
+pub mod model;
+pub mod plate;
+pub mod properties;
+pub mod raster;
+pub mod results;
+pub mod stress;
+pub mod validate;
+pub mod warping;
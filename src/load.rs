@@ -1,16 +1,34 @@
-use flo_curves::{bezier, Coord2};
+use serde::{Deserialize, Serialize};
 
+/// A concentrated force, positive acting downward
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct PointLoad {
-    position: f32, // distance from left end of beam
-    mag: f32,
+    pub position: f64, // distance from left end of beam
+    pub mag: f64,
 }
+
+/// A uniform force per unit length between two positions, positive acting downward
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct DistributedLoad {
+    pub start: f64,
+    pub end: f64,
+    pub mag: f64,
+}
+
+/// A concentrated couple, positive counterclockwise
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Couple {
+    pub position: f64,
+    pub mag: f64,
+}
+
 pub enum Moment {
     Simple { left: f32, center: f32, right: f32 },
     Cantilever { left: f32, center: f32, right: f32 },
 }
 
 impl Moment {
-    pub fn beam_curve(&self, left: f32, center: f32, right: f32) /* -> bezier::Curve  */
+    pub fn beam_curve(&self, _left: f32, _center: f32, _right: f32) /* -> bezier::Curve  */
     {
         /*         match self {
             Moment::Simple {
@@ -41,6 +59,47 @@ impl Moment {
     }
 }
 
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Load {
+    Point(PointLoad),
+    Distributed(DistributedLoad),
+    Moment(Couple),
+}
+
+impl Load {
+    /// The shear and bending moment this load produces at `x` on the part of the beam to its
+    /// left, using the convention that upward shear on the left and sagging moment are positive
+    pub fn left_of(&self, x: f64, inclusive: bool) -> (f64, f64) {
+        let acts = |position: f64| position < x || (inclusive && position == x);
+        match *self {
+            Load::Point(load) => {
+                if acts(load.position) {
+                    (-load.mag, -load.mag * (x - load.position))
+                } else {
+                    (0., 0.)
+                }
+            }
+            Load::Distributed(load) => {
+                let end = load.end.min(x);
+                if end <= load.start {
+                    (0., 0.)
+                } else {
+                    let force = load.mag * (end - load.start);
+                    (-force, -force * (x - (load.start + end) / 2.))
+                }
+            }
+            Load::Moment(load) => {
+                if acts(load.position) {
+                    (0., -load.mag)
+                } else {
+                    (0., 0.)
+                }
+            }
+        }
+    }
+}
+
 /*         let p = [
     (0, 0),
     (1 / 3, moment / (9 * stiff)),
@@ -1,40 +1,591 @@
-extern crate nalgebra as na;
-use crate::build::Section;
-mod build;
-/* mod load;
-mod position; */
+use bender_rust::beam::Analysis;
+use bender_rust::build::{write_geojson, Section};
+use bender_rust::catalog;
+use bender_rust::dxf::{write_dxf, Drawing, Import, Thickness};
+use bender_rust::mesh::{self, Quality};
+use bender_rust::model::{Format as ModelFormat, Model};
+use bender_rust::properties::Properties;
+use bender_rust::results::{to_json, Units};
+use bender_rust::warping::Warping;
+use serde_json::{json, Map, Value};
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+/// Models are read from .toml, .json, .yaml or .yml files, see `bender_rust::model::Model`
+const USAGE: &str = "usage:
+    bender_rust section props <model> [--format text|json|csv] [--output <file>]
+    bender_rust section warping <model> [--size <length>] [--format text|json|csv]
+                                [--output <file>]
+    bender_rust section mesh <model> [--size <length>] [--angle <degrees>]
+                             [--output <file.geojson|file.vtk>]
+    bender_rust section draw <model> [--width <px>] [--output <file.svg>]
+    bender_rust section outline <model> [--output <file.geojson>]
+    bender_rust section dxf <model> [--output <file.dxf>]
+    bender_rust section check <model> [--format text|json] [--output <file>]
+    bender_rust beam analyze <model> [--divisions <n>] [--format text|json|csv]
+                             [--output <file>] [--draw <file.svg>]
+    bender_rust model convert <model> --output <file.toml|json|yaml>
+    bender_rust model import <file.dxf> [--layer <name>] [--thickness extrusion|width|<t>]
+                             [--output <file.toml|json|yaml>]
+    bender_rust catalog list [--format text|json|csv] [--output <file>]
+    bender_rust catalog lookup <name> [--format text|json|csv] [--output <file>]";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+/// Positional arguments and options following the subcommand
+struct Options {
+    args: Vec<String>,
+    format: Format,
+    output: Option<String>,
+    width: f64,
+    divisions: usize,
+    size: Option<f64>,
+    angle: Option<f64>,
+    draw: Option<String>,
+    layer: Option<String>,
+    thickness: Thickness,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Options, String> {
+        let mut options = Options {
+            args: vec![],
+            format: Format::Text,
+            output: None,
+            width: 600.,
+            divisions: 100,
+            size: None,
+            angle: None,
+            draw: None,
+            layer: None,
+            thickness: Thickness::Extrusion,
+        };
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if !arg.starts_with("--") {
+                options.args.push(arg.clone());
+                continue;
+            }
+            let value = iter
+                .next()
+                .ok_or_else(|| format!("missing value for {}", arg))?;
+            match arg.as_str() {
+                "--format" => {
+                    options.format = match value.as_str() {
+                        "text" => Format::Text,
+                        "json" => Format::Json,
+                        "csv" => Format::Csv,
+                        _ => return Err(format!("unknown format {}", value)),
+                    }
+                }
+                "--output" => options.output = Some(value.clone()),
+                "--width" => {
+                    options.width = value
+                        .parse()
+                        .map_err(|_| format!("invalid width {}", value))?
+                }
+                "--divisions" => {
+                    options.divisions = value
+                        .parse()
+                        .map_err(|_| format!("invalid number of divisions {}", value))?
+                }
+                "--size" => {
+                    options.size = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("invalid mesh size {}", value))?,
+                    )
+                }
+                "--angle" => {
+                    options.angle = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("invalid angle {}", value))?,
+                    )
+                }
+                "--draw" => options.draw = Some(value.clone()),
+                "--layer" => options.layer = Some(value.clone()),
+                "--thickness" => {
+                    options.thickness = match value.as_str() {
+                        "extrusion" => Thickness::Extrusion,
+                        "width" => Thickness::Width,
+                        _ => Thickness::Constant(
+                            value
+                                .parse()
+                                .map_err(|_| format!("invalid thickness {}", value))?,
+                        ),
+                    }
+                }
+                _ => return Err(format!("unknown option {}", arg)),
+            }
+        }
+        Ok(options)
+    }
+
+    /// The single positional argument, such as the model file
+    fn target(&self) -> Result<&str, String> {
+        match self.args.as_slice() {
+            [target] => Ok(target),
+            [] => Err("missing argument".to_string()),
+            _ => Err(format!("unexpected arguments {:?}", &self.args[1..])),
+        }
+    }
+
+    /// This function writes the results to the output file, or to stdout without one
+    fn emit(&self, text: String) -> Result<(), String> {
+        match &self.output {
+            Some(path) => {
+                fs::write(path, text).map_err(|why| format!("couldn't write {}: {}", path, why))?;
+                println!("successfully wrote to {}", path);
+            }
+            None => print!("{}", text),
+        }
+        Ok(())
+    }
+}
 
 fn main() {
-    /*     let x: [f64; 6] = [-0.5, 0.0, 0.5, -0.5, 0.0, 0.5];
-    let y: [f64; 6] = [0.5, 0.5, 0.5, -0.5, -0.5, -0.5]; */
-
-    /*     let mut j = Section::new(0.3);
-    j.add_node(1., 2.); */
-    let mut x = Section::new(0.3);
-    let a = x.add_node(-9., 9.);
-    let b = x.add_node(0.0, 9.);
-    let c = x.add_node(9., 9.);
-    let d = x.add_node(-9., -9.);
-    let e = x.add_node(0.0, -9.);
-    let f = x.add_node(9., -9.);
-    let _ab = x.add_edge(a, b, None, None);
-    let _bc = x.add_edge(b, c, None, None);
-    let _be = x.add_edge(b, e, None, None);
-    let _de = x.add_edge(d, e, None, None);
-    let _ef = x.add_edge(e, f, None, None);
-    /*     let a = x.add_node(1., 0.);
-    let b = x.add_node(1. / 2., (3f64).sqrt() / 2.);
-    let c = x.add_node(-1. / 2., (3f64).sqrt() / 2.);
-    let d = x.add_node(-1., 0.);
-    let e = x.add_node(-1. / 2., -(3f64).sqrt() / 2.);
-    let f = x.add_node(1. / 2., -(3f64).sqrt() / 2.);
-
-    let _ab = x.add_edge(a, b, None, None);
-    let _bc = x.add_edge(b, c, None, None);
-    let _cd = x.add_edge(c, d, None, None);
-    let _de = x.add_edge(d, e, None, None);
-    let _ef = x.add_edge(e, f, None, None);
-    let _fa = x.add_edge(f, a, None, None); */
-
-    x.build();
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Err(message) = run(&args) {
+        eprintln!("error: {}", message);
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    }
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let (group, command) = match args {
+        [group, command, ..] => (group.as_str(), command.as_str()),
+        _ => return Err("missing command".to_string()),
+    };
+    let options = Options::parse(&args[2..])?;
+    match (group, command) {
+        ("section", "props") => {
+            let path = options.target()?;
+            let model = read_model(path)?;
+            let props = model
+                .section
+                .ok_or_else(|| format!("{} has no section", path))?
+                .properties();
+            options.emit(match options.format {
+                Format::Json => format!("{}\n", to_json(&props, &model.units.unwrap_or_default())),
+                _ => table(&properties_rows(&props), options.format),
+            })
+        }
+        ("section", "warping") => {
+            let path = options.target()?;
+            let model = read_model(path)?;
+            let section = model
+                .section
+                .ok_or_else(|| format!("{} has no section", path))?;
+            // by default the thinnest plate is spanned by two elements
+            let size = match options.size {
+                Some(size) => size,
+                None => section
+                    .graph
+                    .edge_indices()
+                    .map(|edge| section.graph[edge].thickness / 2.)
+                    .fold(f64::INFINITY, f64::min),
+            };
+            if !(size > 0. && size.is_finite()) {
+                return Err(format!("{} needs a mesh size, given by --size", path));
+            }
+            let warping = section.warping(size);
+            options.emit(match options.format {
+                Format::Json => {
+                    format!("{}\n", to_json(&warping, &model.units.unwrap_or_default()))
+                }
+                _ => table(&warping_rows(&warping), options.format),
+            })
+        }
+        ("section", "mesh") => {
+            let section = read_section(options.target()?)?;
+            let quality = Quality {
+                max_area: options.size.map(|size| size * size * 3f64.sqrt() / 4.),
+                min_angle: options.angle.unwrap_or(Quality::default().min_angle),
+            };
+            let mesh = section.triangulate(&quality);
+            let path = Path::new(options.output.as_deref().unwrap_or("mesh.geojson"));
+            match path.extension().and_then(|ext| ext.to_str()) {
+                Some("vtk") => mesh::write_vtk(&mesh, path),
+                _ => mesh::write_geojson(&mesh, path),
+            }
+            .map_err(|why| format!("couldn't write {}: {}", path.display(), why))
+        }
+        ("section", "draw") => {
+            let section = read_section(options.target()?)?;
+            let path = options.output.as_deref().unwrap_or("section.svg");
+            section
+                .draw(options.width)
+                .write(Path::new(path))
+                .map_err(|why| format!("couldn't write {}: {}", path, why))
+        }
+        ("section", "outline") => {
+            let section = read_section(options.target()?)?;
+            let path = options.output.as_deref().unwrap_or("section.geojson");
+            write_geojson(&section.build(), Path::new(path))
+                .map_err(|why| format!("couldn't write {}: {}", path, why))
+        }
+        ("section", "dxf") => {
+            let section = read_section(options.target()?)?;
+            let path = options.output.as_deref().unwrap_or("section.dxf");
+            write_dxf(&section, Path::new(path))
+                .map_err(|why| format!("couldn't write {}: {}", path, why))
+        }
+        ("section", "check") => {
+            let diagnostics = read_section(options.target()?)?.validate();
+            options.emit(match options.format {
+                Format::Json => format!("{}\n", json!(diagnostics)),
+                _ => diagnostics.iter().map(|d| format!("{}\n", d)).collect(),
+            })
+        }
+        ("beam", "analyze") => {
+            let path = options.target()?;
+            let model = read_model(path)?;
+            let beam = model
+                .resolved_beam()
+                .ok_or_else(|| format!("{} has no beam", path))?;
+            if beam.stiffness == 0. {
+                return Err(format!("{} gives neither a stiffness nor a section", path));
+            }
+            let analysis = beam
+                .analyze(options.divisions)
+                .ok_or("the supports do not prevent rigid body motion")?;
+            if let Some(path) = &options.draw {
+                beam.draw(&analysis, options.width)
+                    .write(Path::new(path))
+                    .map_err(|why| format!("couldn't write {}: {}", path, why))?;
+            }
+            let units = model.units.unwrap_or_default();
+            options.emit(beam_results(&analysis, options.format, &units))
+        }
+        ("model", "convert") => {
+            let model = read_model(options.target()?)?;
+            let path = options.output.as_deref().ok_or("missing --output")?;
+            model
+                .write(Path::new(path))
+                .map_err(|why| format!("couldn't write {}: {}", path, why))
+        }
+        ("model", "import") => {
+            let path = options.target()?;
+            let drawing = Drawing::read(Path::new(path))
+                .map_err(|why| format!("couldn't read {}: {}", path, why))?;
+            let import = Import {
+                layer: options.layer.clone(),
+                thickness: options.thickness,
+                ..Import::default()
+            };
+            let model = Model {
+                section: Some(drawing.section(&import)),
+                ..Model::default()
+            };
+            let output = options.output.as_deref().unwrap_or("section.toml");
+            if let Err(why) = ModelFormat::from_path(Path::new(output)) {
+                return Err(format!("couldn't write {}: {}", output, why));
+            }
+            model
+                .write(Path::new(output))
+                .map_err(|why| format!("couldn't write {}: {}", output, why))
+        }
+        ("catalog", "list") => {
+            let names: Vec<&str> = catalog::SHAPES.iter().map(|shape| shape.name).collect();
+            options.emit(match options.format {
+                Format::Json => format!("{}\n", json!(names)),
+                Format::Text | Format::Csv => names.iter().map(|n| format!("{}\n", n)).collect(),
+            })
+        }
+        ("catalog", "lookup") => {
+            let name = options.target()?;
+            let shape = catalog::lookup(name).ok_or_else(|| format!("unknown shape {}", name))?;
+            let mut rows = vec![
+                ("depth", shape.depth),
+                ("width", shape.width),
+                ("flange", shape.flange),
+                ("web", shape.web),
+            ];
+            rows.extend(properties_rows(&shape.section(0.3).properties()));
+            options.emit(table(&rows, options.format))
+        }
+        _ => Err(format!("unknown command {} {}", group, command)),
+    }
+}
+
+/// This function reads a model file and takes its section
+fn read_section(path: &str) -> Result<Section, String> {
+    read_model(path)?
+        .section
+        .ok_or_else(|| format!("{} has no section", path))
+}
+
+fn read_model(path: &str) -> Result<Model, String> {
+    Model::read(Path::new(path)).map_err(|why| format!("couldn't read {}: {}", path, why))
+}
+
+fn properties_rows(props: &Properties) -> Vec<(&'static str, f64)> {
+    vec![
+        ("area", props.area),
+        ("centroid_x", props.centroid.0),
+        ("centroid_y", props.centroid.1),
+        ("ixx", props.ixx),
+        ("iyy", props.iyy),
+        ("ixy", props.ixy),
+        ("i11", props.i11),
+        ("i22", props.i22),
+        ("phi", props.phi),
+        ("r11", props.r11()),
+        ("r22", props.r22()),
+        ("sxx", props.sxx),
+        ("syy", props.syy),
+        ("zxx", props.zxx),
+        ("zyy", props.zyy),
+        ("torsion", props.torsion),
+        ("shear_centre_x", props.shear_centre.0),
+        ("shear_centre_y", props.shear_centre.1),
+        ("warping", props.warping),
+        ("elastic", props.elastic),
+    ]
+}
+
+fn warping_rows(warping: &Warping) -> Vec<(&'static str, f64)> {
+    vec![
+        ("area", warping.area),
+        ("centroid_x", warping.centroid.0),
+        ("centroid_y", warping.centroid.1),
+        ("ixx", warping.ixx),
+        ("iyy", warping.iyy),
+        ("ixy", warping.ixy),
+        ("torsion", warping.torsion),
+        ("warping", warping.warping),
+        ("shear_centre_x", warping.shear_centre.0),
+        ("shear_centre_y", warping.shear_centre.1),
+        ("shear_area_x", warping.shear_area.0),
+        ("shear_area_y", warping.shear_area.1),
+        ("nodes", warping.nodes as f64),
+        ("elements", warping.elements as f64),
+    ]
+}
+
+/// Formats a value in fixed notation, or in scientific notation when very large or small
+fn fixed(value: f64) -> String {
+    if value == 0. || (1e-3..1e7).contains(&value.abs()) {
+        format!("{:.4}", value)
+    } else {
+        format!("{:.4e}", value)
+    }
+}
+
+fn table(rows: &[(&str, f64)], format: Format) -> String {
+    match format {
+        Format::Text => rows
+            .iter()
+            .map(|(name, value)| format!("{:<16}{:>16}\n", name, fixed(*value)))
+            .collect(),
+        Format::Json => {
+            let map: Map<String, Value> = rows
+                .iter()
+                .map(|(name, value)| (name.to_string(), json!(value)))
+                .collect();
+            format!("{}\n", Value::Object(map))
+        }
+        Format::Csv => {
+            let mut csv = "quantity,value\n".to_string();
+            for (name, value) in rows {
+                csv.push_str(&format!("{},{}\n", name, value));
+            }
+            csv
+        }
+    }
+}
+
+fn beam_results(analysis: &Analysis, format: Format, units: &Units) -> String {
+    let maxima = [
+        ("moment", analysis.max_moment()),
+        ("shear", analysis.max_shear()),
+        ("deflection", analysis.max_deflection()),
+    ];
+    match format {
+        Format::Text => {
+            let mut text = format!("{:>12}{:>16}{:>16}\n", "reaction at", "force", "moment");
+            for r in &analysis.reactions {
+                text.push_str(&format!(
+                    "{:>12}{:>16}{:>16}\n",
+                    fixed(r.position),
+                    fixed(r.force),
+                    fixed(r.moment)
+                ));
+            }
+            text.push_str(&format!("\n{:>12}{:>16}{:>16}\n", "maximum", "value", "at"));
+            for (name, station) in &maxima {
+                let value = match *name {
+                    "moment" => station.moment,
+                    "shear" => station.shear,
+                    _ => station.deflection,
+                };
+                text.push_str(&format!(
+                    "{:>12}{:>16}{:>16}\n",
+                    name,
+                    fixed(value),
+                    fixed(station.x)
+                ));
+            }
+            text
+        }
+        Format::Json => format!("{}\n", to_json(analysis, units)),
+        Format::Csv => {
+            let mut csv = "x,shear,moment,slope,deflection\n".to_string();
+            for s in &analysis.stations {
+                csv.push_str(&format!(
+                    "{},{},{},{},{}\n",
+                    s.x, s.shear, s.moment, s.slope, s.deflection
+                ));
+            }
+            csv
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    /// A file in the temporary directory, named for this process and the test
+    fn scratch(name: &str) -> String {
+        env::temp_dir()
+            .join(format!("bender_rust_cli_{}_{}", process::id(), name))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn options() {
+        let options = Options::parse(&strings(&[
+            "model.toml",
+            "--format",
+            "csv",
+            "--width",
+            "800",
+            "--divisions",
+            "20",
+            "--thickness",
+            "0.25",
+            "--output",
+            "out.csv",
+        ]))
+        .unwrap();
+        assert_eq!(options.target(), Ok("model.toml"));
+        assert_eq!(options.format, Format::Csv);
+        assert_eq!(options.width, 800.);
+        assert_eq!(options.divisions, 20);
+        assert_eq!(options.thickness, Thickness::Constant(0.25));
+        assert_eq!(options.output.as_deref(), Some("out.csv"));
+
+        let options = Options::parse(&[]).unwrap();
+        assert_eq!(options.format, Format::Text);
+        assert_eq!(options.thickness, Thickness::Extrusion);
+        assert_eq!(options.target(), Err("missing argument".to_string()));
+        let options = Options::parse(&strings(&["a", "b", "c"])).unwrap();
+        assert_eq!(
+            options.target(),
+            Err("unexpected arguments [\"b\", \"c\"]".to_string())
+        );
+    }
+
+    #[test]
+    fn option_errors() {
+        let error = |args: &[&str]| Options::parse(&strings(args)).err().unwrap();
+        assert_eq!(error(&["--output"]), "missing value for --output");
+        assert_eq!(error(&["--format", "xml"]), "unknown format xml");
+        assert_eq!(error(&["--width", "wide"]), "invalid width wide");
+        assert_eq!(
+            error(&["--divisions", "-1"]),
+            "invalid number of divisions -1"
+        );
+        assert_eq!(error(&["--thickness", "thin"]), "invalid thickness thin");
+        assert_eq!(error(&["--colour", "red"]), "unknown option --colour");
+    }
+
+    #[test]
+    fn commands() {
+        assert_eq!(
+            run(&strings(&["section"])),
+            Err("missing command".to_string())
+        );
+        assert_eq!(
+            run(&strings(&["section", "paint"])),
+            Err("unknown command section paint".to_string())
+        );
+        assert_eq!(
+            run(&strings(&["catalog", "lookup", "W99X1"])),
+            Err("unknown shape W99X1".to_string())
+        );
+        let missing = scratch("missing.toml");
+        assert!(run(&strings(&["section", "props", &missing]))
+            .unwrap_err()
+            .starts_with(&format!("couldn't read {}: ", missing)));
+        assert_eq!(
+            run(&strings(&["model", "convert", "model.txt"])),
+            Err("couldn't read model.txt: unknown model format \"txt\"".to_string())
+        );
+    }
+
+    #[test]
+    fn section_properties() {
+        let model = scratch("angle.toml");
+        fs::write(
+            &model,
+            "[section]\nnodes = [[0.0, 4.0], [0.0, 0.0], [3.0, 0.0]]\n\
+             plates = [{ start = 0, end = 1, thickness = 0.5 }, { start = 1, end = 2, thickness = 0.5 }]\n\
+             [beam]\nlength = 10.0\n",
+        )
+        .unwrap();
+        let output = scratch("angle.csv");
+        run(&strings(&[
+            "section", "props", &model, "--format", "csv", "--output", &output,
+        ]))
+        .unwrap();
+        let csv = fs::read_to_string(&output).unwrap();
+        assert!(csv.starts_with("quantity,value\narea,3.5\n"));
+        assert_eq!(csv.lines().count(), 21);
+
+        // a beam without supports cannot be analysed
+        assert_eq!(
+            run(&strings(&["beam", "analyze", &model])),
+            Err("the supports do not prevent rigid body motion".to_string())
+        );
+        fs::remove_file(&model).unwrap();
+        fs::remove_file(&output).unwrap();
+    }
+
+    #[test]
+    fn tables() {
+        let rows = [("area", 3.5), ("warping", 1.25e-5)];
+        assert_eq!(
+            table(&rows, Format::Text),
+            format!(
+                "{:<16}{:>16}\n{:<16}{:>16}\n",
+                "area", "3.5000", "warping", "1.2500e-5"
+            )
+        );
+        assert_eq!(
+            table(&rows, Format::Json),
+            "{\"area\":3.5,\"warping\":0.0000125}\n"
+        );
+        assert_eq!(
+            table(&rows, Format::Csv),
+            "quantity,value\narea,3.5\nwarping,0.0000125\n"
+        );
+        assert_eq!(fixed(0.), "0.0000");
+        assert_eq!(fixed(-2.5e7), "-2.5000e7");
+    }
 }
@@ -0,0 +1,65 @@
+/// A structural material grade
+#[derive(Clone, Debug)]
+pub struct Material {
+    pub name: String,
+    /// Yield strength, Fy
+    pub yield_strength: f64,
+    /// Tensile strength, Fu
+    pub ultimate: f64,
+    /// Modulus of elasticity, E
+    pub elastic: f64,
+}
+
+impl Material {
+    pub fn new(name: &str, yield_strength: f64, ultimate: f64, elastic: f64) -> Material {
+        Material {
+            name: name.to_string(),
+            yield_strength,
+            ultimate,
+            elastic,
+        }
+    }
+
+    /// ASTM A992 structural steel, in ksi
+    pub fn a992() -> Material {
+        Material::new("A992", 50., 65., 29000.)
+    }
+
+    /// ASTM A36 structural steel, in ksi
+    pub fn a36() -> Material {
+        Material::new("A36", 36., 58., 29000.)
+    }
+
+    /// ASTM A572 Grade 50 structural steel, in ksi
+    pub fn a572_50() -> Material {
+        Material::new("A572-50", 50., 65., 29000.)
+    }
+
+    /// EN 10025-2 S235 structural steel, in N/mm²
+    pub fn s235() -> Material {
+        Material::new("S235", 235., 360., 210000.)
+    }
+
+    /// EN 10025-2 S275 structural steel, in N/mm²
+    pub fn s275() -> Material {
+        Material::new("S275", 275., 430., 210000.)
+    }
+
+    /// EN 10025-2 S355 structural steel, in N/mm²
+    pub fn s355() -> Material {
+        Material::new("S355", 355., 490., 210000.)
+    }
+
+    /// This function looks up a grade by name
+    pub fn grade(name: &str) -> Option<Material> {
+        match name.to_uppercase().as_str() {
+            "A992" => Some(Material::a992()),
+            "A36" => Some(Material::a36()),
+            "A572-50" | "A572" => Some(Material::a572_50()),
+            "S235" => Some(Material::s235()),
+            "S275" => Some(Material::s275()),
+            "S355" => Some(Material::s355()),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,761 @@
+// Triangular meshes of solid outlines, for finite element analysis
+use crate::build::Section;
+use geo::{LineString, MultiPolygon, Polygon};
+use geojson::{Feature, FeatureCollection, GeoJson, Geometry, Value};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::File;
+use std::io::{self, prelude::*};
+use std::path::Path;
+
+/// The most points a mesh is refined to, guarding against quality requirements that cannot
+/// be met
+const LIMIT: usize = 200_000;
+
+/// A mesh of straight-sided triangles, with their corners listed anticlockwise
+#[derive(Clone, Debug, Serialize)]
+pub struct Mesh {
+    pub points: Vec<(f64, f64)>,
+    pub triangles: Vec<[usize; 3]>,
+}
+
+/// Requirements on the size and shape of the triangles of a mesh
+#[derive(Clone, Copy, Debug)]
+pub struct Quality {
+    /// The largest area of a triangle, if limited
+    pub max_area: Option<f64>,
+    /// The smallest angle of a triangle in degrees. Angles up to about 30° can be met, except
+    /// in the corners of the outline sharper than that.
+    pub min_angle: f64,
+}
+
+impl Default for Quality {
+    fn default() -> Quality {
+        Quality {
+            max_area: None,
+            min_angle: 20.,
+        }
+    }
+}
+
+impl Mesh {
+    /// This function meshes an outline, holes included, by a constrained Delaunay
+    /// triangulation of its corners. The triangulation is then refined by Ruppert's
+    /// algorithm, splitting the boundary and adding points at the circumcentres of triangles
+    /// until every triangle meets the quality requirements.
+    pub fn triangulate(outline: &MultiPolygon<f64>, quality: &Quality) -> Mesh {
+        let rings = rings(outline);
+        let points: Vec<(f64, f64)> = rings.iter().flatten().cloned().collect();
+        if points.is_empty() {
+            return Mesh {
+                points,
+                triangles: vec![],
+            };
+        }
+        let mut triangulation = Triangulation::new(&points);
+        let mut segments = vec![];
+        for ring in &rings {
+            let corners: Vec<usize> = ring.iter().map(|&p| triangulation.insert(p)).collect();
+            for i in 0..corners.len() {
+                segments.push((corners[i], corners[(i + 1) % corners.len()]));
+            }
+        }
+        for (a, b) in segments {
+            triangulation.enforce(a, b);
+        }
+        triangulation.classify();
+        triangulation.refine(quality);
+        triangulation.mesh()
+    }
+
+    /// This function meshes an outline with triangles of about the given size, meaning
+    /// triangles no larger than an equilateral triangle of that side
+    pub fn generate(outline: &MultiPolygon<f64>, size: f64) -> Mesh {
+        let quality = Quality {
+            max_area: Some(size * size * 3f64.sqrt() / 4.),
+            ..Quality::default()
+        };
+        Mesh::triangulate(outline, &quality)
+    }
+
+    /// The corners of a triangle
+    pub fn corners(&self, triangle: usize) -> [(f64, f64); 3] {
+        let [a, b, c] = self.triangles[triangle];
+        [self.points[a], self.points[b], self.points[c]]
+    }
+
+    pub fn area(&self) -> f64 {
+        (0..self.triangles.len())
+            .map(|t| {
+                let [a, b, c] = self.corners(t);
+                signed_area(a, b, c)
+            })
+            .sum()
+    }
+
+    /// The smallest angle of any triangle, in degrees
+    pub fn min_angle(&self) -> f64 {
+        (0..self.triangles.len())
+            .map(|t| {
+                let [a, b, c] = self.corners(t);
+                smallest_angle(a, b, c).to_degrees()
+            })
+            .fold(180., f64::min)
+    }
+
+    /// This function writes the triangles as a GeoJSON collection of polygons
+    pub fn to_geojson(&self) -> String {
+        let features = (0..self.triangles.len())
+            .map(|t| {
+                let [a, b, c] = self.corners(t);
+                let polygon = Polygon::new(LineString::from(vec![a, b, c, a]), vec![]);
+                Feature {
+                    bbox: None,
+                    geometry: Some(Geometry::new(Value::from(&polygon))),
+                    id: None,
+                    properties: None,
+                    foreign_members: None,
+                }
+            })
+            .collect();
+        GeoJson::FeatureCollection(FeatureCollection {
+            bbox: None,
+            features,
+            foreign_members: None,
+        })
+        .to_string()
+    }
+
+    /// This function writes the mesh as a legacy VTK unstructured grid, in the plane z = 0
+    pub fn to_vtk(&self) -> String {
+        let mut vtk = "# vtk DataFile Version 3.0\nbender_rust mesh\nASCII\n".to_string();
+        vtk.push_str("DATASET UNSTRUCTURED_GRID\n");
+        vtk.push_str(&format!("POINTS {} double\n", self.points.len()));
+        for p in &self.points {
+            vtk.push_str(&format!("{} {} 0\n", p.0, p.1));
+        }
+        let count = self.triangles.len();
+        vtk.push_str(&format!("CELLS {} {}\n", count, 4 * count));
+        for [a, b, c] in &self.triangles {
+            vtk.push_str(&format!("3 {} {} {}\n", a, b, c));
+        }
+        vtk.push_str(&format!("CELL_TYPES {}\n", count));
+        // 5 is the VTK cell type of a linear triangle
+        vtk.push_str(&"5\n".repeat(count));
+        vtk
+    }
+}
+
+impl Section {
+    /// This function meshes the built outline of the section
+    pub fn triangulate(&self, quality: &Quality) -> Mesh {
+        Mesh::triangulate(&self.build(), quality)
+    }
+}
+
+/// This function writes a mesh to a GeoJSON file
+pub fn write_geojson(mesh: &Mesh, path: &Path) -> io::Result<()> {
+    write(mesh.to_geojson(), path)
+}
+
+/// This function writes a mesh to a VTK file
+pub fn write_vtk(mesh: &Mesh, path: &Path) -> io::Result<()> {
+    write(mesh.to_vtk(), path)
+}
+
+fn write(text: String, path: &Path) -> io::Result<()> {
+    File::create(path)?.write_all(text.as_bytes())?;
+    println!("successfully wrote to {}", path.display());
+    Ok(())
+}
+
+/// A triangulation under construction, covering a triangle enclosing the outline. The first
+/// three points are the corners of the enclosing triangle.
+struct Triangulation {
+    points: Vec<(f64, f64)>,
+    triangles: Vec<[usize; 3]>,
+    alive: Vec<bool>,
+    /// Whether each triangle lies within the outline
+    inside: Vec<bool>,
+    /// The triangle on the left of each directed edge
+    edges: HashMap<(usize, usize), usize>,
+    /// The boundary segments, which triangles may not cross, keyed by their lower point first
+    fixed: HashSet<(usize, usize)>,
+    /// The triangle last inserted, from which the search for the next point starts
+    last: usize,
+    /// Points closer than this are taken as one
+    tolerance: f64,
+}
+
+fn key(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+impl Triangulation {
+    fn new(points: &[(f64, f64)]) -> Triangulation {
+        let (min, max) = bounds(points);
+        let span = (max.0 - min.0).max(max.1 - min.1).max(f64::EPSILON);
+        let centre = ((min.0 + max.0) / 2., (min.1 + max.1) / 2.);
+        let mut triangulation = Triangulation {
+            points: vec![
+                (centre.0 - 20. * span, centre.1 - span),
+                (centre.0 + 20. * span, centre.1 - span),
+                (centre.0, centre.1 + 20. * span),
+            ],
+            triangles: vec![],
+            alive: vec![],
+            inside: vec![],
+            edges: HashMap::new(),
+            fixed: HashSet::new(),
+            last: 0,
+            tolerance: 1e-9 * span,
+        };
+        triangulation.add([0, 1, 2], false);
+        triangulation
+    }
+
+    fn corners(&self, t: usize) -> [(f64, f64); 3] {
+        let [a, b, c] = self.triangles[t];
+        [self.points[a], self.points[b], self.points[c]]
+    }
+
+    fn add(&mut self, t: [usize; 3], inside: bool) -> usize {
+        let index = self.triangles.len();
+        self.triangles.push(t);
+        self.alive.push(true);
+        self.inside.push(inside);
+        for i in 0..3 {
+            self.edges.insert((t[i], t[(i + 1) % 3]), index);
+        }
+        self.last = index;
+        index
+    }
+
+    fn remove(&mut self, index: usize) {
+        self.alive[index] = false;
+        let t = self.triangles[index];
+        for i in 0..3 {
+            self.edges.remove(&(t[i], t[(i + 1) % 3]));
+        }
+    }
+
+    /// The corner of a triangle opposite one of its edges
+    fn opposite(&self, t: usize, a: usize, b: usize) -> usize {
+        self.triangles[t]
+            .iter()
+            .cloned()
+            .find(|&v| v != a && v != b)
+            .unwrap()
+    }
+
+    /// Whether a point lies on the segment between two points, short of its ends
+    fn on_segment(&self, a: usize, b: usize, p: (f64, f64)) -> bool {
+        let (pa, pb) = (self.points[a], self.points[b]);
+        let length = distance(pa, pb);
+        let along = (p.0 - pa.0) * (pb.0 - pa.0) + (p.1 - pa.1) * (pb.1 - pa.1);
+        (2. * signed_area(pa, pb, p)).abs() <= self.tolerance * length
+            && along > self.tolerance * length
+            && along < length * (length - self.tolerance)
+    }
+
+    /// This function finds the triangle holding a point, walking towards it from the last
+    /// triangle inserted
+    fn locate(&self, p: (f64, f64)) -> usize {
+        let mut t = self.last;
+        'walk: for step in 0..self.triangles.len() {
+            let tri = self.triangles[t];
+            for i in 0..3 {
+                // turning the starting edge keeps the walk from circling
+                let (a, b) = (tri[(i + step) % 3], tri[(i + step + 1) % 3]);
+                if signed_area(self.points[a], self.points[b], p) < 0. {
+                    if let Some(&next) = self.edges.get(&(b, a)) {
+                        t = next;
+                        continue 'walk;
+                    }
+                }
+            }
+            return t;
+        }
+        // otherwise the triangle nearest to holding the point
+        (0..self.triangles.len())
+            .filter(|&t| self.alive[t])
+            .map(|t| {
+                let [a, b, c] = self.corners(t);
+                let least = signed_area(a, b, p)
+                    .min(signed_area(b, c, p))
+                    .min(signed_area(c, a, p));
+                (t, least)
+            })
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .unwrap()
+            .0
+    }
+
+    /// This function inserts a point by the Bowyer-Watson algorithm, returning its index.
+    /// The cavity left by the point is grown from the triangle holding it to the triangles
+    /// whose circumcircles hold it, without crossing a boundary segment unless the point lies
+    /// on the segment, which is then split in two.
+    fn insert(&mut self, p: (f64, f64)) -> usize {
+        let first = self.locate(p);
+        if let Some(&v) = self.triangles[first]
+            .iter()
+            .find(|&&v| distance(self.points[v], p) <= self.tolerance)
+        {
+            return v;
+        }
+        let index = self.points.len();
+        self.points.push(p);
+
+        let mut cavity = vec![first];
+        let mut split = vec![];
+        let mut k = 0;
+        while k < cavity.len() {
+            let t = self.triangles[cavity[k]];
+            for i in 0..3 {
+                let (a, b) = (t[i], t[(i + 1) % 3]);
+                let next = match self.edges.get(&(b, a)) {
+                    Some(&next) if !cavity.contains(&next) => next,
+                    _ => continue,
+                };
+                let grow = if self.fixed.contains(&key(a, b)) {
+                    let on = self.on_segment(a, b, p);
+                    if on {
+                        split.push(key(a, b));
+                    }
+                    on
+                } else {
+                    let [ca, cb, cc] = self.corners(next);
+                    in_circle(ca, cb, cc, p) > 0.
+                };
+                if grow {
+                    cavity.push(next);
+                }
+            }
+            k += 1;
+        }
+
+        // the edges of the cavity are those not shared by two of its triangles, and each new
+        // triangle lies on the same side of the boundary as the triangle it replaces
+        let mut boundary = vec![];
+        for &t in &cavity {
+            let tri = self.triangles[t];
+            for i in 0..3 {
+                let (a, b) = (tri[i], tri[(i + 1) % 3]);
+                match self.edges.get(&(b, a)) {
+                    Some(next) if cavity.contains(next) => {}
+                    _ => boundary.push((a, b, self.inside[t])),
+                }
+            }
+        }
+        for &t in &cavity {
+            self.remove(t);
+        }
+        for (a, b, inside) in boundary {
+            self.add([a, b, index], inside);
+        }
+        for (a, b) in split {
+            self.fixed.remove(&(a, b));
+            self.fixed.insert(key(a, index));
+            self.fixed.insert(key(index, b));
+        }
+        index
+    }
+
+    /// This function replaces the edge between two triangles with the other diagonal of the
+    /// quadrilateral they form, returning the new edge
+    fn flip(&mut self, a: usize, b: usize) -> (usize, usize) {
+        let (left, right) = (self.edges[&(a, b)], self.edges[&(b, a)]);
+        let (c, d) = (self.opposite(left, a, b), self.opposite(right, a, b));
+        let inside = self.inside[left];
+        self.remove(left);
+        self.remove(right);
+        self.add([a, d, c], inside);
+        self.add([d, b, c], inside);
+        (c, d)
+    }
+
+    /// This function makes a boundary segment an edge of the triangulation, flipping the edges
+    /// that cross it after Sloan, then restores the Delaunay property about the new edges
+    fn enforce(&mut self, a: usize, b: usize) {
+        if a == b {
+            return;
+        }
+        let (pa, pb) = (self.points[a], self.points[b]);
+        // a corner lying on the segment splits it
+        let between = (3..self.points.len())
+            .filter(|&v| v != a && v != b && self.on_segment(a, b, self.points[v]))
+            .min_by(|&u, &v| {
+                let (du, dv) = (distance(pa, self.points[u]), distance(pa, self.points[v]));
+                du.partial_cmp(&dv).unwrap()
+            });
+        if let Some(v) = between {
+            self.enforce(a, v);
+            self.enforce(v, b);
+            return;
+        }
+        if !self.edges.contains_key(&(a, b)) && !self.edges.contains_key(&(b, a)) {
+            let mut crossing: VecDeque<(usize, usize)> = self
+                .edges
+                .keys()
+                .filter(|&&(u, v)| u < v && crosses(pa, pb, self.points[u], self.points[v]))
+                .cloned()
+                .collect();
+            let mut created = vec![];
+            let mut guard = 0;
+            while let Some((u, v)) = crossing.pop_front() {
+                guard += 1;
+                if guard > 100 * LIMIT {
+                    break;
+                }
+                let (left, right) = (self.edges[&(u, v)], self.edges[&(v, u)]);
+                let (c, d) = (self.opposite(left, u, v), self.opposite(right, u, v));
+                // only the diagonal of a convex quadrilateral can be flipped
+                if crosses(
+                    self.points[c],
+                    self.points[d],
+                    self.points[u],
+                    self.points[v],
+                ) {
+                    let (c, d) = self.flip(u, v);
+                    if crosses(pa, pb, self.points[c], self.points[d]) {
+                        crossing.push_back((c, d));
+                    } else {
+                        created.push((c, d));
+                    }
+                } else {
+                    crossing.push_back((u, v));
+                }
+            }
+            self.legalize(created);
+        }
+        self.fixed.insert(key(a, b));
+    }
+
+    /// This function flips edges that fail the Delaunay test, starting from the given edges
+    /// and spreading to the edges around each flip, leaving the boundary segments
+    fn legalize(&mut self, mut stack: Vec<(usize, usize)>) {
+        let mut guard = 0;
+        while let Some((a, b)) = stack.pop() {
+            guard += 1;
+            if guard > 100 * LIMIT || self.fixed.contains(&key(a, b)) {
+                continue;
+            }
+            let (left, right) = match (self.edges.get(&(a, b)), self.edges.get(&(b, a))) {
+                (Some(&left), Some(&right)) => (left, right),
+                _ => continue,
+            };
+            let (c, d) = (self.opposite(left, a, b), self.opposite(right, a, b));
+            let [ca, cb, cc] = self.corners(left);
+            let scale = distance(ca, cb).powi(2) * distance(cc, self.points[d]).powi(2);
+            if in_circle(ca, cb, cc, self.points[d]) > 1e-12 * scale {
+                self.flip(a, b);
+                stack.extend_from_slice(&[(a, d), (d, b), (b, c), (c, a)]);
+            }
+        }
+    }
+
+    /// This function marks the triangles within the outline, counting the boundary segments
+    /// crossed on the way to each from the enclosing triangle
+    fn classify(&mut self) {
+        let mut depth = vec![usize::MAX; self.triangles.len()];
+        let mut queue = VecDeque::new();
+        for (t, depth) in depth.iter_mut().enumerate() {
+            if self.alive[t] && self.triangles[t].iter().any(|&v| v < 3) {
+                *depth = 0;
+                queue.push_back(t);
+            }
+        }
+        while let Some(t) = queue.pop_front() {
+            let tri = self.triangles[t];
+            for i in 0..3 {
+                let (a, b) = (tri[i], tri[(i + 1) % 3]);
+                if let Some(&next) = self.edges.get(&(b, a)) {
+                    let crossed = self.fixed.contains(&key(a, b));
+                    let d = depth[t] + crossed as usize;
+                    if d < depth[next] {
+                        depth[next] = d;
+                        // crossing nothing keeps the depth, so is searched first
+                        if crossed {
+                            queue.push_back(next);
+                        } else {
+                            queue.push_front(next);
+                        }
+                    }
+                }
+            }
+        }
+        self.inside = depth
+            .iter()
+            .map(|&d| d != usize::MAX && d % 2 == 1)
+            .collect();
+    }
+
+    /// Whether a triangle within the outline falls short of the quality requirements, leaving
+    /// those with their smallest angle in a corner of the outline
+    fn is_bad(&self, t: usize, quality: &Quality) -> bool {
+        if !self.alive[t] || !self.inside[t] {
+            return false;
+        }
+        let [a, b, c] = self.corners(t);
+        let large = quality
+            .max_area
+            .is_some_and(|max| signed_area(a, b, c) > max);
+        let angles = angles(a, b, c);
+        let (corner, &smallest) = angles
+            .iter()
+            .enumerate()
+            .min_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+            .unwrap();
+        if large {
+            return true;
+        }
+        let tri = self.triangles[t];
+        let v = tri[corner];
+        let sides = [tri[(corner + 1) % 3], tri[(corner + 2) % 3]];
+        smallest < quality.min_angle.to_radians()
+            && !sides.iter().all(|&u| self.fixed.contains(&key(u, v)))
+    }
+
+    /// The boundary segments whose diametral circles hold a point
+    fn encroached(&self, p: (f64, f64)) -> Vec<(usize, usize)> {
+        self.fixed
+            .iter()
+            .filter(|&&(a, b)| {
+                let (pa, pb) = (self.points[a], self.points[b]);
+                (pa.0 - p.0) * (pb.0 - p.0) + (pa.1 - p.1) * (pb.1 - p.1)
+                    < -self.tolerance * self.tolerance
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// This function splits boundary segments at their middles until none is encroached by
+    /// a corner of a triangle beside it, checking the triangles from the given index on
+    fn split_encroached(&mut self, from: usize) {
+        let mut from = from;
+        while from < self.triangles.len() && self.points.len() < LIMIT {
+            let end = self.triangles.len();
+            let mut segments = vec![];
+            for t in from..end {
+                if !self.alive[t] {
+                    continue;
+                }
+                let tri = self.triangles[t];
+                for i in 0..3 {
+                    let (a, b, c) = (tri[i], tri[(i + 1) % 3], tri[(i + 2) % 3]);
+                    let (pa, pb, pc) = (self.points[a], self.points[b], self.points[c]);
+                    if self.fixed.contains(&key(a, b))
+                        && (pa.0 - pc.0) * (pb.0 - pc.0) + (pa.1 - pc.1) * (pb.1 - pc.1) < 0.
+                    {
+                        segments.push(key(a, b));
+                    }
+                }
+            }
+            for (a, b) in segments {
+                if self.fixed.contains(&(a, b)) {
+                    self.split(a, b);
+                }
+            }
+            from = end;
+        }
+    }
+
+    fn split(&mut self, a: usize, b: usize) {
+        let (pa, pb) = (self.points[a], self.points[b]);
+        self.insert(((pa.0 + pb.0) / 2., (pa.1 + pb.1) / 2.));
+    }
+
+    /// This function refines the triangulation by Ruppert's algorithm
+    fn refine(&mut self, quality: &Quality) {
+        self.split_encroached(0);
+        let mut stack: Vec<usize> = (0..self.triangles.len()).rev().collect();
+        while let Some(t) = stack.pop() {
+            if self.points.len() >= LIMIT {
+                break;
+            }
+            if !self.is_bad(t, quality) {
+                continue;
+            }
+            let from = self.triangles.len();
+            let [a, b, c] = self.corners(t);
+            let centre = circumcentre(a, b, c);
+            let encroached = self.encroached(centre);
+            if encroached.is_empty() {
+                if !self.inside[self.locate(centre)] {
+                    continue;
+                }
+                self.insert(centre);
+            } else {
+                for (a, b) in encroached {
+                    if self.fixed.contains(&(a, b)) {
+                        self.split(a, b);
+                    }
+                }
+                stack.push(t);
+            }
+            self.split_encroached(from);
+            stack.extend(from..self.triangles.len());
+        }
+    }
+
+    /// The triangles within the outline, numbering only the points they use
+    fn mesh(&self) -> Mesh {
+        let mut numbers = HashMap::new();
+        let mut points = vec![];
+        let mut triangles = vec![];
+        for t in 0..self.triangles.len() {
+            if !self.alive[t] || !self.inside[t] {
+                continue;
+            }
+            let mut number = |v: usize| {
+                *numbers.entry(v).or_insert_with(|| {
+                    points.push(self.points[v]);
+                    points.len() - 1
+                })
+            };
+            let [a, b, c] = self.triangles[t];
+            triangles.push([number(a), number(b), number(c)]);
+        }
+        Mesh { points, triangles }
+    }
+}
+
+/// The exterior and interior rings of an outline, without the closing point
+fn rings(outline: &MultiPolygon<f64>) -> Vec<Vec<(f64, f64)>> {
+    let mut rings = vec![];
+    for polygon in &outline.0 {
+        for ring in std::iter::once(polygon.exterior()).chain(polygon.interiors()) {
+            let mut points: Vec<(f64, f64)> = ring.0.iter().map(|c| (c.x, c.y)).collect();
+            if points.len() > 1 && points[0] == points[points.len() - 1] {
+                points.pop();
+            }
+            if points.len() > 2 {
+                rings.push(points);
+            }
+        }
+    }
+    rings
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt()
+}
+
+fn bounds(points: &[(f64, f64)]) -> ((f64, f64), (f64, f64)) {
+    points.iter().fold(
+        (
+            (f64::INFINITY, f64::INFINITY),
+            (f64::NEG_INFINITY, f64::NEG_INFINITY),
+        ),
+        |(min, max), p| {
+            (
+                (min.0.min(p.0), min.1.min(p.1)),
+                (max.0.max(p.0), max.1.max(p.1)),
+            )
+        },
+    )
+}
+
+/// The area of a triangle, positive when the corners run anticlockwise
+fn signed_area(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> f64 {
+    ((b.0 - a.0) * (c.1 - a.1) - (c.0 - a.0) * (b.1 - a.1)) / 2.
+}
+
+/// Whether two segments cross at a point inside both
+fn crosses(a: (f64, f64), b: (f64, f64), c: (f64, f64), d: (f64, f64)) -> bool {
+    signed_area(a, b, c) * signed_area(a, b, d) < 0.
+        && signed_area(c, d, a) * signed_area(c, d, b) < 0.
+}
+
+/// The angles at the corners of a triangle
+fn angles(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> [f64; 3] {
+    let angle = |p: (f64, f64), q: (f64, f64), r: (f64, f64)| {
+        let (u, v) = ((q.0 - p.0, q.1 - p.1), (r.0 - p.0, r.1 - p.1));
+        (u.0 * v.1 - u.1 * v.0).abs().atan2(u.0 * v.0 + u.1 * v.1)
+    };
+    [angle(a, b, c), angle(b, c, a), angle(c, a, b)]
+}
+
+fn smallest_angle(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> f64 {
+    angles(a, b, c)
+        .iter()
+        .cloned()
+        .fold(f64::INFINITY, f64::min)
+}
+
+fn circumcentre(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> (f64, f64) {
+    let (b, c) = ((b.0 - a.0, b.1 - a.1), (c.0 - a.0, c.1 - a.1));
+    let d = 2. * (b.0 * c.1 - b.1 * c.0);
+    let (b2, c2) = (b.0 * b.0 + b.1 * b.1, c.0 * c.0 + c.1 * c.1);
+    (
+        a.0 + (c.1 * b2 - b.1 * c2) / d,
+        a.1 + (b.0 * c2 - c.0 * b2) / d,
+    )
+}
+
+/// Positive when the fourth point lies inside the circle through the corners of an
+/// anticlockwise triangle
+fn in_circle(a: (f64, f64), b: (f64, f64), c: (f64, f64), d: (f64, f64)) -> f64 {
+    let row = |p: (f64, f64)| {
+        let (x, y) = (p.0 - d.0, p.1 - d.1);
+        (x, y, x * x + y * y)
+    };
+    let (a, b, c) = (row(a), row(b), row(c));
+    a.0 * (b.1 * c.2 - b.2 * c.1) - a.1 * (b.0 * c.2 - b.2 * c.0) + a.2 * (b.0 * c.1 - b.1 * c.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo::prelude::Area;
+
+    fn ring(points: &[(f64, f64)]) -> LineString<f64> {
+        LineString::from(points.to_vec())
+    }
+
+    #[test]
+    fn area_of_outline_with_hole() {
+        let outline = MultiPolygon(vec![Polygon::new(
+            ring(&[(0., 0.), (10., 0.), (10., 8.), (0., 8.)]),
+            vec![ring(&[(2., 2.), (2., 6.), (8., 6.), (8., 2.)])],
+        )]);
+        let quality = Quality {
+            max_area: Some(0.5),
+            min_angle: 25.,
+        };
+        let mesh = Mesh::triangulate(&outline, &quality);
+        assert!((mesh.area() - (80. - 24.)).abs() < 1e-9);
+        assert!(mesh.min_angle() >= 25.);
+        for t in 0..mesh.triangles.len() {
+            let [a, b, c] = mesh.corners(t);
+            let area = signed_area(a, b, c);
+            assert!(area > 0. && area <= 0.5);
+            // no triangle lies in the hole
+            let centre = ((a.0 + b.0 + c.0) / 3., (a.1 + b.1 + c.1) / 3.);
+            assert!(!(centre.0 > 2. && centre.0 < 8. && centre.1 > 2. && centre.1 < 6.));
+        }
+    }
+
+    #[test]
+    fn area_of_built_section() {
+        let mut section = Section::new(0.3);
+        let n: Vec<_> = [
+            (-9., 9.),
+            (0., 9.),
+            (9., 9.),
+            (-9., -9.),
+            (0., -9.),
+            (9., -9.),
+        ]
+        .iter()
+        .map(|&(x, y)| section.add_node(x, y))
+        .collect();
+        for &(a, b) in &[(0, 1), (1, 2), (1, 4), (3, 4), (4, 5)] {
+            section.add_edge(n[a], n[b], None, None);
+        }
+        let outline = section.build();
+        let mesh = section.triangulate(&Quality::default());
+        assert!((mesh.area() - outline.area().abs()).abs() < 1e-9 * outline.area().abs());
+        assert!(mesh.min_angle() >= Quality::default().min_angle);
+    }
+}
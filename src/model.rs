@@ -0,0 +1,495 @@
+// A declarative model file format for sections and beams in TOML, JSON or YAML
+use crate::beam::{Beam, Support};
+use crate::build::{Element, Node, Section};
+use crate::catalog;
+use crate::concrete::{Rebar, Solid};
+use crate::results::Units;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::Path;
+
+/// A model file, holding a section, a beam or both. A beam without a stiffness bends about
+/// the major axis of the section. The units are recorded with the results.
+///
+/// ```toml
+/// units = { force = "kip", length = "in" }
+///
+/// [section]
+/// poisson = 0.3
+/// nodes = [[-9.0, 9.0], [0.0, 9.0], [9.0, 9.0], [-9.0, -9.0], [0.0, -9.0], [9.0, -9.0]]
+/// plates = [
+///     { start = 0, end = 1, thickness = 2.0, elastic = 29000.0, label = "top" },
+///     ...
+/// ]
+/// labels = [{ node = 1, label = "web top" }]
+///
+/// [beam]
+/// length = 240.0
+/// supports = [{ position = 0.0, type = "pinned" }, { position = 240.0, type = "pinned" }]
+/// loads = [{ type = "distributed", start = 0.0, end = 240.0, mag = 0.1 }]
+/// ```
+#[derive(Serialize, Deserialize, Default)]
+pub struct Model {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub units: Option<Units>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section: Option<Section>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub beam: Option<Beam>,
+}
+
+/// The file formats of a model, picked by the file extension
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Toml,
+    Json,
+    Yaml,
+}
+
+#[derive(Debug)]
+pub enum ModelError {
+    Io(std::io::Error),
+    /// The file extension is not one of toml, json, yaml or yml
+    Extension(String),
+    Parse(String),
+}
+
+impl Display for ModelError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModelError::Io(why) => write!(f, "{}", why),
+            ModelError::Extension(ext) => write!(f, "unknown model format {:?}", ext),
+            ModelError::Parse(why) => write!(f, "{}", why),
+        }
+    }
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Result<Format, ModelError> {
+        let ext = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        match ext.as_str() {
+            "toml" => Ok(Format::Toml),
+            "json" => Ok(Format::Json),
+            "yaml" | "yml" => Ok(Format::Yaml),
+            _ => Err(ModelError::Extension(ext)),
+        }
+    }
+}
+
+impl Model {
+    /// This function parses a model from text in the given format
+    pub fn parse(text: &str, format: Format) -> Result<Model, ModelError> {
+        match format {
+            Format::Toml => toml::from_str(text).map_err(|why| ModelError::Parse(why.to_string())),
+            Format::Json => {
+                serde_json::from_str(text).map_err(|why| ModelError::Parse(why.to_string()))
+            }
+            Format::Yaml => {
+                serde_yaml::from_str(text).map_err(|why| ModelError::Parse(why.to_string()))
+            }
+        }
+    }
+
+    /// This function reads a model file, in the format given by its extension
+    pub fn read(path: &Path) -> Result<Model, ModelError> {
+        let format = Format::from_path(path)?;
+        let text = fs::read_to_string(path).map_err(ModelError::Io)?;
+        Model::parse(&text, format)
+    }
+
+    /// This function returns the beam, taking its stiffness from the section if it has none
+    pub fn resolved_beam(&self) -> Option<Beam> {
+        let mut beam = self.beam.clone()?;
+        if let (0., Some(section)) = (beam.stiffness, &self.section) {
+            let props = section.properties();
+            beam.stiffness = props.elastic * props.i11;
+        }
+        Some(beam)
+    }
+
+    /// This function serialises the model in the given format
+    pub fn to_string(&self, format: Format) -> String {
+        match format {
+            // going through a value puts the plain values ahead of the tables
+            Format::Toml => toml::to_string(&toml::Value::try_from(self).unwrap()).unwrap(),
+            Format::Json => serde_json::to_string_pretty(self).unwrap(),
+            Format::Yaml => serde_yaml::to_string(self).unwrap(),
+        }
+    }
+
+    /// This function writes the model to a file, in the format given by its extension
+    pub fn write(&self, path: &Path) -> Result<(), ModelError> {
+        let format = Format::from_path(path)?;
+        File::create(path)
+            .and_then(|mut file| file.write_all(self.to_string(format).as_bytes()))
+            .map_err(ModelError::Io)?;
+        println!("successfully wrote to {}", path.display());
+        Ok(())
+    }
+}
+
+/// A plate joining two nodes, by their position in the list of nodes, with the thickness
+/// and modulus defaulting as in `Section::add_edge`
+#[derive(Serialize, Deserialize)]
+struct Plate {
+    start: usize,
+    end: usize,
+    #[serde(default = "default_thickness")]
+    thickness: f64,
+    #[serde(default = "default_elastic")]
+    elastic: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+}
+
+/// A name given to a node, by its position in the list of nodes
+#[derive(Serialize, Deserialize)]
+struct Label {
+    node: usize,
+    label: String,
+}
+
+/// The file representation of a section. A section may instead name a catalogue shape, in
+/// which case the nodes and plates are ignored.
+#[derive(Serialize, Deserialize)]
+struct SectionModel {
+    #[serde(default = "default_poisson")]
+    poisson: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    shape: Option<String>,
+    #[serde(default)]
+    nodes: Vec<(f64, f64)>,
+    #[serde(default)]
+    plates: Vec<Plate>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    labels: Vec<Label>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    solid: Option<Solid>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    bars: Vec<Rebar>,
+}
+
+fn default_poisson() -> f64 {
+    0.3
+}
+
+fn default_thickness() -> f64 {
+    2.
+}
+
+fn default_elastic() -> f64 {
+    29000.
+}
+
+impl Serialize for Section {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SectionModel {
+            poisson: self.poisson(),
+            shape: None,
+            nodes: self
+                .graph
+                .node_indices()
+                .map(|n| (self.graph[n].x, self.graph[n].y))
+                .collect(),
+            plates: self
+                .graph
+                .edge_indices()
+                .map(|edge| {
+                    let (a, b) = self.graph.edge_endpoints(edge).unwrap();
+                    Plate {
+                        start: a.index(),
+                        end: b.index(),
+                        thickness: self.graph[edge].thickness,
+                        elastic: self.graph[edge].elastic,
+                        label: self.graph[edge].label.clone(),
+                    }
+                })
+                .collect(),
+            labels: self
+                .graph
+                .node_indices()
+                .filter_map(|n| {
+                    self.graph[n].label.clone().map(|label| Label {
+                        node: n.index(),
+                        label,
+                    })
+                })
+                .collect(),
+            solid: self.solid.clone(),
+            bars: self.bars.clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Section {
+    /// The nodes and plates are added to the graph as given, without the checks for nearby
+    /// nodes and crossing plates made when drawing a section
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Section, D::Error> {
+        let model = SectionModel::deserialize(deserializer)?;
+        let mut section = match &model.shape {
+            Some(name) => match catalog::lookup(name) {
+                Some(shape) => shape.section(model.poisson),
+                None => return Err(D::Error::custom(format!("unknown shape {}", name))),
+            },
+            None => Section::new(model.poisson),
+        };
+        if model.shape.is_none() {
+            let nodes: Vec<_> = model
+                .nodes
+                .iter()
+                .map(|&(x, y)| section.graph.add_node(Node { x, y, label: None }))
+                .collect();
+            for plate in model.plates {
+                match (nodes.get(plate.start), nodes.get(plate.end)) {
+                    (Some(&a), Some(&b)) if a != b => {
+                        section.graph.add_edge(
+                            a,
+                            b,
+                            Element {
+                                thickness: plate.thickness,
+                                elastic: plate.elastic,
+                                label: plate.label,
+                            },
+                        );
+                    }
+                    _ => {
+                        return Err(D::Error::custom(format!(
+                            "invalid plate from node {} to node {}",
+                            plate.start, plate.end
+                        )))
+                    }
+                }
+            }
+            for label in model.labels {
+                match nodes.get(label.node) {
+                    Some(_) if section.node_by_label(&label.label).is_some() => {
+                        return Err(D::Error::custom(format!("duplicate label {}", label.label)))
+                    }
+                    Some(&node) => section.graph[node].label = Some(label.label),
+                    None => {
+                        return Err(D::Error::custom(format!(
+                            "invalid label for node {}",
+                            label.node
+                        )))
+                    }
+                }
+            }
+        }
+        section.reindex();
+        section.solid = model.solid;
+        section.bars = model.bars;
+        Ok(section)
+    }
+}
+
+/// Serialises the supports of a beam as `{ position, type }` tables
+pub(crate) mod supports {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Entry {
+        position: f64,
+        #[serde(rename = "type")]
+        support: Support,
+    }
+
+    pub fn serialize<S: Serializer>(
+        supports: &[(f64, Support)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(
+            supports
+                .iter()
+                .map(|&(position, support)| Entry { position, support }),
+        )
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(f64, Support)>, D::Error> {
+        let entries = Vec::<Entry>::deserialize(deserializer)?;
+        Ok(entries
+            .into_iter()
+            .map(|e| (e.position, e.support))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load::Load;
+
+    const MODEL: &str = r#"
+units = { force = "kip", length = "in" }
+
+[section]
+poisson = 0.25
+nodes = [[0.0, 6.0], [0.0, 0.0], [4.0, 0.0]]
+plates = [
+    { start = 0, end = 1, thickness = 0.5, label = "long leg" },
+    { start = 1, end = 2, elastic = 10000.0 },
+]
+labels = [{ node = 1, label = "heel" }]
+
+[beam]
+length = 120.0
+supports = [{ position = 0.0, type = "fixed" }]
+loads = [
+    { type = "point", position = 120.0, mag = 2.0 },
+    { type = "distributed", start = 0.0, end = 60.0, mag = 0.1 },
+]
+"#;
+
+    /// A section model with the given plates and labels on three nodes
+    fn section(plates: &str, labels: &str) -> String {
+        format!(
+            "[section]\nnodes = [[0.0, 0.0], [1.0, 0.0], [2.0, 0.0]]\nplates = {}\nlabels = {}\n",
+            plates, labels
+        )
+    }
+
+    /// A file in the temporary directory, named for this process and the test
+    fn scratch(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("bender_rust_model_{}_{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn toml_model() {
+        let model = Model::parse(MODEL, Format::Toml).unwrap();
+        assert_eq!(model.units, Some(Units::new("kip", "in")));
+        let section = model.section.as_ref().unwrap();
+        assert_eq!(section.poisson(), 0.25);
+        assert_eq!(section.graph.node_count(), 3);
+        let heel = section.node_by_label("heel").unwrap();
+        assert_eq!((section.graph[heel].x, section.graph[heel].y), (0., 0.));
+        let plates: Vec<_> = section
+            .graph
+            .edge_indices()
+            .map(|e| &section.graph[e])
+            .collect();
+        assert_eq!(plates.len(), 2);
+        assert_eq!(
+            (plates[0].thickness, plates[0].elastic),
+            (0.5, default_elastic())
+        );
+        assert_eq!(plates[0].label.as_deref(), Some("long leg"));
+        assert_eq!(
+            (plates[1].thickness, plates[1].elastic),
+            (default_thickness(), 10000.)
+        );
+
+        let beam = model.beam.as_ref().unwrap();
+        assert_eq!(beam.length, 120.);
+        assert_eq!(beam.stiffness, 0.);
+        assert_eq!(beam.supports.len(), 1);
+        assert!(matches!(beam.supports[0], (p, Support::Fixed) if p == 0.));
+        assert!(matches!(beam.loads[0], Load::Point(p) if p.position == 120. && p.mag == 2.));
+        assert!(matches!(beam.loads[1], Load::Distributed(d) if d.end == 60.));
+        // the beam bends about the major axis of the section
+        let props = section.properties();
+        assert_eq!(
+            model.resolved_beam().unwrap().stiffness,
+            props.elastic * props.i11
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let model = Model::parse(MODEL, Format::Toml).unwrap();
+        let json = model.to_string(Format::Json);
+        for &format in &[Format::Toml, Format::Json, Format::Yaml] {
+            let text = model.to_string(format);
+            let again = Model::parse(&text, format).unwrap();
+            assert_eq!(again.to_string(format), text);
+            assert_eq!(again.to_string(Format::Json), json);
+        }
+
+        let path = scratch("round_trip.yml");
+        model.write(&path).unwrap();
+        let read = Model::read(&path).unwrap();
+        assert_eq!(read.to_string(Format::Json), json);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn catalogue_shape() {
+        let text = "[section]\nshape = \"w8x31\"\nnodes = [[5.0, 5.0]]\n";
+        let section = Model::parse(text, Format::Toml).unwrap().section.unwrap();
+        assert_eq!(section.graph.node_count(), 6);
+        let error = Model::parse("[section]\nshape = \"W1X1\"\n", Format::Toml)
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("unknown shape W1X1"));
+    }
+
+    #[test]
+    fn rejected_sections() {
+        let reject = |text: String, message: &str| {
+            let error = Model::parse(&text, Format::Toml).err().unwrap();
+            assert!(matches!(error, ModelError::Parse(_)));
+            assert!(
+                error.to_string().contains(message),
+                "{} does not say {}",
+                error,
+                message
+            );
+        };
+        let plates = "[{ start = 0, end = 1 }, { start = 1, end = 2 }]";
+        reject(
+            section("[{ start = 0, end = 3 }]", "[]"),
+            "invalid plate from node 0 to node 3",
+        );
+        reject(
+            section("[{ start = 1, end = 1 }]", "[]"),
+            "invalid plate from node 1 to node 1",
+        );
+        reject(
+            section(
+                plates,
+                r#"[{ node = 0, label = "end" }, { node = 2, label = "end" }]"#,
+            ),
+            "duplicate label end",
+        );
+        reject(
+            section(plates, r#"[{ node = 5, label = "end" }]"#),
+            "invalid label for node 5",
+        );
+        let json =
+            r#"{ "section": { "nodes": [[0, 0], [1, 0]], "plates": [{ "start": 0, "end": 2 }] } }"#;
+        assert!(Model::parse(json, Format::Json).is_err());
+        let yaml = "section:\n  nodes: [[0, 0], [1, 0]]\n  plates: [{ start: 2, end: 0 }]\n";
+        assert!(Model::parse(yaml, Format::Yaml).is_err());
+    }
+
+    #[test]
+    fn formats() {
+        assert_eq!(
+            Format::from_path(Path::new("a.toml")).unwrap(),
+            Format::Toml
+        );
+        assert_eq!(
+            Format::from_path(Path::new("a.JSON")).unwrap(),
+            Format::Json
+        );
+        assert_eq!(Format::from_path(Path::new("a.yml")).unwrap(), Format::Yaml);
+        assert_eq!(
+            Format::from_path(Path::new("a.yaml")).unwrap(),
+            Format::Yaml
+        );
+        let error = Format::from_path(Path::new("a.txt")).err().unwrap();
+        assert_eq!(error.to_string(), "unknown model format \"txt\"");
+        assert!(matches!(
+            Model::read(Path::new("no/such/model.toml")),
+            Err(ModelError::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,332 @@
+// Local plate buckling and effective widths (EN 1993-1-5, 4.4)
+use crate::build::Section;
+use crate::properties::{Properties, Segment};
+use petgraph::graph::EdgeIndex;
+use serde::Serialize;
+use std::f64::consts::PI;
+
+/// Support condition of a plate along its long edges
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum Boundary {
+    /// Supported by other plates at both ends
+    Internal,
+    /// Supported at one end and free at the other
+    Outstand,
+}
+
+/// Local buckling results for one plate
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Plate {
+    pub edge: EdgeIndex,
+    pub boundary: Boundary,
+    pub width: f64,
+    pub thickness: f64,
+    /// Stress ratio, ψ, of the less to the more compressed end
+    pub psi: f64,
+    /// Buckling factor, kσ
+    pub buckling_factor: f64,
+    /// Elastic critical plate buckling stress, σcr
+    pub critical: f64,
+    /// Plate slenderness, λp
+    pub slenderness: f64,
+    /// Reduction factor, ρ
+    pub reduction: f64,
+    pub effective_width: f64,
+}
+
+/// Effective plates and the properties of the effective section
+#[derive(Clone, Debug, Serialize)]
+pub struct Effective {
+    pub plates: Vec<Plate>,
+    pub segments: Vec<Segment>,
+    pub properties: Properties,
+}
+
+impl Section {
+    /// This function classifies a plate as internal or outstand from the degree of its nodes
+    pub fn boundary(&self, edge: EdgeIndex) -> Boundary {
+        let (a, b) = self.graph.edge_endpoints(edge).unwrap();
+        if self.graph.neighbors(a).count() > 1 && self.graph.neighbors(b).count() > 1 {
+            Boundary::Internal
+        } else {
+            Boundary::Outstand
+        }
+    }
+
+    /// This function computes the effective section under uniform compression `stress`
+    pub fn effective(&self, stress: f64) -> Effective {
+        self.effective_with(|_, _| stress)
+    }
+
+    /// This function computes the effective section under sagging bending about the x axis,
+    /// with `stress` the compression at the extreme top node
+    pub fn effective_bending(&self, stress: f64) -> Effective {
+        let centroid = Properties::from_segments(&self.segments()).centroid;
+        let top = self
+            .graph
+            .node_indices()
+            .map(|n| self.graph[n].y)
+            .fold(f64::MIN, f64::max);
+        self.effective_with(|_, y| stress * (y - centroid.1) / (top - centroid.1))
+    }
+
+    /// This function computes the effective section for a compressive stress distribution
+    /// given as a function of position, compression positive
+    pub fn effective_with(&self, stress: impl Fn(f64, f64) -> f64) -> Effective {
+        let mut plates = vec![];
+        let mut segments = vec![];
+        for (edge, seg) in self.graph.edge_indices().zip(self.segments()) {
+            let (a, _) = self.graph.edge_endpoints(edge).unwrap();
+            let boundary = self.boundary(edge);
+            // orient the plate so it runs from its supported end for outstands
+            let seg = if boundary == Boundary::Outstand && self.graph.neighbors(a).count() == 1 {
+                Segment {
+                    start: seg.end,
+                    end: seg.start,
+                    ..seg
+                }
+            } else {
+                seg
+            };
+            let width = seg.length();
+            let start = stress(seg.start.0, seg.start.1);
+            let end = stress(seg.end.0, seg.end.1);
+
+            let (psi, k, hole) = match boundary {
+                Boundary::Internal => {
+                    // measure from the more compressed end
+                    let (s1, s2, flipped) = if start >= end {
+                        (start, end, false)
+                    } else {
+                        (end, start, true)
+                    };
+                    let psi = if s1 > 0. { s2 / s1 } else { 1. };
+                    let k = internal_factor(psi);
+                    let rho = reduction(boundary, psi, slenderness(&seg, self.poisson(), k, s1));
+                    let hole = if psi >= 0. {
+                        let beff = rho * width;
+                        let be1 = 2. * beff / (5. - psi);
+                        (be1, width - (beff - be1))
+                    } else {
+                        let bc = width / (1. - psi);
+                        let beff = rho * bc;
+                        (0.4 * beff, bc - 0.6 * beff)
+                    };
+                    let hole = if flipped {
+                        (width - hole.1, width - hole.0)
+                    } else {
+                        hole
+                    };
+                    (psi, k, if s1 > 0. { hole } else { (width, width) })
+                }
+                Boundary::Outstand => {
+                    let s1 = start.max(end);
+                    if end >= start {
+                        // maximum compression at the free end
+                        let psi = if s1 > 0. { start / end } else { 1. };
+                        let k = outstand_factor(psi, true);
+                        let rho =
+                            reduction(boundary, psi, slenderness(&seg, self.poisson(), k, s1));
+                        let bc = if psi >= 0. { width } else { width / (1. - psi) };
+                        let hole = (width - bc + rho * bc, width);
+                        (psi, k, if s1 > 0. { hole } else { (width, width) })
+                    } else {
+                        // maximum compression at the supported end
+                        let psi = if s1 > 0. { end / start } else { 1. };
+                        let k = outstand_factor(psi, false);
+                        let rho =
+                            reduction(boundary, psi, slenderness(&seg, self.poisson(), k, s1));
+                        let bc = if psi >= 0. { width } else { width / (1. - psi) };
+                        (
+                            psi,
+                            k,
+                            if s1 > 0. {
+                                (rho * bc, bc)
+                            } else {
+                                (width, width)
+                            },
+                        )
+                    }
+                }
+            };
+
+            let critical = k * euler_stress(&seg, self.poisson());
+            let lambda = (start.max(end).max(0.) / critical).sqrt();
+            let effective_width = width - (hole.1 - hole.0);
+            plates.push(Plate {
+                edge,
+                boundary,
+                width,
+                thickness: seg.thickness,
+                psi,
+                buckling_factor: k,
+                critical,
+                slenderness: lambda,
+                reduction: effective_width / width,
+                effective_width,
+            });
+
+            let at = |s: f64| {
+                (
+                    seg.start.0 + (seg.end.0 - seg.start.0) * s / width,
+                    seg.start.1 + (seg.end.1 - seg.start.1) * s / width,
+                )
+            };
+            for (from, to) in [(0., hole.0), (hole.1, width)].iter() {
+                if to - from > 0. {
+                    segments.push(Segment {
+                        start: at(*from),
+                        end: at(*to),
+                        ..seg
+                    });
+                }
+            }
+        }
+
+        let properties = Properties::from_segments(&segments);
+        Effective {
+            plates,
+            segments,
+            properties,
+        }
+    }
+}
+
+/// The elastic critical stress of a plate with unit buckling factor, σE
+pub fn euler_stress(seg: &Segment, poisson: f64) -> f64 {
+    PI * PI * seg.elastic / (12. * (1. - poisson * poisson))
+        * (seg.thickness / seg.length()).powi(2)
+}
+
+/// Buckling factor of an internal plate (EN 1993-1-5 Table 4.1)
+pub fn internal_factor(psi: f64) -> f64 {
+    if psi >= 1. {
+        4.
+    } else if psi > 0. {
+        8.2 / (1.05 + psi)
+    } else if psi >= -1. {
+        7.81 - 6.29 * psi + 9.78 * psi * psi
+    } else {
+        5.98 * (1. - psi).powi(2)
+    }
+}
+
+/// Buckling factor of an outstand (EN 1993-1-5 Table 4.2), `free` when the maximum
+/// compression is at the free end
+pub fn outstand_factor(psi: f64, free: bool) -> f64 {
+    if free {
+        0.57 - 0.21 * psi + 0.07 * psi * psi
+    } else if psi >= 0. {
+        0.578 / (psi + 0.34)
+    } else {
+        1.7 - 5. * psi + 17.1 * psi * psi
+    }
+}
+
+fn slenderness(seg: &Segment, poisson: f64, k: f64, stress: f64) -> f64 {
+    (stress.max(0.) / (k * euler_stress(seg, poisson))).sqrt()
+}
+
+/// Reduction factor for plate buckling (EN 1993-1-5 Eq. 4.2 and 4.3)
+pub fn reduction(boundary: Boundary, psi: f64, lambda: f64) -> f64 {
+    let rho = match boundary {
+        Boundary::Internal if lambda > 0.5 + (0.085 - 0.055 * psi).sqrt() => {
+            (lambda - 0.055 * (3. + psi)) / (lambda * lambda)
+        }
+        Boundary::Outstand if lambda > 0.748 => (lambda - 0.188) / (lambda * lambda),
+        _ => 1.,
+    };
+    rho.min(1.)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Plates 10 thick of steel between the given nodes
+    fn plates(nodes: &[(f64, f64)], plates: &[(usize, usize)]) -> Section {
+        let mut section = Section::new(0.3);
+        let n: Vec<_> = nodes.iter().map(|&(x, y)| section.add_node(x, y)).collect();
+        for &(a, b) in plates {
+            section.add_edge(n[a], n[b], Some(10.), Some(210000.));
+        }
+        section
+    }
+
+    /// Plate slenderness by EN 1993-1-5 Eq. 4.3, (b/t) / (28.4 ε √kσ)
+    fn code_slenderness(width: f64, stress: f64, k: f64) -> f64 {
+        width / 10. / (28.4 * (235. / stress).sqrt() * k.sqrt())
+    }
+
+    #[test]
+    fn buckling_factors() {
+        // Table 4.1
+        assert_eq!(internal_factor(1.), 4.);
+        assert!((internal_factor(0.) - 7.81).abs() < 1e-2);
+        assert!((internal_factor(-1.) - 23.9).abs() < 0.05);
+        assert!((internal_factor(-3.) - 95.7).abs() < 0.1);
+        // Table 4.2, the maximum compression at the free edge and then at the support
+        assert!((outstand_factor(1., true) - 0.43).abs() < 1e-9);
+        assert!((outstand_factor(0., true) - 0.57).abs() < 1e-9);
+        assert!((outstand_factor(-1., true) - 0.85).abs() < 1e-9);
+        assert!((outstand_factor(1., false) - 0.43).abs() < 1e-2);
+        assert!((outstand_factor(0., false) - 1.70).abs() < 1e-2);
+        assert!((outstand_factor(-1., false) - 23.8).abs() < 1e-9);
+    }
+
+    #[test]
+    fn uniformly_compressed_internal_plate() {
+        // a square box of plates 500 wide, c/t = 50
+        let section = plates(
+            &[(0., 0.), (500., 0.), (500., 500.), (0., 500.)],
+            &[(0, 1), (1, 2), (2, 3), (3, 0)],
+        );
+        let effective = section.effective(355.);
+        for plate in &effective.plates {
+            assert_eq!(plate.boundary, Boundary::Internal);
+            assert_eq!(plate.psi, 1.);
+            assert_eq!(plate.buckling_factor, 4.);
+            let lambda = code_slenderness(500., 355., 4.);
+            assert!((plate.slenderness - lambda).abs() < 2e-3 * lambda);
+            let rho = (plate.slenderness - 0.22) / plate.slenderness.powi(2);
+            assert!((plate.reduction - rho).abs() < 1e-12);
+            assert!((plate.effective_width - rho * 500.).abs() < 1e-9);
+        }
+        // each plate loses its middle, leaving half the effective width at either end
+        assert_eq!(effective.segments.len(), 8);
+        let area: f64 = effective
+            .plates
+            .iter()
+            .map(|p| p.effective_width * 10.)
+            .sum();
+        assert!((effective.properties.area - area).abs() < 1e-6);
+        assert!((effective.properties.centroid.0 - 250.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn uniformly_compressed_outstands() {
+        // a cruciform of outstands 150 long, c/t = 15
+        let section = plates(
+            &[(0., 0.), (150., 0.), (0., 150.), (-150., 0.), (0., -150.)],
+            &[(0, 1), (0, 2), (0, 3), (0, 4)],
+        );
+        let effective = section.effective(355.);
+        for plate in &effective.plates {
+            assert_eq!(plate.boundary, Boundary::Outstand);
+            assert!((plate.buckling_factor - 0.43).abs() < 1e-9);
+            let lambda = code_slenderness(150., 355., 0.43);
+            assert!((plate.slenderness - lambda).abs() < 2e-3 * lambda);
+            let rho = (plate.slenderness - 0.188) / plate.slenderness.powi(2);
+            assert!((plate.reduction - rho).abs() < 1e-12);
+        }
+        // the free ends are lost
+        for seg in &effective.segments {
+            assert!(seg.start == (0., 0.) || seg.end == (0., 0.));
+        }
+
+        // stocky outstands are fully effective
+        let stocky = section.effective(100.);
+        assert!(stocky.plates.iter().all(|p| p.reduction == 1.));
+        assert_eq!(stocky.segments.len(), 4);
+    }
+}
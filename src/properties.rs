@@ -0,0 +1,370 @@
+// Thin-walled section properties computed from the plate centrelines
+use crate::build::Section;
+use petgraph::graph::NodeIndex;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// A straight plate of a thin-walled section, described by its centreline
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Segment {
+    pub start: (f64, f64),
+    pub end: (f64, f64),
+    pub thickness: f64,
+    pub elastic: f64,
+}
+
+impl Segment {
+    pub fn length(&self) -> f64 {
+        ((self.end.0 - self.start.0).powi(2) + (self.end.1 - self.start.1).powi(2)).sqrt()
+    }
+
+    pub fn area(&self) -> f64 {
+        self.length() * self.thickness
+    }
+
+    /// The corners of the rectangle occupied by the plate
+    pub fn corners(&self) -> [(f64, f64); 4] {
+        let length = self.length();
+        let nx = -(self.end.1 - self.start.1) / length * self.thickness / 2.;
+        let ny = (self.end.0 - self.start.0) / length * self.thickness / 2.;
+        [
+            (self.start.0 + nx, self.start.1 + ny),
+            (self.end.0 + nx, self.end.1 + ny),
+            (self.end.0 - nx, self.end.1 - ny),
+            (self.start.0 - nx, self.start.1 - ny),
+        ]
+    }
+}
+
+/// Elastic properties of a cross-section
+#[derive(Clone, Debug, Serialize)]
+pub struct Properties {
+    pub area: f64,
+    pub centroid: (f64, f64),
+    /// Second moments of area about the centroidal axes parallel to x and y
+    pub ixx: f64,
+    pub iyy: f64,
+    pub ixy: f64,
+    /// Principal second moments of area, major then minor
+    pub i11: f64,
+    pub i22: f64,
+    /// Angle from the x axis to the major principal axis, in radians
+    pub phi: f64,
+    /// Elastic section moduli to the extreme fibres, the smaller of the two sides
+    pub sxx: f64,
+    pub syy: f64,
+    /// Plastic section moduli about the equal area axes parallel to x and y
+    pub zxx: f64,
+    pub zyy: f64,
+    /// St. Venant torsion constant
+    pub torsion: f64,
+    pub shear_centre: (f64, f64),
+    /// Warping constant about the shear centre
+    pub warping: f64,
+    /// Area-weighted modulus of elasticity
+    pub elastic: f64,
+}
+
+impl Properties {
+    /// This function computes the properties of a set of plates. The shear centre is placed
+    /// at the centroid and the warping constant left at zero; those need the plate
+    /// connectivity and are filled in by `Section::properties`.
+    pub fn from_segments(segments: &[Segment]) -> Properties {
+        let (mut area, mut qx, mut qy, mut ea) = (0., 0., 0., 0.);
+        for seg in segments {
+            let a = seg.area();
+            area += a;
+            qx += a * (seg.start.1 + seg.end.1) / 2.;
+            qy += a * (seg.start.0 + seg.end.0) / 2.;
+            ea += a * seg.elastic;
+        }
+        let centroid = (qy / area, qx / area);
+
+        let (mut ixx, mut iyy, mut ixy, mut torsion) = (0., 0., 0., 0.);
+        for seg in segments {
+            let a = seg.area();
+            let (x1, y1) = (seg.start.0 - centroid.0, seg.start.1 - centroid.1);
+            let (x2, y2) = (seg.end.0 - centroid.0, seg.end.1 - centroid.1);
+            ixx += a * linear_product(y1, y2, y1, y2);
+            iyy += a * linear_product(x1, x2, x1, x2);
+            ixy += a * linear_product(x1, x2, y1, y2);
+            torsion += seg.length() * seg.thickness.powi(3) / 3.;
+        }
+
+        let corners: Vec<(f64, f64)> = segments.iter().flat_map(|seg| seg.corners()).collect();
+        let extreme = |axis: fn(&(f64, f64)) -> f64, about: f64| {
+            corners
+                .iter()
+                .map(|c| (axis(c) - about).abs())
+                .fold(0., f64::max)
+        };
+        let sxx = ixx / extreme(|c| c.1, centroid.1);
+        let syy = iyy / extreme(|c| c.0, centroid.0);
+        let zxx = plastic_modulus(segments, |p| p.1);
+        let zyy = plastic_modulus(segments, |p| p.0);
+
+        let phi = 0.5 * (-2. * ixy).atan2(ixx - iyy);
+        let mean = (ixx + iyy) / 2.;
+        let radius = (((ixx - iyy) / 2.).powi(2) + ixy * ixy).sqrt();
+
+        Properties {
+            area,
+            centroid,
+            ixx,
+            iyy,
+            ixy,
+            i11: mean + radius,
+            i22: mean - radius,
+            phi,
+            sxx,
+            syy,
+            zxx,
+            zyy,
+            torsion,
+            shear_centre: centroid,
+            warping: 0.,
+            elastic: ea / area,
+        }
+    }
+
+    /// Radius of gyration about the minor principal axis
+    pub fn r22(&self) -> f64 {
+        (self.i22 / self.area).sqrt()
+    }
+
+    /// Radius of gyration about the major principal axis
+    pub fn r11(&self) -> f64 {
+        (self.i11 / self.area).sqrt()
+    }
+}
+
+impl Section {
+    /// This function lists the plates of the section
+    pub fn segments(&self) -> Vec<Segment> {
+        self.graph
+            .edge_indices()
+            .map(|edge| {
+                let (a, b) = self.graph.edge_endpoints(edge).unwrap();
+                let a = &self.graph[a];
+                let b = &self.graph[b];
+                let element = &self.graph[edge];
+                Segment {
+                    start: (a.x, a.y),
+                    end: (b.x, b.y),
+                    thickness: element.thickness,
+                    elastic: element.elastic,
+                }
+            })
+            .collect()
+    }
+
+    /// This function computes the thin-walled properties of the section. The torsion and
+    /// warping constants assume an open section; closing plates of cells are integrated
+    /// but do not carry circulating shear flow.
+    pub fn properties(&self) -> Properties {
+        if let Some(props) = &self.cache().properties {
+            return props.clone();
+        }
+        let segments = self.segments();
+        let mut props = Properties::from_segments(&segments);
+
+        let (omega_x, omega_y) = {
+            let omega = self.sectorial(props.centroid);
+            let (mut omega_x, mut omega_y) = (0., 0.);
+            for (seg, w) in segments.iter().zip(omega) {
+                let a = seg.area();
+                let (x1, y1) = (
+                    seg.start.0 - props.centroid.0,
+                    seg.start.1 - props.centroid.1,
+                );
+                let (x2, y2) = (seg.end.0 - props.centroid.0, seg.end.1 - props.centroid.1);
+                omega_x += a * linear_product(w.0, w.1, x1, x2);
+                omega_y += a * linear_product(w.0, w.1, y1, y2);
+            }
+            (omega_x, omega_y)
+        };
+
+        let det = props.ixx * props.iyy - props.ixy * props.ixy;
+        props.shear_centre = (
+            props.centroid.0 + (props.iyy * omega_y - props.ixy * omega_x) / det,
+            props.centroid.1 + (props.ixy * omega_y - props.ixx * omega_x) / det,
+        );
+
+        // normalise the sectorial coordinate about the shear centre
+        let omega = self.sectorial(props.shear_centre);
+        let mean = segments
+            .iter()
+            .zip(&omega)
+            .map(|(seg, w)| seg.area() * (w.0 + w.1) / 2.)
+            .sum::<f64>()
+            / props.area;
+        props.warping = segments
+            .iter()
+            .zip(&omega)
+            .map(|(seg, w)| {
+                seg.area() * linear_product(w.0 - mean, w.1 - mean, w.0 - mean, w.1 - mean)
+            })
+            .sum();
+
+        self.cache().properties = Some(props.clone());
+        props
+    }
+
+    /// This function computes the unnormalised sectorial coordinate at both ends of every
+    /// plate, taken about the given pole and starting from zero at the first node of each
+    /// connected part of the graph
+    pub fn sectorial(&self, pole: (f64, f64)) -> Vec<(f64, f64)> {
+        let mut omega: HashMap<NodeIndex, f64> = HashMap::new();
+        for root in self.graph.node_indices() {
+            if omega.contains_key(&root) {
+                continue;
+            }
+            omega.insert(root, 0.);
+            let mut stack = vec![root];
+            while let Some(node) = stack.pop() {
+                let (x1, y1) = (self.graph[node].x - pole.0, self.graph[node].y - pole.1);
+                for neigh in self.graph.neighbors(node) {
+                    if omega.contains_key(&neigh) {
+                        continue;
+                    }
+                    let (x2, y2) = (self.graph[neigh].x - pole.0, self.graph[neigh].y - pole.1);
+                    omega.insert(neigh, omega[&node] + x1 * y2 - x2 * y1);
+                    stack.push(neigh);
+                }
+            }
+        }
+
+        self.graph
+            .edge_indices()
+            .map(|edge| {
+                let (a, b) = self.graph.edge_endpoints(edge).unwrap();
+                (omega[&a], omega[&b])
+            })
+            .collect()
+    }
+}
+
+/// This function computes the plastic section modulus of a set of plates, the first moment
+/// of area of both halves about the equal area axis in the coordinate picked by `axis`
+pub fn plastic_modulus(segments: &[Segment], axis: impl Fn((f64, f64)) -> f64) -> f64 {
+    let total: f64 = segments.iter().map(|seg| seg.area()).sum();
+    let above = |p: f64| area_above(segments, &axis, p);
+
+    let points = segments
+        .iter()
+        .flat_map(|seg| vec![axis(seg.start), axis(seg.end)]);
+    let (mut low, mut high) =
+        points.fold((f64::MAX, f64::MIN), |acc, v| (acc.0.min(v), acc.1.max(v)));
+    for _ in 0..100 {
+        let mid = (low + high) / 2.;
+        if above(mid) > total / 2. {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    absolute_moment(segments, axis, (low + high) / 2.)
+}
+
+/// This function computes the first moment of area of the plates about `p` in the
+/// coordinate picked by `axis`, taking distances on either side as positive
+pub fn absolute_moment(segments: &[Segment], axis: impl Fn((f64, f64)) -> f64, p: f64) -> f64 {
+    segments
+        .iter()
+        .map(|seg| {
+            let (f1, f2) = (axis(seg.start) - p, axis(seg.end) - p);
+            if f1 * f2 >= 0. {
+                seg.area() * (f1 + f2).abs() / 2.
+            } else {
+                seg.area() * (f1 * f1 + f2 * f2) / (2. * (f1 - f2).abs())
+            }
+        })
+        .sum()
+}
+
+/// This function computes the area of the plates lying beyond `p` in the coordinate picked
+/// by `axis`
+pub fn area_above(segments: &[Segment], axis: impl Fn((f64, f64)) -> f64, p: f64) -> f64 {
+    segments
+        .iter()
+        .map(|seg| {
+            let (f1, f2) = (axis(seg.start) - p, axis(seg.end) - p);
+            if f1 >= 0. && f2 >= 0. {
+                seg.area()
+            } else if f1 <= 0. && f2 <= 0. {
+                0.
+            } else {
+                seg.area() * f1.max(f2) / (f1 - f2).abs()
+            }
+        })
+        .sum()
+}
+
+/// The mean of the product of two functions varying linearly along a plate
+pub fn linear_product(f1: f64, f2: f64, g1: f64, g2: f64) -> f64 {
+    (2. * f1 * g1 + f1 * g2 + f2 * g1 + 2. * f2 * g2) / 6.
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A section of plates of one thickness between the given nodes
+    fn plates(nodes: &[(f64, f64)], plates: &[(usize, usize)], thickness: f64) -> Section {
+        let mut section = Section::new(0.3);
+        let n: Vec<_> = nodes.iter().map(|&(x, y)| section.add_node(x, y)).collect();
+        for &(a, b) in plates {
+            section.add_edge(n[a], n[b], Some(thickness), Some(200.));
+        }
+        section
+    }
+
+    #[test]
+    fn channel() {
+        // flanges 5 long, web 10 deep: e = 3b²/(h + 6b) behind the web
+        let t = 0.2;
+        let section = plates(
+            &[(5., 5.), (0., 5.), (0., -5.), (5., -5.)],
+            &[(0, 1), (1, 2), (2, 3)],
+            t,
+        );
+        let props = section.properties();
+        assert!((props.area - 20. * t).abs() < 1e-12);
+        assert!((props.centroid.0 - 1.25).abs() < 1e-12);
+        assert!(props.centroid.1.abs() < 1e-12);
+        let ixx = t * 1000. / 12. + 2. * 5. * t * 25.;
+        assert!((props.ixx - ixx).abs() < 1e-9);
+        assert!((props.shear_centre.0 + 1.875).abs() < 1e-9);
+        assert!(props.shear_centre.1.abs() < 1e-9);
+        assert!((props.torsion - 20. * t.powi(3) / 3.).abs() < 1e-12);
+        assert!((props.elastic - 200.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn i_section() {
+        // flanges 8 wide and 1 thick, 12 apart
+        let (b, h, t) = (8., 12., 1.);
+        let section = plates(
+            &[
+                (-b / 2., h / 2.),
+                (0., h / 2.),
+                (b / 2., h / 2.),
+                (-b / 2., -h / 2.),
+                (0., -h / 2.),
+                (b / 2., -h / 2.),
+            ],
+            &[(0, 1), (1, 2), (3, 4), (4, 5), (1, 4)],
+            t,
+        );
+        let props = section.properties();
+        let iyy = 2. * t * b.powi(3) / 12.;
+        assert!((props.iyy - iyy).abs() < 1e-9);
+        assert!((props.i22 - iyy).abs() < 1e-9);
+        assert!(props.ixy.abs() < 1e-9);
+        assert!((props.warping - iyy * h * h / 4.).abs() < 1e-9);
+        assert!(props.shear_centre.0.abs() < 1e-9 && props.shear_centre.1.abs() < 1e-9);
+        // half the area above the axis, at a quarter of the depth for the web
+        let zxx = 2. * b * t * h / 2. + 2. * (h / 2. * t) * h / 4.;
+        assert!((props.zxx - zxx).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,243 @@
+// A minimal RGB raster image with an uncompressed PNG encoder
+use std::fs::File;
+use std::io::{self, prelude::*};
+use std::path::Path;
+
+pub type Colour = [u8; 3];
+
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Colour>,
+}
+
+/// Glyphs of a 3 by 5 pixel font, one row per entry with the leftmost pixel in bit 2
+const GLYPHS: [(char, [u8; 5]); 14] = [
+    ('0', [7, 5, 5, 5, 7]),
+    ('1', [2, 6, 2, 2, 7]),
+    ('2', [7, 1, 7, 4, 7]),
+    ('3', [7, 1, 7, 1, 7]),
+    ('4', [5, 5, 7, 1, 1]),
+    ('5', [7, 4, 7, 1, 7]),
+    ('6', [7, 4, 7, 5, 7]),
+    ('7', [7, 1, 1, 1, 1]),
+    ('8', [7, 5, 7, 5, 7]),
+    ('9', [7, 5, 7, 1, 7]),
+    ('-', [0, 0, 7, 0, 0]),
+    ('+', [0, 2, 7, 2, 0]),
+    ('.', [0, 0, 0, 0, 2]),
+    ('e', [0, 7, 7, 4, 7]),
+];
+
+impl Image {
+    pub fn new(width: usize, height: usize, background: Colour) -> Image {
+        Image {
+            width,
+            height,
+            pixels: vec![background; width * height],
+        }
+    }
+
+    /// This function sets a pixel, ignoring those outside the image
+    pub fn set(&mut self, x: i64, y: i64, colour: Colour) {
+        if x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+            self.pixels[y as usize * self.width + x as usize] = colour;
+        }
+    }
+
+    pub fn fill_rect(&mut self, x: i64, y: i64, width: i64, height: i64, colour: Colour) {
+        for j in y..y + height {
+            for i in x..x + width {
+                self.set(i, j, colour);
+            }
+        }
+    }
+
+    /// This function draws a line between two pixels (Bresenham)
+    pub fn line(&mut self, a: (i64, i64), b: (i64, i64), colour: Colour) {
+        let (dx, dy) = ((b.0 - a.0).abs(), -(b.1 - a.1).abs());
+        let (sx, sy) = ((b.0 - a.0).signum(), (b.1 - a.1).signum());
+        let (mut x, mut y, mut err) = (a.0, a.1, dx + dy);
+        loop {
+            self.set(x, y, colour);
+            if x == b.0 && y == b.1 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    /// This function writes text in the built-in font, which has digits and the characters
+    /// of numbers only, magnified by `scale`
+    pub fn text(&mut self, x: i64, y: i64, text: &str, scale: i64, colour: Colour) {
+        for (n, ch) in text.chars().enumerate() {
+            let glyph = match GLYPHS.iter().find(|g| g.0 == ch.to_ascii_lowercase()) {
+                Some(glyph) => glyph.1,
+                None => continue,
+            };
+            let left = x + n as i64 * 4 * scale;
+            for (row, bits) in glyph.iter().enumerate() {
+                for col in 0..3 {
+                    if bits & (4 >> col) != 0 {
+                        self.fill_rect(
+                            left + col * scale,
+                            y + row as i64 * scale,
+                            scale,
+                            scale,
+                            colour,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// This function encodes the image as a PNG using stored deflate blocks
+    pub fn png(&self) -> Vec<u8> {
+        let mut raw = Vec::with_capacity(self.height * (3 * self.width + 1));
+        for row in self.pixels.chunks(self.width) {
+            raw.push(0);
+            for pixel in row {
+                raw.extend_from_slice(pixel);
+            }
+        }
+
+        let mut zlib = vec![0x78, 0x01];
+        let blocks: Vec<&[u8]> = raw.chunks(65535).collect();
+        for (i, block) in blocks.iter().enumerate() {
+            zlib.push(if i + 1 == blocks.len() { 1 } else { 0 });
+            let len = block.len() as u16;
+            zlib.extend_from_slice(&len.to_le_bytes());
+            zlib.extend_from_slice(&(!len).to_le_bytes());
+            zlib.extend_from_slice(block);
+        }
+        zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+        let mut header = vec![];
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+        let mut png = vec![0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+        chunk(&mut png, b"IHDR", &header);
+        chunk(&mut png, b"IDAT", &zlib);
+        chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    /// This function writes the image to a PNG file
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        File::create(path)?.write_all(&self.png())?;
+        println!("successfully wrote to {}", path.display());
+        Ok(())
+    }
+}
+
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drawing() {
+        let mut image = Image::new(8, 6, [255, 255, 255]);
+        image.set(-1, 2, [0, 0, 0]);
+        image.set(8, 2, [0, 0, 0]);
+        assert!(image.pixels.iter().all(|&p| p == [255, 255, 255]));
+
+        image.line((0, 0), (7, 5), [255, 0, 0]);
+        assert_eq!(image.pixels[0], [255, 0, 0]);
+        assert_eq!(image.pixels[5 * 8 + 7], [255, 0, 0]);
+        assert_eq!(
+            image.pixels.iter().filter(|&&p| p == [255, 0, 0]).count(),
+            8
+        );
+
+        image.fill_rect(6, 4, 4, 4, [0, 0, 255]);
+        assert_eq!(
+            image.pixels.iter().filter(|&&p| p == [0, 0, 255]).count(),
+            4
+        );
+    }
+
+    #[test]
+    fn glyphs() {
+        let mut image = Image::new(12, 5, [255, 255, 255]);
+        image.text(0, 0, "1x-", 1, [0, 0, 0]);
+        let lit = |x: usize, y: usize| image.pixels[y * 12 + x] == [0, 0, 0];
+        // the one, then the minus sign in the third place as there is no glyph for x
+        assert!(lit(1, 0) && !lit(0, 0) && lit(0, 4) && lit(2, 4));
+        assert!((4..8).all(|x| (0..5).all(|y| !lit(x, y))));
+        assert!((8..11).all(|x| lit(x, 2)) && !lit(8, 1));
+    }
+
+    #[test]
+    fn png_encoding() {
+        let mut image = Image::new(3, 2, [10, 20, 30]);
+        image.set(2, 1, [200, 100, 0]);
+        let png = image.png();
+        assert_eq!(&png[..8], &[0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a]);
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[16..24], &[0, 0, 0, 3, 0, 0, 0, 2]);
+        assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+        assert_eq!(&png[png.len() - 4..], &0xae42_6082u32.to_be_bytes());
+
+        // one stored block of the two filtered rows
+        let idat = 8 + 12 + 13;
+        let length = u32::from_be_bytes([png[idat], png[idat + 1], png[idat + 2], png[idat + 3]]);
+        let raw = [
+            0, 10, 20, 30, 10, 20, 30, 10, 20, 30, 0, 10, 20, 30, 10, 20, 30, 200, 100, 0,
+        ];
+        assert_eq!(length as usize, 2 + 5 + raw.len() + 4);
+        let data = &png[idat + 8..idat + 8 + length as usize];
+        assert_eq!(&data[..3], &[0x78, 0x01, 1]);
+        assert_eq!(&data[7..7 + raw.len()], &raw);
+        assert_eq!(&data[7 + raw.len()..], &adler32(&raw).to_be_bytes());
+    }
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+}
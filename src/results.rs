@@ -0,0 +1,488 @@
+// Serialisation of results to JSON with the units of their quantities
+use crate::aisc;
+use crate::beam::Analysis;
+use crate::buckling::LateralTorsional;
+use crate::composite::Plastic;
+use crate::concrete::{Transformed, Ultimate};
+use crate::eurocode;
+use crate::fibre::MomentCurvature;
+use crate::interaction::Surface;
+use crate::plate::Effective;
+use crate::properties::Properties;
+use crate::stress::Sample;
+use crate::warping::Warping;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, prelude::*};
+use std::path::Path;
+
+/// Version of the JSON layout, raised when a field is renamed or removed
+pub const FORMAT_VERSION: u32 = 1;
+
+/// A consistent system of units, named by its units of force and length
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Units {
+    pub force: String,
+    pub length: String,
+}
+
+impl Default for Units {
+    /// Placeholder names, for models that do not state their units
+    fn default() -> Units {
+        Units::new("force", "length")
+    }
+}
+
+impl Units {
+    pub fn new(force: &str, length: &str) -> Units {
+        Units {
+            force: force.to_string(),
+            length: length.to_string(),
+        }
+    }
+
+    /// kip and inch, giving stresses in ksi
+    pub fn kip_inch() -> Units {
+        Units::new("kip", "in")
+    }
+
+    /// N and mm, giving stresses in N/mm²
+    pub fn newton_millimetre() -> Units {
+        Units::new("N", "mm")
+    }
+
+    /// kN and m, giving stresses in kN/m²
+    pub fn kilonewton_metre() -> Units {
+        Units::new("kN", "m")
+    }
+
+    /// This function names the unit of a quantity, such as `kip*in` or `N/mm^2`
+    pub fn unit(&self, dimension: Dimension) -> String {
+        let (force, length) = match dimension {
+            Dimension::Angle => return "rad".to_string(),
+            Dimension::Power(force, length) => (force, length),
+        };
+        let power = |name: &str, n: i32| match n.abs() {
+            1 => name.to_string(),
+            n => format!("{}^{}", name, n),
+        };
+        let mut above = vec![];
+        let mut below = vec![];
+        for &(name, n) in [(&self.force, force), (&self.length, length)].iter() {
+            if n > 0 {
+                above.push(power(name, n));
+            } else if n < 0 {
+                below.push(power(name, n));
+            }
+        }
+        let above = if above.is_empty() {
+            "1".to_string()
+        } else {
+            above.join("*")
+        };
+        if below.is_empty() {
+            above
+        } else {
+            format!("{}/{}", above, below.join("*"))
+        }
+    }
+}
+
+/// Dimensions of a quantity as powers of force and length, or a plane angle
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Dimension {
+    Power(i32, i32),
+    Angle,
+}
+
+pub const RATIO: Dimension = Dimension::Power(0, 0);
+pub const LENGTH: Dimension = Dimension::Power(0, 1);
+pub const AREA: Dimension = Dimension::Power(0, 2);
+pub const MODULUS: Dimension = Dimension::Power(0, 3);
+pub const INERTIA: Dimension = Dimension::Power(0, 4);
+pub const WARPING: Dimension = Dimension::Power(0, 6);
+pub const CURVATURE: Dimension = Dimension::Power(0, -1);
+pub const FORCE: Dimension = Dimension::Power(1, 0);
+pub const MOMENT: Dimension = Dimension::Power(1, 1);
+pub const STRESS: Dimension = Dimension::Power(1, -2);
+
+/// A result that can be written to JSON along with the units of its fields
+pub trait Quantities: Serialize {
+    /// A stable name for the kind of result
+    const KIND: &'static str;
+
+    /// Dimensions of the numeric fields, keyed by field name wherever the field is nested.
+    /// Fields left out, such as the values of code check entries, are in the units of the
+    /// inputs they were computed from.
+    fn dimensions() -> Vec<(&'static str, Dimension)>;
+}
+
+#[derive(Serialize)]
+struct Envelope<'a, T> {
+    kind: &'static str,
+    version: u32,
+    units: &'a Units,
+    quantities: BTreeMap<&'static str, String>,
+    data: &'a T,
+}
+
+/// This function serialises a result to JSON, wrapped with its kind, the layout version and
+/// the units of its fields
+pub fn to_json<T: Quantities>(result: &T, units: &Units) -> String {
+    let envelope = Envelope {
+        kind: T::KIND,
+        version: FORMAT_VERSION,
+        units,
+        quantities: T::dimensions()
+            .into_iter()
+            .map(|(field, dimension)| (field, units.unit(dimension)))
+            .collect(),
+        data: result,
+    };
+    serde_json::to_string_pretty(&envelope).unwrap()
+}
+
+/// This function writes a result to a JSON file
+pub fn write_json<T: Quantities>(result: &T, units: &Units, path: &Path) -> io::Result<()> {
+    File::create(path)?.write_all(to_json(result, units).as_bytes())?;
+    println!("successfully wrote to {}", path.display());
+    Ok(())
+}
+
+/// Serialises named values as a map, keeping their order
+pub(crate) fn pairs<S: Serializer>(
+    values: &[(String, f64)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(values.iter().map(|(name, value)| (name, value)))
+}
+
+impl Quantities for Properties {
+    const KIND: &'static str = "section_properties";
+
+    fn dimensions() -> Vec<(&'static str, Dimension)> {
+        vec![
+            ("area", AREA),
+            ("centroid", LENGTH),
+            ("ixx", INERTIA),
+            ("iyy", INERTIA),
+            ("ixy", INERTIA),
+            ("i11", INERTIA),
+            ("i22", INERTIA),
+            ("phi", Dimension::Angle),
+            ("sxx", MODULUS),
+            ("syy", MODULUS),
+            ("zxx", MODULUS),
+            ("zyy", MODULUS),
+            ("torsion", INERTIA),
+            ("shear_centre", LENGTH),
+            ("warping", WARPING),
+            ("elastic", STRESS),
+        ]
+    }
+}
+
+impl Quantities for Vec<Sample> {
+    const KIND: &'static str = "stress_samples";
+
+    fn dimensions() -> Vec<(&'static str, Dimension)> {
+        vec![
+            ("x", LENGTH),
+            ("y", LENGTH),
+            ("normal", STRESS),
+            ("shear", STRESS),
+            ("von_mises", STRESS),
+        ]
+    }
+}
+
+impl Quantities for Analysis {
+    const KIND: &'static str = "beam_analysis";
+
+    fn dimensions() -> Vec<(&'static str, Dimension)> {
+        vec![
+            ("length", LENGTH),
+            ("x", LENGTH),
+            ("shear", FORCE),
+            ("moment", MOMENT),
+            ("slope", Dimension::Angle),
+            ("deflection", LENGTH),
+            ("position", LENGTH),
+            ("force", FORCE),
+        ]
+    }
+}
+
+impl Quantities for LateralTorsional {
+    const KIND: &'static str = "lateral_torsional_buckling";
+
+    fn dimensions() -> Vec<(&'static str, Dimension)> {
+        vec![
+            ("length", LENGTH),
+            ("c1", RATIO),
+            ("c2", RATIO),
+            ("load_height", LENGTH),
+            ("mcr", MOMENT),
+        ]
+    }
+}
+
+impl Quantities for Effective {
+    const KIND: &'static str = "effective_section";
+
+    fn dimensions() -> Vec<(&'static str, Dimension)> {
+        let mut dimensions = vec![
+            ("width", LENGTH),
+            ("thickness", LENGTH),
+            ("psi", RATIO),
+            ("buckling_factor", RATIO),
+            ("critical", STRESS),
+            ("slenderness", RATIO),
+            ("reduction", RATIO),
+            ("effective_width", LENGTH),
+            ("start", LENGTH),
+            ("end", LENGTH),
+        ];
+        dimensions.extend(Properties::dimensions());
+        dimensions
+    }
+}
+
+impl Quantities for aisc::Report {
+    const KIND: &'static str = "aisc_360_check";
+
+    fn dimensions() -> Vec<(&'static str, Dimension)> {
+        vec![
+            ("width", LENGTH),
+            ("thickness", LENGTH),
+            ("slenderness", RATIO),
+            ("flexure_limits", RATIO),
+            ("compression_limit", RATIO),
+            ("ratio", RATIO),
+        ]
+    }
+}
+
+impl Quantities for eurocode::Report {
+    const KIND: &'static str = "en_1993_check";
+
+    fn dimensions() -> Vec<(&'static str, Dimension)> {
+        vec![
+            ("epsilon", RATIO),
+            ("slenderness", RATIO),
+            ("alpha", RATIO),
+            ("psi", RATIO),
+            ("limits", RATIO),
+            ("ratio", RATIO),
+        ]
+    }
+}
+
+impl Quantities for Transformed {
+    const KIND: &'static str = "transformed_section";
+
+    fn dimensions() -> Vec<(&'static str, Dimension)> {
+        vec![
+            ("area", AREA),
+            ("centroid", LENGTH),
+            ("inertia", INERTIA),
+            ("depth", LENGTH),
+        ]
+    }
+}
+
+impl Quantities for Ultimate {
+    const KIND: &'static str = "flexural_strength";
+
+    fn dimensions() -> Vec<(&'static str, Dimension)> {
+        vec![
+            ("axial", FORCE),
+            ("moment", MOMENT),
+            ("depth", LENGTH),
+            ("block", LENGTH),
+            ("tension_strain", RATIO),
+            ("phi", RATIO),
+        ]
+    }
+}
+
+impl Quantities for Plastic {
+    const KIND: &'static str = "composite_plastic_moment";
+
+    fn dimensions() -> Vec<(&'static str, Dimension)> {
+        vec![
+            ("moment", MOMENT),
+            ("concrete_force", FORCE),
+            ("block", LENGTH),
+            ("axis", LENGTH),
+            ("connection", RATIO),
+        ]
+    }
+}
+
+impl Quantities for MomentCurvature {
+    const KIND: &'static str = "moment_curvature";
+
+    fn dimensions() -> Vec<(&'static str, Dimension)> {
+        vec![
+            ("curvature", CURVATURE),
+            ("moment", MOMENT),
+            ("strain", RATIO),
+            ("depth", LENGTH),
+        ]
+    }
+}
+
+impl Quantities for Surface {
+    const KIND: &'static str = "interaction_surface";
+
+    fn dimensions() -> Vec<(&'static str, Dimension)> {
+        vec![
+            ("axial", FORCE),
+            ("mx", MOMENT),
+            ("my", MOMENT),
+            ("tension", FORCE),
+            ("compression", FORCE),
+        ]
+    }
+}
+
+impl Quantities for Warping {
+    const KIND: &'static str = "warping_properties";
+
+    fn dimensions() -> Vec<(&'static str, Dimension)> {
+        vec![
+            ("area", AREA),
+            ("centroid", LENGTH),
+            ("ixx", INERTIA),
+            ("iyy", INERTIA),
+            ("ixy", INERTIA),
+            ("torsion", INERTIA),
+            ("warping", WARPING),
+            ("shear_centre", LENGTH),
+            ("shear_area", AREA),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beam::{Beam, Support};
+    use crate::build::Section;
+    use crate::load::{Load, PointLoad};
+    use crate::stress::LoadState;
+    use serde_json::Value;
+
+    /// An unequal angle with legs 6 and 4 long and 0.5 thick
+    fn angle() -> Section {
+        let mut section = Section::new(0.3);
+        let n: Vec<_> = [(0., 6.), (0., 0.), (4., 0.)]
+            .iter()
+            .map(|&(x, y)| section.add_node(x, y))
+            .collect();
+        section.add_edge(n[0], n[1], Some(0.5), Some(29000.));
+        section.add_edge(n[1], n[2], Some(0.5), Some(29000.));
+        section
+    }
+
+    /// Whether a field of a name appears anywhere in a JSON value
+    fn has_field(value: &Value, name: &str) -> bool {
+        match value {
+            Value::Object(map) => map
+                .iter()
+                .any(|(key, value)| key == name || has_field(value, name)),
+            Value::Array(values) => values.iter().any(|value| has_field(value, name)),
+            _ => false,
+        }
+    }
+
+    /// This function checks the envelope of a result and that every field given units is in
+    /// its data
+    fn envelope<T: Quantities>(result: &T, units: &Units) -> Value {
+        let json: Value = serde_json::from_str(&to_json(result, units)).unwrap();
+        assert_eq!(json["kind"], T::KIND);
+        assert_eq!(json["version"], FORMAT_VERSION);
+        assert_eq!(json["units"]["force"], units.force.as_str());
+        assert_eq!(json["units"]["length"], units.length.as_str());
+        let quantities = json["quantities"].as_object().unwrap();
+        assert_eq!(quantities.len(), T::dimensions().len());
+        for (field, dimension) in T::dimensions() {
+            assert_eq!(quantities[field], units.unit(dimension));
+            assert!(has_field(&json["data"], field), "no field {}", field);
+        }
+        json
+    }
+
+    #[test]
+    fn unit_names() {
+        let units = Units::kip_inch();
+        assert_eq!(units.unit(MOMENT), "kip*in");
+        assert_eq!(units.unit(STRESS), "kip/in^2");
+        assert_eq!(units.unit(WARPING), "in^6");
+        assert_eq!(units.unit(CURVATURE), "1/in");
+        assert_eq!(units.unit(RATIO), "1");
+        assert_eq!(units.unit(FORCE), "kip");
+        assert_eq!(units.unit(Dimension::Angle), "rad");
+        assert_eq!(Units::newton_millimetre().unit(STRESS), "N/mm^2");
+        assert_eq!(Units::kilonewton_metre().unit(MOMENT), "kN*m");
+        assert_eq!(Units::default().unit(AREA), "length^2");
+        assert_eq!(
+            Units::new("lbf", "ft").unit(Dimension::Power(-1, 2)),
+            "ft^2/lbf"
+        );
+    }
+
+    #[test]
+    fn section_properties() {
+        let props = angle().properties();
+        let json = envelope(&props, &Units::kip_inch());
+        assert_eq!(json["kind"], "section_properties");
+        assert_eq!(json["quantities"]["ixx"], "in^4");
+        assert_eq!(json["quantities"]["elastic"], "kip/in^2");
+        assert_eq!(json["quantities"]["phi"], "rad");
+        assert_eq!(json["data"]["area"], props.area);
+        assert_eq!(json["data"]["centroid"][1], props.centroid.1);
+    }
+
+    #[test]
+    fn stress_samples_and_beam_analysis() {
+        let samples = angle()
+            .stresses(LoadState {
+                moment_x: 10.,
+                ..LoadState::default()
+            })
+            .samples(2);
+        let json = envelope(&samples, &Units::newton_millimetre());
+        assert_eq!(json["quantities"]["von_mises"], "N/mm^2");
+        assert_eq!(json["data"].as_array().unwrap().len(), samples.len());
+
+        let mut beam = Beam::new(4., 5e3);
+        beam.add_support(0., Support::Fixed);
+        beam.add_load(Load::Point(PointLoad {
+            position: 4.,
+            mag: 2.,
+        }));
+        let analysis = beam.analyze(4).unwrap();
+        let json = envelope(&analysis, &Units::kilonewton_metre());
+        assert_eq!(json["kind"], "beam_analysis");
+        assert_eq!(json["quantities"]["moment"], "kN*m");
+        assert_eq!(json["quantities"]["slope"], "rad");
+        let reaction = json["data"]["reactions"][0]["force"].as_f64().unwrap();
+        assert!((reaction - 2.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn written_to_file() {
+        let props = angle().properties();
+        let path = std::env::temp_dir().join(format!(
+            "bender_rust_results_{}_props.json",
+            std::process::id()
+        ));
+        write_json(&props, &Units::kip_inch(), &path).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(text, to_json(&props, &Units::kip_inch()));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,336 @@
+// Elastic stresses over a thin-walled section from a load state
+use crate::build::Section;
+use crate::properties::{Properties, Segment};
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Stress resultants acting on the section. Compression is positive, the moment about x
+/// is positive when it compresses fibres at positive y and the moment about y when it
+/// compresses fibres at positive x. Shears act through the shear centre.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LoadState {
+    pub axial: f64,
+    pub moment_x: f64,
+    pub moment_y: f64,
+    pub shear_x: f64,
+    pub shear_y: f64,
+}
+
+/// The stress to report
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Component {
+    /// Normal stress, tension positive
+    Normal,
+    /// Magnitude of the shear stress from the shear flow
+    Shear,
+    VonMises,
+}
+
+/// Stresses at a point on the centreline of a plate
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Sample {
+    pub x: f64,
+    pub y: f64,
+    /// Normal stress, tension positive
+    pub normal: f64,
+    /// Magnitude of the shear stress
+    pub shear: f64,
+    pub von_mises: f64,
+}
+
+/// A plate oriented along the direction of its shear flow, with the flow entering it
+#[derive(Clone, Copy, Debug)]
+struct Flow {
+    segment: Segment,
+    start: f64,
+}
+
+/// The elastic stress field of a section under a load state
+#[derive(Clone, Debug)]
+pub struct StressField {
+    pub props: Properties,
+    /// Normal stress σ = axial + gradient · (p - centroid)
+    pub axial: f64,
+    pub gradient: (f64, f64),
+    /// Coefficients of the first moments of area about x and y in the shear flow
+    shear: (f64, f64),
+    flows: Vec<Flow>,
+}
+
+impl Section {
+    /// This function computes the normal stress from axial force and biaxial bending and
+    /// the shear stress from the open-section shear flow. Cells are cut open, so closing
+    /// plates carry no circulating flow.
+    pub fn stresses(&self, load: LoadState) -> StressField {
+        let props = self.properties();
+        let det = props.ixy * props.ixy - props.ixx * props.iyy;
+        let gradient = (
+            (props.ixx * load.moment_y - props.ixy * load.moment_x) / det,
+            (props.iyy * load.moment_x - props.ixy * load.moment_y) / det,
+        );
+        let shear = (
+            (load.shear_y * props.iyy - load.shear_x * props.ixy) / det,
+            (load.shear_x * props.ixx - load.shear_y * props.ixy) / det,
+        );
+
+        let mut field = StressField {
+            axial: -load.axial / props.area,
+            gradient,
+            shear,
+            flows: vec![],
+            props,
+        };
+
+        // order the nodes so that every plate is visited from its far end towards the root
+        let mut parents: HashMap<NodeIndex, Option<EdgeIndex>> = HashMap::new();
+        let mut order = vec![];
+        for root in self.graph.node_indices() {
+            if parents.contains_key(&root) {
+                continue;
+            }
+            parents.insert(root, None);
+            let mut stack = vec![root];
+            while let Some(node) = stack.pop() {
+                order.push(node);
+                for edge in self.graph.edges(node) {
+                    let neigh = if edge.source() == node {
+                        edge.target()
+                    } else {
+                        edge.source()
+                    };
+                    if parents.contains_key(&neigh) {
+                        continue;
+                    }
+                    parents.insert(neigh, Some(edge.id()));
+                    stack.push(neigh);
+                }
+            }
+        }
+
+        let mut inflow: HashMap<NodeIndex, f64> = HashMap::new();
+        for node in order.into_iter().rev() {
+            let edge = match parents[&node] {
+                Some(edge) => edge,
+                None => continue,
+            };
+            let (a, b) = self.graph.edge_endpoints(edge).unwrap();
+            let parent = if a == node { b } else { a };
+            let flow = Flow {
+                segment: Segment {
+                    start: (self.graph[node].x, self.graph[node].y),
+                    end: (self.graph[parent].x, self.graph[parent].y),
+                    thickness: self.graph[edge].thickness,
+                    elastic: self.graph[edge].elastic,
+                },
+                start: inflow.get(&node).copied().unwrap_or(0.),
+            };
+            let end = field.flow(&flow, flow.segment.length());
+            *inflow.entry(parent).or_insert(0.) += end;
+            field.flows.push(flow);
+        }
+        // plates closing cells, cut open at their start
+        let tree: Vec<EdgeIndex> = parents.values().filter_map(|e| *e).collect();
+        for edge in self.graph.edge_indices().filter(|e| !tree.contains(e)) {
+            let (a, b) = self.graph.edge_endpoints(edge).unwrap();
+            field.flows.push(Flow {
+                segment: Segment {
+                    start: (self.graph[a].x, self.graph[a].y),
+                    end: (self.graph[b].x, self.graph[b].y),
+                    thickness: self.graph[edge].thickness,
+                    elastic: self.graph[edge].elastic,
+                },
+                start: 0.,
+            });
+        }
+        field
+    }
+}
+
+impl StressField {
+    /// Shear flow at a distance `s` along an oriented plate
+    fn flow(&self, flow: &Flow, s: f64) -> f64 {
+        let seg = &flow.segment;
+        let length = seg.length();
+        let c = self.props.centroid;
+        let (x0, y0) = (seg.start.0 - c.0, seg.start.1 - c.1);
+        let (x1, y1) = (seg.end.0 - c.0, seg.end.1 - c.1);
+        let integral =
+            |f0: f64, f1: f64| seg.thickness * (f0 * s + (f1 - f0) * s * s / (2. * length));
+        flow.start - self.shear.0 * integral(y0, y1) - self.shear.1 * integral(x0, x1)
+    }
+
+    /// Normal stress at a point, tension positive
+    pub fn normal(&self, x: f64, y: f64) -> f64 {
+        let c = self.props.centroid;
+        self.axial + self.gradient.0 * (x - c.0) + self.gradient.1 * (y - c.1)
+    }
+
+    /// Shear stress at a point, taken from the closest plate
+    pub fn shear(&self, x: f64, y: f64) -> f64 {
+        let closest = self
+            .flows
+            .iter()
+            .map(|flow| {
+                let seg = &flow.segment;
+                let (dx, dy) = (seg.end.0 - seg.start.0, seg.end.1 - seg.start.1);
+                let length = seg.length();
+                let s =
+                    (((x - seg.start.0) * dx + (y - seg.start.1) * dy) / length).clamp(0., length);
+                let (px, py) = (seg.start.0 + dx * s / length, seg.start.1 + dy * s / length);
+                ((x - px).hypot(y - py), flow, s)
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        match closest {
+            Some((_, flow, s)) => (self.flow(flow, s) / flow.segment.thickness).abs(),
+            None => 0.,
+        }
+    }
+
+    /// This function samples the stresses at `divisions + 1` evenly spaced points along every
+    /// plate, with the shear stress taken from the plate being sampled
+    pub fn samples(&self, divisions: usize) -> Vec<Sample> {
+        let divisions = divisions.max(1);
+        let mut samples = vec![];
+        for flow in &self.flows {
+            let seg = &flow.segment;
+            let length = seg.length();
+            for i in 0..=divisions {
+                let s = length * i as f64 / divisions as f64;
+                let x = seg.start.0 + (seg.end.0 - seg.start.0) * s / length;
+                let y = seg.start.1 + (seg.end.1 - seg.start.1) * s / length;
+                let normal = self.normal(x, y);
+                let shear = (self.flow(flow, s) / seg.thickness).abs();
+                samples.push(Sample {
+                    x,
+                    y,
+                    normal,
+                    shear,
+                    von_mises: (normal * normal + 3. * shear * shear).sqrt(),
+                });
+            }
+        }
+        samples
+    }
+
+    pub fn von_mises(&self, x: f64, y: f64) -> f64 {
+        (self.normal(x, y).powi(2) + 3. * self.shear(x, y).powi(2)).sqrt()
+    }
+
+    pub fn value(&self, component: Component, x: f64, y: f64) -> f64 {
+        match component {
+            Component::Normal => self.normal(x, y),
+            Component::Shear => self.shear(x, y),
+            Component::VonMises => self.von_mises(x, y),
+        }
+    }
+
+    /// A point on the neutral axis and its direction, or None without bending
+    pub fn neutral_axis(&self) -> Option<((f64, f64), (f64, f64))> {
+        let (gx, gy) = self.gradient;
+        let norm = gx.hypot(gy);
+        if norm == 0. {
+            return None;
+        }
+        let c = self.props.centroid;
+        // the point on the axis closest to the centroid
+        let shift = -self.axial / norm;
+        Some((
+            (c.0 + shift * gx / norm, c.1 + shift * gy / norm),
+            (-gy / norm, gx / norm),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An I-section with flanges 8 wide, 12 apart and plates 1 thick
+    fn i_section() -> Section {
+        let mut section = Section::new(0.3);
+        let n: Vec<_> = [
+            (-4., 6.),
+            (0., 6.),
+            (4., 6.),
+            (-4., -6.),
+            (0., -6.),
+            (4., -6.),
+        ]
+        .iter()
+        .map(|&(x, y)| section.add_node(x, y))
+        .collect();
+        for &(a, b) in &[(0, 1), (1, 2), (3, 4), (4, 5), (1, 4)] {
+            section.add_edge(n[a], n[b], Some(1.), Some(200.));
+        }
+        section
+    }
+
+    #[test]
+    fn bending_stress_at_the_extreme_fibres() {
+        // σ = M y / I, compressing the fibres on the side the moment acts towards
+        let section = i_section();
+        let props = section.properties();
+        let field = section.stresses(LoadState {
+            moment_x: 300.,
+            ..LoadState::default()
+        });
+        assert!((field.normal(0., 6.) + 300. * 6. / props.ixx).abs() < 1e-12);
+        assert!((field.normal(-4., -6.) - 300. * 6. / props.ixx).abs() < 1e-12);
+        assert!(field.normal(2., 0.).abs() < 1e-12);
+        let (point, direction) = field.neutral_axis().unwrap();
+        assert!(point.0.abs() < 1e-12 && point.1.abs() < 1e-12);
+        assert!(direction.1.abs() < 1e-12);
+
+        let field = section.stresses(LoadState {
+            moment_y: 50.,
+            ..LoadState::default()
+        });
+        assert!((field.normal(4., 6.) + 50. * 4. / props.iyy).abs() < 1e-12);
+        assert!((field.normal(-4., 6.) - 50. * 4. / props.iyy).abs() < 1e-12);
+    }
+
+    #[test]
+    fn axial_force_moves_the_neutral_axis() {
+        let section = i_section();
+        let props = section.properties();
+        let field = section.stresses(LoadState {
+            axial: 28.,
+            ..LoadState::default()
+        });
+        assert!((field.normal(4., -6.) + 28. / props.area).abs() < 1e-12);
+        assert!(field.neutral_axis().is_none());
+
+        let field = section.stresses(LoadState {
+            axial: 28.,
+            moment_x: 300.,
+            ..LoadState::default()
+        });
+        let (point, _) = field.neutral_axis().unwrap();
+        assert!(field.normal(point.0, point.1).abs() < 1e-12);
+        assert!(point.1 < 0.);
+    }
+
+    #[test]
+    fn shear_stress_from_the_shear_flow() {
+        // τ = V Q / I t, with Q of a flange and half the web at the centroid
+        let section = i_section();
+        let props = section.properties();
+        let field = section.stresses(LoadState {
+            shear_y: 10.,
+            ..LoadState::default()
+        });
+        let q = 8. * 6. + 6. * 6. / 2.;
+        let tau = 10. * q / props.ixx;
+        assert!((field.shear(0., 0.) - tau).abs() < 1e-9);
+        assert!(field.shear(4., 6.).abs() < 1e-9);
+        assert!(field.normal(0., 0.).abs() < 1e-12);
+        assert!((field.von_mises(0., 0.) - 3f64.sqrt() * tau).abs() < 1e-9);
+
+        let samples = field.samples(4);
+        assert_eq!(samples.len(), 5 * 5);
+        let largest = samples.iter().map(|s| s.shear).fold(0., f64::max);
+        assert!((largest - tau).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,385 @@
+// Checks of the graph of a section, made before building it
+use crate::build::{in_line, intersects, point2segment, Crossing, Section};
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter, Result};
+
+/// A problem with the graph of a section, giving the nodes and elements to highlight
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Diagnostic {
+    /// Nodes connected to each other but not to the largest part of the section
+    Disconnected { nodes: Vec<NodeIndex> },
+    /// An element whose ends coincide
+    ZeroLength { edge: EdgeIndex },
+    /// Elements joining the same pair of nodes
+    Duplicate { edges: (EdgeIndex, EdgeIndex) },
+    /// Elements crossing or lying along each other away from a shared node
+    Overlapping { edges: (EdgeIndex, EdgeIndex) },
+    /// A node closer to an element it is not part of than `near` allows
+    Near {
+        node: NodeIndex,
+        edge: EdgeIndex,
+        distance: f64,
+    },
+    /// Elements lying in line at a node with different thicknesses, which the builder joins
+    /// with a square step
+    Thickness {
+        node: NodeIndex,
+        edges: (EdgeIndex, EdgeIndex),
+    },
+    /// Plates the clipper failed to merge into an outline, which `validate` never reports
+    Unmerged,
+}
+
+impl Diagnostic {
+    /// Whether or not the problem spoils the built outline. The others are built as drawn
+    /// but may not be what was meant.
+    pub fn spoils(&self) -> bool {
+        matches!(
+            self,
+            Diagnostic::ZeroLength { .. }
+                | Diagnostic::Duplicate { .. }
+                | Diagnostic::Overlapping { .. }
+                | Diagnostic::Unmerged
+        )
+    }
+
+    /// The nodes involved
+    pub fn nodes(&self) -> Vec<NodeIndex> {
+        match self {
+            Diagnostic::Disconnected { nodes } => nodes.clone(),
+            Diagnostic::Near { node, .. } | Diagnostic::Thickness { node, .. } => vec![*node],
+            _ => vec![],
+        }
+    }
+
+    /// The elements involved
+    pub fn edges(&self) -> Vec<EdgeIndex> {
+        match self {
+            Diagnostic::ZeroLength { edge } | Diagnostic::Near { edge, .. } => vec![*edge],
+            Diagnostic::Duplicate { edges }
+            | Diagnostic::Overlapping { edges }
+            | Diagnostic::Thickness { edges, .. } => vec![edges.0, edges.1],
+            Diagnostic::Disconnected { .. } | Diagnostic::Unmerged => vec![],
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let list = |nodes: &[NodeIndex]| {
+            nodes
+                .iter()
+                .map(|node| node.index().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self {
+            Diagnostic::Disconnected { nodes } => {
+                write!(f, "nodes {} are disconnected", list(nodes))
+            }
+            Diagnostic::ZeroLength { edge } => write!(f, "edge {} has zero length", edge.index()),
+            Diagnostic::Duplicate { edges } => write!(
+                f,
+                "edges {} and {} join the same nodes",
+                edges.0.index(),
+                edges.1.index()
+            ),
+            Diagnostic::Overlapping { edges } => write!(
+                f,
+                "edges {} and {} overlap",
+                edges.0.index(),
+                edges.1.index()
+            ),
+            Diagnostic::Near {
+                node,
+                edge,
+                distance,
+            } => write!(
+                f,
+                "node {} is {:.4} from edge {}",
+                node.index(),
+                distance,
+                edge.index()
+            ),
+            Diagnostic::Thickness { node, edges } => write!(
+                f,
+                "edges {} and {} meet in line at node {} with different thicknesses",
+                edges.0.index(),
+                edges.1.index(),
+                node.index()
+            ),
+            Diagnostic::Unmerged => write!(f, "the plates could not be merged into an outline"),
+        }
+    }
+}
+
+impl Section {
+    /// This function checks the graph for problems that would spoil or stop the build, and
+    /// lists them rather than panicking
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.disconnected();
+        let point = |node: NodeIndex| (self.graph[node].x, self.graph[node].y);
+        let (lower, upper) = self.graph.node_indices().map(point).fold(
+            (
+                (f64::INFINITY, f64::INFINITY),
+                (f64::NEG_INFINITY, f64::NEG_INFINITY),
+            ),
+            |(lower, upper), p| {
+                (
+                    (lower.0.min(p.0), lower.1.min(p.1)),
+                    (upper.0.max(p.0), upper.1.max(p.1)),
+                )
+            },
+        );
+        let span = (upper.0 - lower.0).max(upper.1 - lower.1).max(0.);
+        let tolerance = 1e-9 * span;
+
+        let mut ends: HashMap<(NodeIndex, NodeIndex), EdgeIndex> = HashMap::new();
+        let mut overlaps = HashSet::new();
+        for edge in self.graph.edge_indices() {
+            let (a, b) = self.graph.edge_endpoints(edge).unwrap();
+            let (p, q) = (point(a), point(b));
+            if a == b || (q.0 - p.0).hypot(q.1 - p.1) <= tolerance {
+                diagnostics.push(Diagnostic::ZeroLength { edge });
+                continue;
+            }
+            match ends.get(&(a.min(b), a.max(b))) {
+                Some(&first) => diagnostics.push(Diagnostic::Duplicate {
+                    edges: (first, edge),
+                }),
+                None => {
+                    ends.insert((a.min(b), a.max(b)), edge);
+                }
+            }
+            // crossings at nodes of other elements are found again from those elements
+            for crossing in intersects(self, a, b) {
+                if let Crossing::Point(other, _) | Crossing::End(other, _) = crossing {
+                    overlaps.insert((edge.min(other), edge.max(other)));
+                }
+            }
+        }
+        let mut overlaps: Vec<_> = overlaps.into_iter().collect();
+        overlaps.sort();
+        diagnostics.extend(
+            overlaps
+                .into_iter()
+                .map(|edges| Diagnostic::Overlapping { edges }),
+        );
+
+        for node in self.graph.node_indices() {
+            let p = point(node);
+            for edge in self.edges_within(p, p) {
+                let (a, b) = self.graph.edge_endpoints(edge).unwrap();
+                if a == node || b == node {
+                    continue;
+                }
+                let distance = point2segment(point(a), point(b), p);
+                if distance < 2. * self.graph[edge].thickness {
+                    diagnostics.push(Diagnostic::Near {
+                        node,
+                        edge,
+                        distance,
+                    });
+                }
+            }
+        }
+
+        diagnostics.extend(self.mismatched());
+        diagnostics
+    }
+
+    /// The parts of the section other than the one with the most nodes
+    fn disconnected(&self) -> Vec<Diagnostic> {
+        let mut part = vec![usize::MAX; self.graph.node_count()];
+        let mut parts: Vec<Vec<NodeIndex>> = vec![];
+        for start in self.graph.node_indices() {
+            if part[start.index()] != usize::MAX {
+                continue;
+            }
+            let mut nodes = vec![start];
+            part[start.index()] = parts.len();
+            let mut next = 0;
+            while next < nodes.len() {
+                for neighbour in self.graph.neighbors(nodes[next]) {
+                    if part[neighbour.index()] == usize::MAX {
+                        part[neighbour.index()] = parts.len();
+                        nodes.push(neighbour);
+                    }
+                }
+                next += 1;
+            }
+            nodes.sort();
+            parts.push(nodes);
+        }
+        let largest = (0..parts.len()).max_by_key(|&i| (parts[i].len(), std::cmp::Reverse(i)));
+        parts
+            .into_iter()
+            .enumerate()
+            .filter(|&(i, _)| Some(i) != largest)
+            .map(|(_, nodes)| Diagnostic::Disconnected { nodes })
+            .collect()
+    }
+
+    /// The elements lying in line at each node with different thicknesses, which the builder
+    /// joins with a step, found from the elements around the node in the same order as the
+    /// builder
+    fn mismatched(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+        for node in self.graph.node_indices() {
+            let here = &self.graph[node];
+            let mut around: Vec<(f64, EdgeIndex)> = self
+                .graph
+                .edges(node)
+                .filter_map(|edge| {
+                    let (a, b) = (edge.source(), edge.target());
+                    let there = &self.graph[if a == node { b } else { a }];
+                    if (there.x, there.y) == (here.x, here.y) {
+                        return None;
+                    }
+                    Some(((there.y - here.y).atan2(there.x - here.x), edge.id()))
+                })
+                .collect();
+            around.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+            around.dedup_by(|a, b| a.0 == b.0);
+            if around.len() < 2 {
+                continue;
+            }
+            for i in 0..around.len() {
+                let (a, b) = (around[i], around[(i + 1) % around.len()]);
+                if in_line(a.0, b.0) && self.graph[a.1].thickness != self.graph[b.1].thickness {
+                    let diagnostic = Diagnostic::Thickness {
+                        node,
+                        edges: (a.1.min(b.1), a.1.max(b.1)),
+                    };
+                    if !diagnostics.contains(&diagnostic) {
+                        diagnostics.push(diagnostic);
+                    }
+                }
+            }
+        }
+        diagnostics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::{Element, Node};
+    use crate::clipping::Clipper;
+    use geo::prelude::Area;
+
+    fn plates(nodes: &[(f64, f64)], plates: &[(usize, usize, f64)]) -> Section {
+        let mut section = Section::new(0.3);
+        let n: Vec<_> = nodes.iter().map(|&(x, y)| section.add_node(x, y)).collect();
+        for &(a, b, t) in plates {
+            section.add_edge(n[a], n[b], Some(t), None);
+        }
+        section
+    }
+
+    #[test]
+    fn lone_node_is_reported_and_built_around() {
+        let mut section = plates(&[(0., 0.), (10., 0.)], &[(0, 1, 2.)]);
+        section.add_node(50., 50.);
+        assert_eq!(
+            section.validate(),
+            vec![Diagnostic::Disconnected {
+                nodes: vec![NodeIndex::new(2)]
+            }]
+        );
+        let outline = section.try_build().unwrap();
+        assert_eq!(outline.0.len(), 1);
+        assert!((outline.area().abs() - 24.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn plates_in_line_step_in_thickness() {
+        let section = plates(&[(0., 0.), (10., 0.), (20., 0.)], &[(0, 1, 1.), (1, 2, 2.)]);
+        assert_eq!(
+            section.validate(),
+            vec![Diagnostic::Thickness {
+                node: NodeIndex::new(1),
+                edges: (EdgeIndex::new(0), EdgeIndex::new(1)),
+            }]
+        );
+        // the free ends are capped by half a thickness
+        for &clipper in &[Clipper::BooleanOp, Clipper::Overlay] {
+            let area = section.build_with(clipper).area().abs();
+            assert!((area - (10.5 * 1. + 11. * 2.)).abs() < 1e-9, "{}", area);
+        }
+    }
+
+    #[test]
+    fn square_plates_of_different_thickness_are_joined() {
+        // flanges 10 by 1 and a web 0.5 thick, 20 deep between the flange centrelines
+        let section = plates(
+            &[
+                (-5., 10.),
+                (0., 10.),
+                (5., 10.),
+                (-5., -10.),
+                (0., -10.),
+                (5., -10.),
+            ],
+            &[(0, 1, 1.), (1, 2, 1.), (3, 4, 1.), (4, 5, 1.), (1, 4, 0.5)],
+        );
+        assert!(section.validate().is_empty());
+        for &clipper in &[Clipper::BooleanOp, Clipper::Overlay] {
+            let area = section.build_with(clipper).area().abs();
+            assert!((area - (2. * 11. + 19. * 0.5)).abs() < 1e-9, "{}", area);
+        }
+    }
+
+    #[test]
+    fn zero_length_spoils_the_build() {
+        let mut section = plates(&[(0., 0.), (10., 0.)], &[(0, 1, 2.)]);
+        let end = NodeIndex::new(1);
+        let copy = section.graph.add_node(Node {
+            x: 10.,
+            y: 0.,
+            label: None,
+        });
+        section.graph.add_edge(
+            end,
+            copy,
+            Element {
+                thickness: 2.,
+                elastic: 29000.,
+                label: None,
+            },
+        );
+        section.reindex();
+        let diagnostics = section.try_build().unwrap_err();
+        assert!(diagnostics.contains(&Diagnostic::ZeroLength {
+            edge: EdgeIndex::new(1)
+        }));
+        // the build itself leaves the element out
+        assert!((section.build().area().abs() - 24.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn chain_geo_booleanop_cannot_merge_is_built() {
+        let nodes = [
+            (19.916, 5.2332),
+            (1.7184, -2.5336),
+            (-15.632, -1.0808),
+            (-3.9732, -16.9844),
+        ];
+        let section = plates(&nodes, &[(3, 1, 0.1), (3, 2, 0.1), (0, 2, 0.1)]);
+        assert!(section.validate().is_empty());
+        let outline = section.try_build().unwrap();
+        let length: f64 = [(3, 1), (3, 2), (0, 2)]
+            .iter()
+            .map(|&(a, b): &(usize, usize)| {
+                let ((x1, y1), (x2, y2)) = (nodes[a], nodes[b]);
+                ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt()
+            })
+            .sum();
+        assert_eq!(outline.0.len(), 1);
+        assert!((outline.area().abs() - 0.1 * length).abs() < 0.05);
+    }
+}
@@ -0,0 +1,439 @@
+// Torsion and shear properties of solid sections by finite element analysis of the warping
+use crate::build::Section;
+use crate::mesh::Mesh;
+use geo::MultiPolygon;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Six point Gauss rule for triangles, as area coordinates and weights summing to one
+const GAUSS: [(f64, f64, f64, f64); 6] = [
+    (
+        0.816_847_572_980_459,
+        0.091_576_213_509_771,
+        0.091_576_213_509_771,
+        0.109_951_743_655_322,
+    ),
+    (
+        0.091_576_213_509_771,
+        0.816_847_572_980_459,
+        0.091_576_213_509_771,
+        0.109_951_743_655_322,
+    ),
+    (
+        0.091_576_213_509_771,
+        0.091_576_213_509_771,
+        0.816_847_572_980_459,
+        0.109_951_743_655_322,
+    ),
+    (
+        0.108_103_018_168_070,
+        0.445_948_490_915_965,
+        0.445_948_490_915_965,
+        0.223_381_589_678_011,
+    ),
+    (
+        0.445_948_490_915_965,
+        0.108_103_018_168_070,
+        0.445_948_490_915_965,
+        0.223_381_589_678_011,
+    ),
+    (
+        0.445_948_490_915_965,
+        0.445_948_490_915_965,
+        0.108_103_018_168_070,
+        0.223_381_589_678_011,
+    ),
+];
+
+/// Properties of a solid section found from the warping and shear functions, which are solved
+/// for over a mesh of six node triangles. The inertias are about the centroid and the shear
+/// areas are for shear along the x and y axes.
+#[derive(Clone, Debug, Serialize)]
+pub struct Warping {
+    pub area: f64,
+    pub centroid: (f64, f64),
+    pub ixx: f64,
+    pub iyy: f64,
+    pub ixy: f64,
+    pub torsion: f64,
+    pub warping: f64,
+    pub shear_centre: (f64, f64),
+    pub shear_area: (f64, f64),
+    pub nodes: usize,
+    pub elements: usize,
+}
+
+/// A quadrature point of an element, with the values and gradients of the shape functions
+struct Sample {
+    weight: f64,
+    x: f64,
+    y: f64,
+    n: [f64; 6],
+    dx: [f64; 6],
+    dy: [f64; 6],
+}
+
+/// A symmetric sparse matrix, stored by rows
+struct Sparse {
+    rows: Vec<Vec<(usize, f64)>>,
+}
+
+impl Sparse {
+    fn new(size: usize) -> Sparse {
+        Sparse {
+            rows: vec![vec![]; size],
+        }
+    }
+
+    fn add(&mut self, r: usize, c: usize, value: f64) {
+        match self.rows[r].iter_mut().find(|(k, _)| *k == c) {
+            Some(entry) => entry.1 += value,
+            None => self.rows[r].push((c, value)),
+        }
+    }
+
+    fn multiply(&self, x: &[f64]) -> Vec<f64> {
+        self.rows
+            .iter()
+            .map(|row| row.iter().map(|&(c, value)| value * x[c]).sum())
+            .collect()
+    }
+
+    fn diagonal(&self) -> Vec<f64> {
+        self.rows
+            .iter()
+            .enumerate()
+            .map(|(r, row)| row.iter().find(|(c, _)| *c == r).map_or(1., |e| e.1))
+            .collect()
+    }
+
+    /// This function solves the system by conjugate gradients with a diagonal preconditioner
+    fn solve(&self, b: &[f64]) -> Vec<f64> {
+        let dot = |a: &[f64], b: &[f64]| a.iter().zip(b).map(|(a, b)| a * b).sum::<f64>();
+        let diagonal = self.diagonal();
+        let mut x = vec![0.; b.len()];
+        let mut r = b.to_vec();
+        let mut z: Vec<f64> = r.iter().zip(&diagonal).map(|(r, d)| r / d).collect();
+        let mut p = z.clone();
+        let mut rz = dot(&r, &z);
+        let limit = 1e-24 * dot(b, b);
+        for _ in 0..10 * b.len() {
+            if dot(&r, &r) <= limit {
+                break;
+            }
+            let q = self.multiply(&p);
+            let alpha = rz / dot(&p, &q);
+            for i in 0..x.len() {
+                x[i] += alpha * p[i];
+                r[i] -= alpha * q[i];
+            }
+            z = r.iter().zip(&diagonal).map(|(r, d)| r / d).collect();
+            let next = dot(&r, &z);
+            for i in 0..p.len() {
+                p[i] = z[i] + next / rz * p[i];
+            }
+            rz = next;
+        }
+        x
+    }
+}
+
+impl Warping {
+    /// This function finds the properties of an outline meshed with triangles of about the
+    /// given size
+    pub fn solve(outline: &MultiPolygon<f64>, size: f64, poisson: f64) -> Warping {
+        Warping::from_mesh(&Mesh::generate(outline, size), poisson)
+    }
+
+    pub fn from_mesh(mesh: &Mesh, poisson: f64) -> Warping {
+        let (mut points, elements) = quadratic(mesh);
+
+        let (mut area, mut qx, mut qy) = (0., 0., 0.);
+        for element in &elements {
+            for s in samples(&points, element) {
+                area += s.weight;
+                qx += s.weight * s.y;
+                qy += s.weight * s.x;
+            }
+        }
+        let centroid = (qy / area, qx / area);
+        for p in points.iter_mut() {
+            *p = (p.0 - centroid.0, p.1 - centroid.1);
+        }
+        let (mut ixx, mut iyy, mut ixy) = (0., 0., 0.);
+        for element in &elements {
+            for s in samples(&points, element) {
+                ixx += s.weight * s.y * s.y;
+                iyy += s.weight * s.x * s.x;
+                ixy += s.weight * s.x * s.y;
+            }
+        }
+
+        // the warping and shear functions carry an arbitrary constant, removed by fixing
+        // the first node
+        let count = points.len();
+        let mut stiffness = Sparse::new(count);
+        let mut loads = vec![vec![0.; count]; 3];
+        let nu = poisson;
+        for element in &elements {
+            for s in samples(&points, element) {
+                let (r, q) = (s.x * s.x - s.y * s.y, 2. * s.x * s.y);
+                let d = (ixx * r - ixy * q, ixy * r + ixx * q);
+                let h = (-ixy * r + iyy * q, -iyy * r - ixy * q);
+                for i in 0..6 {
+                    let a = element[i];
+                    for (j, &b) in element.iter().enumerate() {
+                        let value = s.dx[i] * s.dx[j] + s.dy[i] * s.dy[j];
+                        stiffness.add(a, b, s.weight * value);
+                    }
+                    loads[0][a] += s.weight * (s.dx[i] * s.y - s.dy[i] * s.x);
+                    loads[1][a] += s.weight
+                        * (nu / 2. * (s.dx[i] * d.0 + s.dy[i] * d.1)
+                            + 2. * (1. + nu) * s.n[i] * (ixx * s.x - ixy * s.y));
+                    loads[2][a] += s.weight
+                        * (nu / 2. * (s.dx[i] * h.0 + s.dy[i] * h.1)
+                            + 2. * (1. + nu) * s.n[i] * (iyy * s.y - ixy * s.x));
+                }
+            }
+        }
+        for row in stiffness.rows.iter_mut().skip(1) {
+            row.retain(|&(c, _)| c != 0);
+        }
+        stiffness.rows[0] = vec![(0, 1.)];
+        for load in loads.iter_mut() {
+            load[0] = 0.;
+        }
+        let solutions: Vec<Vec<f64>> = loads.iter().map(|load| stiffness.solve(load)).collect();
+        let (omega, psi, phi) = (&solutions[0], &solutions[1], &solutions[2]);
+
+        let value = |f: &[f64], element: &[usize; 6], n: &[f64; 6]| {
+            (0..6).map(|i| n[i] * f[element[i]]).sum::<f64>()
+        };
+        let gradient = |f: &[f64], element: &[usize; 6], s: &Sample| {
+            (0..6).fold((0., 0.), |g, i| {
+                (g.0 + s.dx[i] * f[element[i]], g.1 + s.dy[i] * f[element[i]])
+            })
+        };
+
+        // the torsion constant is the polar inertia less the strain energy of the warping
+        let (mut energy, mut ixw, mut iyw) = (0., 0., 0.);
+        for element in &elements {
+            for s in samples(&points, element) {
+                let g = gradient(omega, element, &s);
+                energy += s.weight * (g.0 * g.0 + g.1 * g.1);
+                let w = value(omega, element, &s.n);
+                ixw += s.weight * s.x * w;
+                iyw += s.weight * s.y * w;
+            }
+        }
+        let torsion = ixx + iyy - energy;
+
+        // the shear centre is the pole about which the warping is orthogonal to x and y
+        let det = ixx * iyy - ixy * ixy;
+        let pole = ((ixy * ixw - iyy * iyw) / det, (ixx * ixw - ixy * iyw) / det);
+        let (mut sum, mut square) = (0., 0.);
+        for element in &elements {
+            for s in samples(&points, element) {
+                let w = value(omega, element, &s.n) - pole.1 * s.x + pole.0 * s.y;
+                sum += s.weight * w;
+                square += s.weight * w * w;
+            }
+        }
+        let warping = square - sum * sum / area;
+
+        let (mut kx, mut ky) = (0., 0.);
+        for element in &elements {
+            for s in samples(&points, element) {
+                let (r, q) = (s.x * s.x - s.y * s.y, 2. * s.x * s.y);
+                let d = (ixx * r - ixy * q, ixy * r + ixx * q);
+                let h = (-ixy * r + iyy * q, -iyy * r - ixy * q);
+                let gp = gradient(psi, element, &s);
+                let gf = gradient(phi, element, &s);
+                kx += s.weight * ((gp.0 - nu / 2. * d.0).powi(2) + (gp.1 - nu / 2. * d.1).powi(2));
+                ky += s.weight * ((gf.0 - nu / 2. * h.0).powi(2) + (gf.1 - nu / 2. * h.1).powi(2));
+            }
+        }
+        let delta = 2. * (1. + nu) * det;
+
+        Warping {
+            area,
+            centroid,
+            ixx,
+            iyy,
+            ixy,
+            torsion,
+            warping,
+            shear_centre: (centroid.0 + pole.0, centroid.1 + pole.1),
+            shear_area: (delta * delta / kx, delta * delta / ky),
+            nodes: count,
+            elements: elements.len(),
+        }
+    }
+}
+
+/// This function adds a node at the middle of every edge of a mesh, numbering the nodes of
+/// each element as the corners then the middles of the edges following each corner
+fn quadratic(mesh: &Mesh) -> (Vec<(f64, f64)>, Vec<[usize; 6]>) {
+    let mut points = mesh.points.clone();
+    let mut middles = HashMap::new();
+    let elements = mesh
+        .triangles
+        .iter()
+        .map(|&[a, b, c]| {
+            let mut middle = |a: usize, b: usize| {
+                *middles.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                    let (p, q) = (points[a], points[b]);
+                    points.push(((p.0 + q.0) / 2., (p.1 + q.1) / 2.));
+                    points.len() - 1
+                })
+            };
+            [a, b, c, middle(a, b), middle(b, c), middle(c, a)]
+        })
+        .collect();
+    (points, elements)
+}
+
+/// The quadrature points of a six node triangle
+fn samples(points: &[(f64, f64)], element: &[usize; 6]) -> Vec<Sample> {
+    GAUSS
+        .iter()
+        .map(|&(z1, z2, z3, weight)| {
+            let n = [
+                z1 * (2. * z1 - 1.),
+                z2 * (2. * z2 - 1.),
+                z3 * (2. * z3 - 1.),
+                4. * z1 * z2,
+                4. * z2 * z3,
+                4. * z3 * z1,
+            ];
+            // derivatives with respect to z2 and z3, taking z1 = 1 - z2 - z3
+            let ds = [
+                1. - 4. * z1,
+                4. * z2 - 1.,
+                0.,
+                4. * (z1 - z2),
+                4. * z3,
+                -4. * z3,
+            ];
+            let dt = [
+                1. - 4. * z1,
+                0.,
+                4. * z3 - 1.,
+                -4. * z2,
+                4. * z2,
+                4. * (z1 - z3),
+            ];
+            let (mut x, mut y) = (0., 0.);
+            let mut jacobian = [[0.; 2]; 2];
+            for i in 0..6 {
+                let p = points[element[i]];
+                x += n[i] * p.0;
+                y += n[i] * p.1;
+                jacobian[0][0] += ds[i] * p.0;
+                jacobian[0][1] += ds[i] * p.1;
+                jacobian[1][0] += dt[i] * p.0;
+                jacobian[1][1] += dt[i] * p.1;
+            }
+            let det = jacobian[0][0] * jacobian[1][1] - jacobian[0][1] * jacobian[1][0];
+            let mut dx = [0.; 6];
+            let mut dy = [0.; 6];
+            for i in 0..6 {
+                dx[i] = (jacobian[1][1] * ds[i] - jacobian[0][1] * dt[i]) / det;
+                dy[i] = (-jacobian[1][0] * ds[i] + jacobian[0][0] * dt[i]) / det;
+            }
+            Sample {
+                weight: weight * det / 2.,
+                x,
+                y,
+                n,
+                dx,
+                dy,
+            }
+        })
+        .collect()
+}
+
+impl Section {
+    /// This function finds the torsion and shear properties of the built outline of the
+    /// section, meshed with triangles of about the given size
+    pub fn warping(&self, size: f64) -> Warping {
+        Warping::solve(&self.build(), size, self.poisson())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo::{LineString, Polygon};
+    use std::f64::consts::PI;
+
+    fn outline(points: &[(f64, f64)]) -> MultiPolygon<f64> {
+        MultiPolygon(vec![Polygon::new(
+            LineString::from(points.to_vec()),
+            vec![],
+        )])
+    }
+
+    fn close(value: f64, expected: f64, tolerance: f64) -> bool {
+        (value - expected).abs() <= tolerance * expected.abs()
+    }
+
+    #[test]
+    fn rectangle() {
+        let (a, b) = (2., 1.);
+        let warping = Warping::solve(&outline(&[(0., 0.), (a, 0.), (a, b), (0., b)]), 0.1, 0.);
+        // Saint-Venant's series for the torsion constant
+        let sum: f64 = (0..50)
+            .map(|k| {
+                let n = (2 * k + 1) as f64;
+                (n * PI * a / (2. * b)).tanh() / n.powi(5)
+            })
+            .sum();
+        let torsion = a * b.powi(3) / 3. * (1. - 192. / PI.powi(5) * b / a * sum);
+        assert!(close(warping.torsion, torsion, 1e-3));
+        assert!(close(warping.area, a * b, 1e-12));
+        assert!((warping.shear_centre.0 - a / 2.).abs() < 1e-4);
+        assert!((warping.shear_centre.1 - b / 2.).abs() < 1e-4);
+        // without Poisson's effect the shear areas are five sixths of the area
+        assert!(close(warping.shear_area.0, 5. / 6. * a * b, 1e-3));
+        assert!(close(warping.shear_area.1, 5. / 6. * a * b, 1e-3));
+    }
+
+    #[test]
+    fn thin_rectangle() {
+        let (a, b) = (20., 1.);
+        let warping = Warping::solve(&outline(&[(0., 0.), (a, 0.), (a, b), (0., b)]), 0.25, 0.);
+        assert!(close(warping.warping, a.powi(3) * b.powi(3) / 144., 0.02));
+    }
+
+    #[test]
+    fn i_section() {
+        // flanges 20 by 1 and a web 18 by 1, 20 deep overall
+        let (b, t, d) = (20., 1., 20.);
+        let (x, y) = (b / 2., d / 2.);
+        let warping = Warping::solve(
+            &outline(&[
+                (-x, -y),
+                (x, -y),
+                (x, t - y),
+                (t / 2., t - y),
+                (t / 2., y - t),
+                (x, y - t),
+                (x, y),
+                (-x, y),
+                (-x, y - t),
+                (-t / 2., y - t),
+                (-t / 2., t - y),
+                (-x, t - y),
+            ]),
+            0.5,
+            0.3,
+        );
+        let torsion = (2. * b + d - 2. * t) * t.powi(3) / 3.;
+        assert!(close(warping.torsion, torsion, 0.02));
+        let flanges = 2. * t * b.powi(3) / 12.;
+        assert!(close(warping.warping, flanges * (d - t).powi(2) / 4., 0.01));
+        assert!(warping.shear_centre.0.abs() < 1e-3 * d);
+        assert!(warping.shear_centre.1.abs() < 1e-3 * d);
+    }
+}